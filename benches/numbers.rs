@@ -0,0 +1,56 @@
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use jsonvfy::tokenizer::read_next_token;
+use jsonvfy::verifier::verify_detailed;
+
+
+fn integer_array(count: usize) -> Vec<u8> {
+    let mut json = b"[".to_vec();
+    for i in 0..count {
+        if i > 0 {
+            json.push(b',');
+        }
+        json.extend_from_slice((i % 1000).to_string().as_bytes());
+    }
+    json.push(b']');
+    json
+}
+
+
+fn float_array(count: usize) -> Vec<u8> {
+    let mut json = b"[".to_vec();
+    for i in 0..count {
+        if i > 0 {
+            json.extend_from_slice(b", ");
+        }
+        json.extend_from_slice(format!("-{}.{}e+{}", i, i * 7919 % 100000, i % 300).as_bytes());
+    }
+    json.push(b']');
+    json
+}
+
+
+fn bench_numbers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("numbers");
+    for (name, json) in [("integers", integer_array(1_000_000)), ("floats", float_array(200_000))] {
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_function(format!("tokenize {}", name), |b| b.iter(|| {
+            let mut cursor = Cursor::new(&json);
+            let mut count = 0usize;
+            while read_next_token(&mut cursor).unwrap().is_some() {
+                count += 1;
+            }
+            count
+        }));
+        group.bench_function(format!("verify {}", name), |b| b.iter(|| {
+            verify_detailed(Cursor::new(&json)).unwrap()
+        }));
+    }
+    group.finish();
+}
+
+
+criterion_group!(benches, bench_numbers);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use std::io::{BufReader, Cursor};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use jsonvfy::tokenizer::read_next_token;
+
+
+fn huge_string(length: usize) -> Vec<u8> {
+    let mut json = b"\"".to_vec();
+    let text = b"The quick brown fox jumps over the lazy dog. \\u00e9\\n";
+    while json.len() < length {
+        json.extend_from_slice(text);
+    }
+    json.push(b'"');
+    json
+}
+
+
+fn bench_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("strings");
+    let json = huge_string(8 * 1024 * 1024);
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("tokenize huge string", |b| b.iter(|| {
+        let mut reader = BufReader::new(Cursor::new(&json));
+        read_next_token(&mut reader).unwrap()
+    }));
+    group.finish();
+}
+
+
+criterion_group!(benches, bench_strings);
+criterion_main!(benches);
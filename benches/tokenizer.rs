@@ -0,0 +1,143 @@
+use std::io::{BufReader, Cursor};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use jsonvfy::tokenizer::{
+    interpret_string, read_next_token, read_next_token_into, JsonChar, JsonToken, TokenBuffer, TokenizerOptions,
+};
+
+
+fn deeply_nested(depth: usize) -> Vec<u8> {
+    let mut json = Vec::new();
+    for i in 0..depth {
+        if i % 2 == 0 {
+            json.extend_from_slice(b"{\"k\":");
+        } else {
+            json.push(b'[');
+        }
+    }
+    json.extend_from_slice(b"null");
+    for i in (0..depth).rev() {
+        if i % 2 == 0 {
+            json.push(b'}');
+        } else {
+            json.push(b']');
+        }
+    }
+    json
+}
+
+
+fn string_heavy(count: usize) -> Vec<u8> {
+    let mut json = b"{".to_vec();
+    for i in 0..count {
+        if i > 0 {
+            json.push(b',');
+        }
+        json.extend_from_slice(format!("\"key{}\":\"value number {} with \\\"escapes\\\" and \\u00e9\"", i, i).as_bytes());
+    }
+    json.push(b'}');
+    json
+}
+
+
+fn number_heavy(count: usize) -> Vec<u8> {
+    let mut json = b"[".to_vec();
+    for i in 0..count {
+        if i > 0 {
+            json.push(b',');
+        }
+        json.extend_from_slice(format!("{},-{}.{},{}e-{}", i, i, i % 97, i * 31, i % 20).as_bytes());
+    }
+    json.push(b']');
+    json
+}
+
+
+fn whitespace_heavy(count: usize) -> Vec<u8> {
+    let mut json = b"[".to_vec();
+    for i in 0..count {
+        if i > 0 {
+            json.extend_from_slice(b"  ,\r\n\t\t\t\t");
+        }
+        json.extend_from_slice(b"\n        \n        true        \n\n");
+    }
+    json.push(b']');
+    json
+}
+
+
+fn string_chars(text: &str) -> Vec<JsonChar> {
+    let json = format!("\"{}\"", text);
+    match read_next_token(Cursor::new(json.as_bytes())).unwrap() {
+        Some(JsonToken::String(chars)) => chars,
+        other => panic!("fixture is not a string but {:?}", other),
+    }
+}
+
+
+fn bench_read_next_token(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_next_token");
+    let workloads = [
+        ("deeply nested", deeply_nested(100_000)),
+        ("string-heavy", string_heavy(100_000)),
+        ("number-heavy", number_heavy(100_000)),
+        ("whitespace-heavy", whitespace_heavy(100_000)),
+    ];
+    for (name, json) in workloads {
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_function(name, |b| b.iter(|| {
+            let mut reader = BufReader::new(Cursor::new(&json));
+            let mut count = 0usize;
+            while read_next_token(&mut reader).unwrap().is_some() {
+                count += 1;
+            }
+            count
+        }));
+    }
+    group.finish();
+}
+
+
+fn bench_read_next_token_into(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_next_token_into");
+    let options = TokenizerOptions::default();
+    let workloads = [
+        ("string-heavy", string_heavy(100_000)),
+        ("number-heavy", number_heavy(100_000)),
+    ];
+    for (name, json) in workloads {
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_function(name, |b| b.iter(|| {
+            let mut reader = BufReader::new(Cursor::new(&json));
+            let mut buffer = TokenBuffer::new();
+            let mut count = 0usize;
+            while read_next_token_into(&mut reader, &options, &mut buffer).unwrap().is_some() {
+                count += 1;
+            }
+            count
+        }));
+    }
+    group.finish();
+}
+
+
+fn bench_interpret_string(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpret_string");
+    let workloads = [
+        ("ASCII", string_chars(&"The quick brown fox jumps over the lazy dog. ".repeat(20_000))),
+        ("multi-byte", string_chars(&"Příliš žluťoučký kůň úpěl ďábelské ódy. 日本語のテキスト。 ".repeat(10_000))),
+        ("escaped", string_chars(&"\\u0050\\u0159\\u00ed\\n\\t\\\"\\ud83d\\ude00 ".repeat(20_000))),
+    ];
+    for (name, chars) in workloads {
+        group.throughput(Throughput::Elements(chars.len() as u64));
+        group.bench_function(name, |b| b.iter(|| {
+            interpret_string(&chars).unwrap()
+        }));
+    }
+    group.finish();
+}
+
+
+criterion_group!(benches, bench_read_next_token, bench_read_next_token_into, bench_interpret_string);
+criterion_main!(benches);
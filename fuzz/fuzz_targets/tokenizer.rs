@@ -0,0 +1,15 @@
+#![no_main]
+
+use jsonvfy::tokenizer::{interpret_string, JsonToken, read_next_token};
+use libfuzzer_sys::fuzz_target;
+
+
+fuzz_target!(|data: &[u8]| {
+    // neither tokenizing nor interpreting strings may panic, whatever the input
+    let mut cursor = std::io::Cursor::new(data);
+    while let Ok(Some(token)) = read_next_token(&mut cursor) {
+        if let JsonToken::String(s) = token {
+            let _ = interpret_string(&s);
+        }
+    }
+});
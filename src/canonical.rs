@@ -0,0 +1,356 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use crate::number::canonical_number;
+use crate::position::Position;
+use crate::tokenizer::{interpret_string, JsonToken};
+use crate::verifier::{DuplicateKeyComparison, DuplicateKeyPolicy, Verifier, VerifyError};
+use crate::visit::{visit_values, ValueVisitor};
+
+
+#[derive(Debug)]
+pub enum CanonicalizeError {
+    Verify(VerifyError),
+    UnrepresentableNumber(Position, Vec<u8>),
+    Write(std::io::Error),
+}
+impl CanonicalizeError {
+    /// The position in the document at which the error was detected, if it was detected in the
+    /// document.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Self::Verify(e) => Some(e.position()),
+            Self::UnrepresentableNumber(p, _) => Some(*p),
+            Self::Write(_) => None,
+        }
+    }
+}
+impl fmt::Display for CanonicalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verify(e) => write!(f, "{}", e),
+            Self::UnrepresentableNumber(_, number) => write!(f, "number {} cannot be represented as a double", String::from_utf8_lossy(number)),
+            Self::Write(e) => write!(f, "failed to write canonical form: {}", e),
+        }
+    }
+}
+impl std::error::Error for CanonicalizeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Verify(e) => Some(e),
+            Self::UnrepresentableNumber(_, _) => None,
+            Self::Write(e) => Some(e),
+        }
+    }
+}
+impl From<VerifyError> for CanonicalizeError {
+    fn from(value: VerifyError) -> Self { Self::Verify(value) }
+}
+
+
+/// Appends the string in canonical form (with only the necessary characters escaped).
+fn push_canonical_string(out: &mut Vec<u8>, s: &str) {
+    out.push(b'"');
+    for c in s.chars() {
+        match c {
+            '"' => out.extend_from_slice(b"\\\""),
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\u{08}' => out.extend_from_slice(b"\\b"),
+            '\u{0C}' => out.extend_from_slice(b"\\f"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\t' => out.extend_from_slice(b"\\t"),
+            c if c < ' ' => out.extend_from_slice(format!("\\u{:04x}", u32::from(c)).as_bytes()),
+            c => {
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            },
+        }
+    }
+    out.push(b'"');
+}
+
+
+/// Produces the canonical form of each value.
+///
+/// Since the members of an object have to be sorted by key, the canonical form of each member
+/// value has to be kept until the object is complete. Arrays are buffered the same way so that
+/// the canonical form of a value can always be appended to its container.
+struct Canonicalizer;
+impl ValueVisitor for Canonicalizer {
+    type Value = Vec<u8>;
+    type Array = Vec<Vec<u8>>;
+    type Object = Vec<(String, Vec<u8>)>;
+    type Error = CanonicalizeError;
+
+    fn begin_array(&mut self) -> Self::Array {
+        Vec::new()
+    }
+
+    fn begin_object(&mut self) -> Self::Object {
+        Vec::new()
+    }
+
+    fn array_element(&mut self, elements: &mut Self::Array, element: Vec<u8>) {
+        elements.push(element);
+    }
+
+    fn object_member(&mut self, members: &mut Self::Object, key: String, value: Vec<u8>) {
+        members.push((key, value));
+    }
+
+    fn end_array(&mut self, elements: Self::Array) -> Vec<u8> {
+        let mut value = vec![b'['];
+        for (i, element) in elements.iter().enumerate() {
+            if i > 0 {
+                value.push(b',');
+            }
+            value.extend_from_slice(element);
+        }
+        value.push(b']');
+        value
+    }
+
+    fn end_object(&mut self, mut members: Self::Object) -> Vec<u8> {
+        // RFC 8785 sorts by UTF-16 code units, not by code points
+        members.sort_by(|(k1, _), (k2, _)| k1.encode_utf16().cmp(k2.encode_utf16()));
+        let mut value = vec![b'{'];
+        for (i, (key, member_value)) in members.iter().enumerate() {
+            if i > 0 {
+                value.push(b',');
+            }
+            push_canonical_string(&mut value, key);
+            value.push(b':');
+            value.extend_from_slice(member_value);
+        }
+        value.push(b'}');
+        value
+    }
+
+    fn scalar(&mut self, tok: JsonToken, position: Position) -> Result<Vec<u8>, CanonicalizeError> {
+        match tok {
+            JsonToken::String(chars) => {
+                let processed_string = interpret_string(&chars)
+                    .map_err(|e| VerifyError::from_string(position, &chars, e))?;
+                let mut value = Vec::new();
+                push_canonical_string(&mut value, &processed_string);
+                Ok(value)
+            },
+            JsonToken::Number(number) => match canonical_number(&number) {
+                Some(n) => Ok(n.into_bytes()),
+                None => Err(CanonicalizeError::UnrepresentableNumber(position, number)),
+            },
+            JsonToken::True => Ok(b"true".to_vec()),
+            JsonToken::False => Ok(b"false".to_vec()),
+            _ => Ok(b"null".to_vec()),
+        }
+    }
+}
+
+
+/// Reads a JSON document and writes it in the form defined by the JSON Canonicalization Scheme
+/// (RFC 8785).
+///
+/// The document is verified strictly; see `canonicalize_with` for what is rejected additionally.
+/// If the input is empty, nothing is written.
+pub fn canonicalize<R: BufRead, W: Write>(json_reader: R, writer: W) -> Result<(), CanonicalizeError> {
+    canonicalize_with(&Verifier::new(), json_reader, writer)
+}
+
+
+/// Reads a JSON document, verifying it following the options of the given verifier, and writes it
+/// in the form defined by the JSON Canonicalization Scheme (RFC 8785).
+///
+/// Additionally, duplicate keys are always rejected (comparing the decoded keys) and so are
+/// numbers that cannot be read as double-precision floating-point values, such as hexadecimal
+/// numbers or numbers with a decimal comma. If the input is empty, nothing is written.
+///
+/// Each object (and array) is kept in memory in canonical form until it is complete, since its
+/// members have to be sorted by key.
+pub fn canonicalize_with<R: BufRead, W: Write>(verifier: &Verifier, json_reader: R, mut writer: W) -> Result<(), CanonicalizeError> {
+    // an object cannot be sorted by key if two of its keys are the same
+    let verifier = verifier.clone()
+        .duplicate_key_policy(DuplicateKeyPolicy::Reject)
+        .duplicate_key_comparison(DuplicateKeyComparison::Decoded);
+    if let Some(root) = visit_values(&verifier, json_reader, &mut Canonicalizer)? {
+        writer.write_all(&root)
+            .map_err(CanonicalizeError::Write)?;
+    }
+    Ok(())
+}
+
+
+/// Verifies a JSON document and returns its canonical form (RFC 8785).
+///
+/// Documents that only differ in formatting, order of keys or notation of numbers and strings have
+/// the same canonical form; see `canonicalize` for what is rejected. An empty input yields an empty
+/// string.
+pub fn validate_and_normalize<R: BufRead>(json_reader: R) -> Result<String, CanonicalizeError> {
+    let mut canonical_form = Vec::new();
+    canonicalize(json_reader, &mut canonical_form)?;
+
+    // strings are decoded and re-encoded, so everything is valid UTF-8
+    Ok(String::from_utf8(canonical_form).expect("canonical form is not valid UTF-8"))
+}
+
+
+/// Returns the SHA-256 hash of the canonical form (RFC 8785) of a JSON document.
+///
+/// Documents that only differ in formatting, order of keys or notation of numbers and strings have
+/// the same hash. The canonical form is hashed as it is produced and never kept in its entirety.
+#[cfg(feature = "sha2")]
+pub fn canonical_hash<R: BufRead>(json_reader: R) -> Result<[u8; 32], CanonicalizeError> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    canonicalize(json_reader, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{canonicalize, canonicalize_with, validate_and_normalize, CanonicalizeError};
+    use crate::verifier::{DuplicateKeyComparison, DuplicateKeyPolicy, Verifier, VerifyError};
+
+    fn canonical(json: &str) -> Result<String, CanonicalizeError> {
+        let mut out = Vec::new();
+        canonicalize(json.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_rfc8785_example() {
+        let json = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonical(json).unwrap(),
+            "{\"literals\":[null,true,false],\"numbers\":[333333333.3333333,1e+30,4.5,0.002,1e-27],\"string\":\"\u{20AC}$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}",
+        );
+    }
+
+    #[test]
+    fn test_sorting() {
+        // U+1F600 is D83D DE00 in UTF-16 and therefore sorts before U+E000
+        let json = "{\"\u{E000}\": 1, \"\u{1F600}\": 2, \"b\": {\"z\": [], \"a\": {}}, \"a\": 3}";
+        assert_eq!(
+            canonical(json).unwrap(),
+            "{\"a\":3,\"b\":{\"a\":{},\"z\":[]},\"\u{1F600}\":2,\"\u{E000}\":1}",
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(canonical("").unwrap(), "");
+        assert_eq!(canonical(" \"a\" ").unwrap(), "\"a\"");
+        match canonical("{\"a\": 1, \"a\": 2}") {
+            Err(CanonicalizeError::Verify(VerifyError::DuplicateKey(_, key, _))) => assert_eq!(key, "a"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match canonical("[1e400]") {
+            Err(CanonicalizeError::UnrepresentableNumber(p, _)) => assert_eq!(p.offset, 1),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match canonical("[1, 2") {
+            Err(CanonicalizeError::Verify(VerifyError::UnclosedDocument(_, 1))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match canonical("[1] 2") {
+            Err(CanonicalizeError::Verify(VerifyError::TrailingGarbage(_, _))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match canonical("[1,]") {
+            Err(CanonicalizeError::Verify(VerifyError::UnexpectedToken(_, _, _))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_with() {
+        fn canonical_with(verifier: &Verifier, json: &str) -> Result<String, CanonicalizeError> {
+            let mut out = Vec::new();
+            canonicalize_with(verifier, json.as_bytes(), &mut out)?;
+            Ok(String::from_utf8(out).unwrap())
+        }
+
+        let lenient = Verifier::new()
+            .allow_comments(true)
+            .allow_single_quotes(true)
+            .allow_unquoted_keys(true);
+        assert_eq!(
+            canonical_with(&lenient, "{b: 'x\"y', /* first */ a: [true, 1.0]}").unwrap(),
+            "{\"a\":[true,1],\"b\":\"x\\\"y\"}",
+        );
+        match canonical("{a: 1}") {
+            Err(CanonicalizeError::Verify(VerifyError::Tokenizer(_, _))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let shallow = Verifier::new().max_depth(1);
+        assert_eq!(canonical_with(&shallow, "[1]").unwrap(), "[1]");
+        match canonical_with(&shallow, "[[1]]") {
+            Err(CanonicalizeError::Verify(VerifyError::MaxDepthExceeded(_, 1))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // duplicate keys cannot be sorted, whatever the verifier allows
+        let duplicates = Verifier::new()
+            .duplicate_key_policy(DuplicateKeyPolicy::Allow)
+            .duplicate_key_comparison(DuplicateKeyComparison::RawBytes);
+        match canonical_with(&duplicates, r#"{"a": 1, "\u0061": 2}"#) {
+            Err(CanonicalizeError::Verify(VerifyError::DuplicateKey(_, key, _))) => assert_eq!(key, "a"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let decimal_comma = Verifier::new().decimal_comma(true);
+        match canonical_with(&decimal_comma, r#"{"pi": 3,14}"#) {
+            Err(CanonicalizeError::UnrepresentableNumber(p, number)) => {
+                assert_eq!(p.offset, 7);
+                assert_eq!(number, b"3,14");
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_canonical_hash() {
+        use super::canonical_hash;
+
+        let hash1 = canonical_hash(r#"{"b": [1.0, "\u0041"], "a": null}"#.as_bytes()).unwrap();
+        let hash2 = canonical_hash("{\n  \"a\" : null,\n  \"b\" : [ 1, \"A\" ]\n}\n".as_bytes()).unwrap();
+        assert_eq!(hash1, hash2);
+
+        let hash3 = canonical_hash(r#"{"a": null, "b": [1, "B"]}"#.as_bytes()).unwrap();
+        assert_ne!(hash1, hash3);
+
+        // SHA-256 of the canonical form {"a":null,"b":[1,"A"]}
+        assert_eq!(
+            hash1,
+            [
+                0x35, 0x0b, 0x19, 0x8d, 0x4a, 0x30, 0x3f, 0xed, 0xbb, 0xaa, 0x30, 0x07, 0x2c, 0x3b, 0x6d, 0x0c,
+                0xf6, 0xa4, 0xd2, 0x46, 0xe4, 0xa4, 0xc1, 0xfe, 0xd4, 0xa3, 0x25, 0xf3, 0x46, 0x72, 0xe4, 0xf9,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_validate_and_normalize() {
+        let equivalent = [
+            r#"{"b": [1.0, "\u00e9"], "a": null}"#,
+            "{\"a\":null,\"b\":[1,\"é\"]}",
+            r#"  { "a" : null , "b" : [ 10e-1 , "\u00E9" ] }  "#,
+        ];
+        for json in equivalent {
+            assert_eq!(validate_and_normalize(json.as_bytes()).unwrap(), "{\"a\":null,\"b\":[1,\"é\"]}");
+        }
+
+        assert_eq!(validate_and_normalize("".as_bytes()).unwrap(), "");
+        assert!(matches!(validate_and_normalize("[1,]".as_bytes()), Err(CanonicalizeError::Verify(_))));
+        assert!(matches!(validate_and_normalize("[1] 2".as_bytes()), Err(CanonicalizeError::Verify(_))));
+        assert!(matches!(validate_and_normalize("[1e400]".as_bytes()), Err(CanonicalizeError::UnrepresentableNumber(_, _))));
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::io::BufRead;
+
+use crate::position::{Position, PositionReader};
+use crate::tokenizer::{read_next_spanned_token, Error, SpannedToken, TokenizerOptions};
+
+
+/// The first point at which the token streams of two documents diverge.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TokenDivergence {
+    /// Both documents contain a token at this point, but the tokens differ.
+    Different(SpannedToken, SpannedToken),
+    /// The first document ends (at the given position) while the second continues.
+    FirstEnded(Position, SpannedToken),
+    /// The second document ends (at the given position) while the first continues.
+    SecondEnded(SpannedToken, Position),
+}
+
+
+#[derive(Debug)]
+pub enum TokenDiffError {
+    First(Position, Error),
+    Second(Position, Error),
+}
+impl TokenDiffError {
+    /// The position at which tokenization failed, in whichever document it failed.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::First(p, _) => *p,
+            Self::Second(p, _) => *p,
+        }
+    }
+}
+impl fmt::Display for TokenDiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::First(p, e) => write!(f, "first document at {}: {}", p, e),
+            Self::Second(p, e) => write!(f, "second document at {}: {}", p, e),
+        }
+    }
+}
+impl std::error::Error for TokenDiffError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::First(_, e) => Some(e),
+            Self::Second(_, e) => Some(e),
+        }
+    }
+}
+
+
+/// Tokenizes both documents and returns the first point at which their token streams diverge, or
+/// `None` if they consist of the same tokens.
+///
+/// Whitespace between tokens is ignored, so documents that only differ in formatting are equal.
+/// Tokens are compared as written: a string spelled with different escapes or a number spelled
+/// differently (e.g. `1.0` and `1`) counts as a divergence. The documents are only tokenized, not
+/// verified.
+pub fn token_diff<R1: BufRead, R2: BufRead>(first: R1, second: R2) -> Result<Option<TokenDivergence>, TokenDiffError> {
+    let options = TokenizerOptions::default();
+    let mut first_reader = PositionReader::new(first);
+    let mut second_reader = PositionReader::new(second);
+    loop {
+        let first_token = read_next_spanned_token(&mut first_reader, &options)
+            .map_err(|e| TokenDiffError::First(first_reader.position(), e))?;
+        let second_token = read_next_spanned_token(&mut second_reader, &options)
+            .map_err(|e| TokenDiffError::Second(second_reader.position(), e))?;
+        match (first_token, second_token) {
+            (None, None) => return Ok(None),
+            (None, Some(s)) => return Ok(Some(TokenDivergence::FirstEnded(first_reader.position(), s))),
+            (Some(f), None) => return Ok(Some(TokenDivergence::SecondEnded(f, second_reader.position()))),
+            (Some(f), Some(s)) => {
+                if f.token != s.token {
+                    return Ok(Some(TokenDivergence::Different(f, s)));
+                }
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{token_diff, TokenDiffError, TokenDivergence};
+    use crate::tokenizer::JsonToken;
+
+    #[test]
+    fn test_token_diff() {
+        assert_eq!(token_diff("".as_bytes(), "".as_bytes()).unwrap(), None);
+        assert_eq!(
+            token_diff(r#"{"a": [1, 2], "b": null}"#.as_bytes(), "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": null\n}\n".as_bytes()).unwrap(),
+            None,
+        );
+
+        match token_diff(r#"{"a": 1, "b": 2}"#.as_bytes(), "{\n  \"a\": 1,\n  \"b\": 3\n}".as_bytes()).unwrap() {
+            Some(TokenDivergence::Different(first, second)) => {
+                assert_eq!(first.token, JsonToken::Number(b"2".to_vec()));
+                assert_eq!(first.span.byte_range(), 14..15);
+                assert_eq!(second.token, JsonToken::Number(b"3".to_vec()));
+                assert_eq!(second.span.start.line, 3);
+                assert_eq!(second.span.byte_range(), 19..20);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // tokens are compared as written
+        assert!(token_diff("[1.0]".as_bytes(), "[1]".as_bytes()).unwrap().is_some());
+        assert!(token_diff(r#"["A"]"#.as_bytes(), r#"["\u0041"]"#.as_bytes()).unwrap().is_some());
+
+        match token_diff("[1]".as_bytes(), "[1, 2]".as_bytes()).unwrap() {
+            Some(TokenDivergence::Different(first, second)) => {
+                assert_eq!(first.token, JsonToken::ClosingBracket);
+                assert_eq!(second.token, JsonToken::Comma);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+        match token_diff("[1] ".as_bytes(), "[1] 2".as_bytes()).unwrap() {
+            Some(TokenDivergence::FirstEnded(position, second)) => {
+                assert_eq!(position.offset, 4);
+                assert_eq!(second.token, JsonToken::Number(b"2".to_vec()));
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+        match token_diff("true false".as_bytes(), "true".as_bytes()).unwrap() {
+            Some(TokenDivergence::SecondEnded(first, position)) => {
+                assert_eq!(first.token, JsonToken::False);
+                assert_eq!(position.offset, 4);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        match token_diff("[1, 2]".as_bytes(), "[1, \"2]".as_bytes()) {
+            Err(TokenDiffError::Second(_, _)) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
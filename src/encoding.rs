@@ -0,0 +1,54 @@
+use std::io::{BufReader, Read};
+
+use encoding_rs::Encoding;
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+
+
+/// A reader that transcodes a document from another encoding to UTF-8.
+pub type TranscodingReader<R> = BufReader<DecodeReaderBytes<R, Vec<u8>>>;
+
+
+/// Wraps a reader of a document in the encoding with the given label (e.g. `windows-1252`) into a
+/// reader of the same document in UTF-8, which can then be passed to the tokenizer or verifier.
+///
+/// The labels are those defined by the WHATWG Encoding Standard; notably, `latin1` and
+/// `iso-8859-1` denote Windows-1252 as well. A byte order mark overrides the given encoding.
+///
+/// Returns `None` if the label does not denote a known encoding.
+pub fn transcoding_reader<R: Read>(reader: R, label: &str) -> Option<TranscodingReader<R>> {
+    let encoding = Encoding::for_label(label.trim().as_bytes())?;
+    let decoder = DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .build(reader);
+    Some(BufReader::new(decoder))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use super::transcoding_reader;
+    use crate::verifier::Verifier;
+
+    #[test]
+    fn test_latin1() {
+        let latin1: &[u8] = b"{\"k\":\"\xE9\"}";
+        assert_eq!(Verifier::new().require_utf8(true).verify(latin1).is_err(), true);
+
+        let reader = transcoding_reader(latin1, "latin1").unwrap();
+        assert_eq!(Verifier::new().require_utf8(true).verify(reader).is_ok(), true);
+
+        let mut transcoded = String::new();
+        transcoding_reader(latin1, "windows-1252").unwrap()
+            .read_to_string(&mut transcoded).unwrap();
+        assert_eq!(transcoded, "{\"k\":\"\u{E9}\"}");
+
+        // Windows-1252 differs from ISO 8859-1 between 0x80 and 0x9F
+        let mut transcoded = String::new();
+        transcoding_reader(&b"\"\x80\""[..], "iso-8859-1").unwrap()
+            .read_to_string(&mut transcoded).unwrap();
+        assert_eq!(transcoded, "\"\u{20AC}\"");
+
+        assert_eq!(transcoding_reader(latin1, "no-such-encoding").is_none(), true);
+    }
+}
@@ -0,0 +1,152 @@
+use std::io::BufRead;
+
+use crate::position::Position;
+use crate::tokenizer::JsonToken;
+use crate::verifier::{ValueKind, Verifier, VerifyError};
+use crate::visit::{visit_values, ValueVisitor};
+
+
+/// Returns the noun for the given kind of value, in plural if the count is not 1.
+fn kind_noun(kind: ValueKind, count: usize) -> &'static str {
+    let (singular, plural) = match kind {
+        ValueKind::Object => ("object", "objects"),
+        ValueKind::Array => ("array", "arrays"),
+        ValueKind::String => ("string", "strings"),
+        ValueKind::Number => ("number", "numbers"),
+        ValueKind::Boolean => ("boolean", "booleans"),
+        ValueKind::Null => ("null", "nulls"),
+    };
+    if count == 1 { singular } else { plural }
+}
+
+
+fn describe_array(kind_counts: &[(ValueKind, usize)]) -> String {
+    let total: usize = kind_counts.iter()
+        .map(|(_, count)| count)
+        .sum();
+    match kind_counts {
+        [] => "empty array".to_owned(),
+        [(kind, count)] => format!("array of {} {}", count, kind_noun(*kind, *count)),
+        _ => {
+            let counts: Vec<String> = kind_counts.iter()
+                .map(|(kind, count)| format!("{} {}", count, kind_noun(*kind, *count)))
+                .collect();
+            format!("array of {} values: {}", total, counts.join(", "))
+        },
+    }
+}
+
+
+fn describe_object(members: &[(String, String)]) -> String {
+    if members.len() == 0 {
+        return "empty object".to_owned();
+    }
+    let member_descriptions: Vec<String> = members.iter()
+        .map(|(key, description)| format!("{} ({})", key, description))
+        .collect();
+    format!(
+        "object with {} {}: {}",
+        members.len(),
+        if members.len() == 1 { "key" } else { "keys" },
+        member_descriptions.join(", "),
+    )
+}
+
+
+/// Describes each value by its kind and a description.
+struct Describer;
+impl ValueVisitor for Describer {
+    type Value = (ValueKind, String);
+    /// How many values of each kind the array contains, in order of first appearance.
+    type Array = Vec<(ValueKind, usize)>;
+    /// The key and the description of the value of each member.
+    type Object = Vec<(String, String)>;
+    type Error = VerifyError;
+
+    fn begin_array(&mut self) -> Self::Array {
+        Vec::new()
+    }
+
+    fn begin_object(&mut self) -> Self::Object {
+        Vec::new()
+    }
+
+    fn array_element(&mut self, kind_counts: &mut Self::Array, (kind, _description): Self::Value) {
+        match kind_counts.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, count)) => *count += 1,
+            None => kind_counts.push((kind, 1)),
+        }
+    }
+
+    fn object_member(&mut self, members: &mut Self::Object, key: String, (_kind, description): Self::Value) {
+        members.push((key, description));
+    }
+
+    fn end_array(&mut self, kind_counts: Self::Array) -> Self::Value {
+        (ValueKind::Array, describe_array(&kind_counts))
+    }
+
+    fn end_object(&mut self, members: Self::Object) -> Self::Value {
+        (ValueKind::Object, describe_object(&members))
+    }
+
+    fn scalar(&mut self, tok: JsonToken, _position: Position) -> Result<Self::Value, VerifyError> {
+        let kind = ValueKind::of(&tok)
+            .expect("scalar token without a value kind");
+        Ok((kind, kind_noun(kind, 1).to_owned()))
+    }
+}
+
+
+/// Verifies a JSON document and describes its structure in prose, e.g. `object with 2 keys: name
+/// (string), tags (array of 2 strings)`.
+///
+/// Objects are described member by member; arrays are summarized by how many values of each kind
+/// they contain. Verification follows the options of the given verifier and stops at the first
+/// error. An empty document is described as an empty string.
+pub fn explain<R: BufRead>(verifier: &Verifier, json_reader: R) -> Result<String, VerifyError> {
+    let root = visit_values(verifier, json_reader, &mut Describer)?;
+    Ok(root.map(|(_kind, description)| description).unwrap_or_default())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::explain;
+    use crate::verifier::Verifier;
+
+    fn explained(json: &str) -> Option<String> {
+        explain(&Verifier::new(), json.as_bytes()).ok()
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            explained(r#"{"name": "Ann", "age": 42, "tags": ["a", "b"]}"#).as_deref(),
+            Some("object with 3 keys: name (string), age (number), tags (array of 2 strings)"),
+        );
+        assert_eq!(
+            explained(r#"{"address": {"city": "Vienna"}, "x": [1, "2", 3, null, {}], "y": [], "z": {}}"#).as_deref(),
+            Some("object with 4 keys: address (object with 1 key: city (string)), x (array of 5 values: 2 numbers, 1 string, 1 null, 1 object), y (empty array), z (empty object)"),
+        );
+        assert_eq!(explained("[true]").as_deref(), Some("array of 1 boolean"));
+        assert_eq!(explained("[[1], [2]]").as_deref(), Some("array of 2 arrays"));
+        assert_eq!(explained("12").as_deref(), Some("number"));
+        assert_eq!(explained("").as_deref(), Some(""));
+        assert_eq!(
+            explain(&Verifier::new().decimal_comma(true), r#"[{"pi": 3,14}]"#.as_bytes()).unwrap(),
+            "array of 1 object",
+        );
+
+        // verification stops at the first error
+        assert_eq!(explained(r#"{"a": 1,}"#), None);
+        assert_eq!(explained(r#"{"a": 1} 2"#), None);
+        assert_eq!(explained(r#"{"a": 1, "a": 2}"#), None);
+
+        let verifier = Verifier::new().allow_unquoted_keys(true);
+        assert_eq!(
+            explain(&verifier, "{a: 1, null: 2}".as_bytes()).unwrap(),
+            "object with 2 keys: a (number), null (number)",
+        );
+    }
+}
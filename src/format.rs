@@ -0,0 +1,408 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::ops::ControlFlow;
+
+use crate::position::Position;
+use crate::tokenizer::{JsonChar, JsonToken};
+use crate::verifier::{Verifier, VerifyError};
+
+
+#[derive(Debug)]
+pub enum FormatError {
+    Verify(VerifyError),
+    Write(std::io::Error),
+}
+impl FormatError {
+    /// The position in the document at which the error was detected, if it was detected in the
+    /// document.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Self::Verify(e) => Some(e.position()),
+            Self::Write(_) => None,
+        }
+    }
+}
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verify(e) => write!(f, "{}", e),
+            Self::Write(e) => write!(f, "failed to write formatted document: {}", e),
+        }
+    }
+}
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Verify(e) => Some(e),
+            Self::Write(e) => Some(e),
+        }
+    }
+}
+impl From<VerifyError> for FormatError {
+    fn from(value: VerifyError) -> Self { Self::Verify(value) }
+}
+
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FormatOptions {
+    /// The number of spaces by which each nesting level is indented.
+    pub indent_width: usize,
+
+    /// The maximum nesting depth of containers that are output in full.
+    ///
+    /// Containers nested more deeply are output as `[...]` or `{...}` (or as `[]` or `{}` if they
+    /// are empty). With a maximum depth of 0, even the root container is abbreviated. The whole
+    /// document is verified regardless.
+    pub max_display_depth: Option<usize>,
+
+    /// Whether unnecessary escapes in strings are replaced by the characters they stand for.
+    ///
+    /// With this option, `\/` and `\u` escapes of printable characters in the Basic Multilingual
+    /// Plane are written literally, while `\u` escapes that have a shorter form (such as `\u000a`)
+    /// are written in that form. Otherwise, strings are output exactly as they are written.
+    pub normalize_escapes: bool,
+}
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            max_display_depth: None,
+            normalize_escapes: false,
+        }
+    }
+}
+
+
+struct Container {
+    is_object: bool,
+    is_empty: bool,
+}
+
+
+/// Appends the escape sequence that is equivalent to the given `\u` escape, or the character
+/// itself if it need not be escaped.
+fn push_normalized_escape(out: &mut Vec<u8>, u: u16) {
+    let c = match char::from_u32(u.into()) {
+        Some(c) => c,
+        None => {
+            // a surrogate; pairs are left escaped as well
+            out.extend_from_slice(format!("\\u{:04x}", u).as_bytes());
+            return;
+        },
+    };
+    match c {
+        '"' => out.extend_from_slice(b"\\\""),
+        '\\' => out.extend_from_slice(b"\\\\"),
+        '\u{08}' => out.extend_from_slice(b"\\b"),
+        '\u{0C}' => out.extend_from_slice(b"\\f"),
+        '\n' => out.extend_from_slice(b"\\n"),
+        '\r' => out.extend_from_slice(b"\\r"),
+        '\t' => out.extend_from_slice(b"\\t"),
+        // the line and paragraph separators are kept escaped as they end lines in JavaScript
+        c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+            out.extend_from_slice(format!("\\u{:04x}", u).as_bytes());
+        },
+        c => {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        },
+    }
+}
+
+
+/// Appends the characters of a string token, escaped the same way as in the document unless the
+/// escapes are to be normalized.
+fn push_json_chars(out: &mut Vec<u8>, json_chars: &[JsonChar], normalize_escapes: bool) {
+    out.push(b'"');
+    for json_char in json_chars {
+        match json_char {
+            JsonChar::EscapedSlash if normalize_escapes => out.push(b'/'),
+            JsonChar::UnicodeEscape(u) if normalize_escapes => push_normalized_escape(out, *u),
+            // only found in strings delimited by single quotes
+            JsonChar::Byte(b'"') => out.extend_from_slice(b"\\\""),
+            JsonChar::Byte(b) => out.push(*b),
+            JsonChar::EscapedQuote => out.extend_from_slice(b"\\\""),
+            JsonChar::EscapedBackslash => out.extend_from_slice(b"\\\\"),
+            JsonChar::EscapedSlash => out.extend_from_slice(b"\\/"),
+            JsonChar::EscapedBackspace => out.extend_from_slice(b"\\b"),
+            JsonChar::EscapedFormFeed => out.extend_from_slice(b"\\f"),
+            JsonChar::EscapedLineFeed => out.extend_from_slice(b"\\n"),
+            JsonChar::EscapedCarriageReturn => out.extend_from_slice(b"\\r"),
+            JsonChar::EscapedTab => out.extend_from_slice(b"\\t"),
+            JsonChar::UnicodeEscape(u) => out.extend_from_slice(format!("\\u{:04x}", u).as_bytes()),
+        }
+    }
+    out.push(b'"');
+}
+
+
+fn push_newline(out: &mut Vec<u8>, depth: usize, options: &FormatOptions) {
+    out.push(b'\n');
+    for _ in 0..(depth * options.indent_width) {
+        out.push(b' ');
+    }
+}
+
+
+/// Verifies a JSON document and writes it in indented form.
+///
+/// Strings and numbers are output exactly as they are written in the document. If the document
+/// is invalid, the output ends where the error was detected.
+pub fn format<R: BufRead, W: Write>(json_reader: R, writer: W, options: &FormatOptions) -> Result<(), FormatError> {
+    format_with(&Verifier::new(), json_reader, writer, options)
+}
+
+
+/// Verifies a JSON document following the options of the given verifier and writes it in indented
+/// form.
+///
+/// Strings and numbers are output exactly as they are written in the document, except that strings
+/// delimited by single quotes are delimited by double quotes instead. Unquoted keys are output
+/// unquoted and comments are dropped. If the document is invalid, the output ends where the error
+/// was detected.
+pub fn format_with<R: BufRead, W: Write>(verifier: &Verifier, json_reader: R, mut writer: W, options: &FormatOptions) -> Result<(), FormatError> {
+    let mut stack: Vec<Container> = Vec::new();
+    // the stack depth at which output is suppressed, if it is
+    let mut elided_depth: Option<usize> = None;
+    let mut saw_value = false;
+    let mut out = Vec::new();
+
+    verifier.scan::<_, (), FormatError, _>(json_reader, |state, tok, span| {
+        let step_outcome = state.feed_token(tok.clone(), span.start)?;
+        saw_value = true;
+
+        // the token has been verified; what remains is writing it
+        let is_closing = matches!(tok, JsonToken::ClosingBracket | JsonToken::ClosingBrace);
+        let displayed = match elided_depth {
+            Some(ed) => stack.len() < ed || (stack.len() == ed && is_closing),
+            None => true,
+        };
+
+        // the first token within a container decides how the container begins
+        if let Some(container) = stack.last_mut() {
+            if container.is_empty && !is_closing {
+                container.is_empty = false;
+                if elided_depth == Some(stack.len()) {
+                    out.extend_from_slice(b"...");
+                } else if displayed {
+                    push_newline(&mut out, stack.len(), options);
+                }
+            }
+        }
+
+        match &tok {
+            JsonToken::String(s) => {
+                if displayed {
+                    push_json_chars(&mut out, s, options.normalize_escapes);
+                }
+            },
+            JsonToken::Number(bytes)|JsonToken::Identifier(bytes) => {
+                if displayed {
+                    out.extend_from_slice(bytes);
+                }
+            },
+            JsonToken::Null|JsonToken::True|JsonToken::False => {
+                if displayed {
+                    let literal: &[u8] = match tok {
+                        JsonToken::Null => b"null",
+                        JsonToken::True => b"true",
+                        _ => b"false",
+                    };
+                    out.extend_from_slice(literal);
+                }
+            },
+            JsonToken::Colon => {
+                if displayed {
+                    out.extend_from_slice(b": ");
+                }
+            },
+            JsonToken::Comma => {
+                if displayed {
+                    out.push(b',');
+                    push_newline(&mut out, stack.len(), options);
+                }
+            },
+            JsonToken::OpeningBracket|JsonToken::OpeningBrace => {
+                let is_object = matches!(tok, JsonToken::OpeningBrace);
+                if displayed {
+                    out.push(if is_object { b'{' } else { b'[' });
+                    if let Some(max_display_depth) = options.max_display_depth {
+                        if stack.len() >= max_display_depth {
+                            elided_depth = Some(stack.len() + 1);
+                        }
+                    }
+                }
+                stack.push(Container {
+                    is_object,
+                    is_empty: true,
+                });
+            },
+            JsonToken::ClosingBracket|JsonToken::ClosingBrace => {
+                let container = stack.pop()
+                    .expect("verifier accepted a closing token but the stack is empty");
+                if displayed {
+                    if !container.is_empty && elided_depth != Some(stack.len() + 1) {
+                        push_newline(&mut out, stack.len(), options);
+                    }
+                    out.push(if container.is_object { b'}' } else { b']' });
+                }
+                if elided_depth == Some(stack.len() + 1) {
+                    elided_depth = None;
+                }
+            },
+        }
+
+        writer.write_all(&out)
+            .map_err(FormatError::Write)?;
+        out.clear();
+        Ok(ControlFlow::Continue(step_outcome))
+    })?;
+
+    if saw_value {
+        writer.write_all(b"\n")
+            .map_err(FormatError::Write)?;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{format, format_with, FormatError, FormatOptions};
+    use crate::verifier::{Verifier, VerifyError};
+
+    fn formatted(json: &str, options: &FormatOptions) -> Result<String, FormatError> {
+        let mut out = Vec::new();
+        format(json.as_bytes(), &mut out, options)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_format() {
+        let options = FormatOptions::default();
+        assert_eq!(formatted("", &options).unwrap(), "");
+        assert_eq!(formatted(" 1.50 ", &options).unwrap(), "1.50\n");
+        assert_eq!(formatted("[]", &options).unwrap(), "[]\n");
+        assert_eq!(
+            formatted(r#"{"a":[1,2.0e1,{}],"bé":{"c":null,"d":[]},"e":"x\"y"}"#, &options).unwrap(),
+            concat!(
+                "{\n",
+                "    \"a\": [\n",
+                "        1,\n",
+                "        2.0e1,\n",
+                "        {}\n",
+                "    ],\n",
+                "    \"bé\": {\n",
+                "        \"c\": null,\n",
+                "        \"d\": []\n",
+                "    },\n",
+                "    \"e\": \"x\\\"y\"\n",
+                "}\n",
+            ),
+        );
+
+        let two_spaces = FormatOptions {
+            indent_width: 2,
+            ..FormatOptions::default()
+        };
+        assert_eq!(formatted("[[true]]", &two_spaces).unwrap(), "[\n  [\n    true\n  ]\n]\n");
+    }
+
+    #[test]
+    fn test_max_display_depth() {
+        let json = r#"{"a": [1, {"b": 2}], "c": [], "d": {"e": {}}, "f": 3}"#;
+        let depth1 = FormatOptions {
+            max_display_depth: Some(1),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            formatted(json, &depth1).unwrap(),
+            "{\n    \"a\": [...],\n    \"c\": [],\n    \"d\": {...},\n    \"f\": 3\n}\n",
+        );
+
+        let depth2 = FormatOptions {
+            max_display_depth: Some(2),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            formatted(json, &depth2).unwrap(),
+            "{\n    \"a\": [\n        1,\n        {...}\n    ],\n    \"c\": [],\n    \"d\": {\n        \"e\": {}\n    },\n    \"f\": 3\n}\n",
+        );
+
+        let depth0 = FormatOptions {
+            max_display_depth: Some(0),
+            ..FormatOptions::default()
+        };
+        assert_eq!(formatted(json, &depth0).unwrap(), "{...}\n");
+        assert_eq!(formatted("\"scalar\"", &depth0).unwrap(), "\"scalar\"\n");
+    }
+
+    #[test]
+    fn test_elided_content_is_verified() {
+        let depth1 = FormatOptions {
+            max_display_depth: Some(1),
+            ..FormatOptions::default()
+        };
+        match formatted(r#"{"a": [1, {"b": 2, "b": 3}]}"#, &depth1) {
+            Err(FormatError::Verify(VerifyError::DuplicateKey(_, key, _))) => assert_eq!(key, "b"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match formatted(r#"{"a": [1, [2 3]]}"#, &depth1) {
+            Err(FormatError::Verify(VerifyError::UnexpectedToken(p, _, _))) => assert_eq!(p.offset, 13),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match formatted(r#"{"a": [1, ["\ud800"]]}"#, &depth1) {
+            Err(FormatError::Verify(VerifyError::InvalidString(_, _))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match formatted(r#"{"a": [[]]"#, &depth1) {
+            Err(FormatError::Verify(VerifyError::UnclosedDocument(_, 1))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_normalize_escapes() {
+        let options = FormatOptions::default();
+        let normalizing = FormatOptions {
+            normalize_escapes: true,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(formatted(r#""\u0041""#, &options).unwrap(), "\"\\u0041\"\n");
+        assert_eq!(formatted(r#""\u0041""#, &normalizing).unwrap(), "\"A\"\n");
+        assert_eq!(
+            formatted(r#"{"\u00e9\/": "\u0022\u005C\u000a\u0001\u007f\u2028\ud83d\ude00\n\"é"}"#, &normalizing).unwrap(),
+            "{\n    \"\u{E9}/\": \"\\\"\\\\\\n\\u0001\\u007f\\u2028\\ud83d\\ude00\\n\\\"\u{E9}\"\n}\n",
+        );
+    }
+
+    #[test]
+    fn test_format_with() {
+        fn formatted_with(verifier: &Verifier, json: &str) -> Result<String, FormatError> {
+            let mut out = Vec::new();
+            format_with(verifier, json.as_bytes(), &mut out, &FormatOptions::default())?;
+            Ok(String::from_utf8(out).unwrap())
+        }
+
+        let lenient = Verifier::new()
+            .allow_comments(true)
+            .allow_single_quotes(true)
+            .allow_unquoted_keys(true)
+            .decimal_comma(true);
+        assert_eq!(
+            formatted_with(&lenient, "{a: 'x\"y\\'', // pi\n'b': 3,14}").unwrap(),
+            "{\n    a: \"x\\\"y'\",\n    \"b\": 3,14\n}\n",
+        );
+        match formatted("{a: 1}", &FormatOptions::default()) {
+            Err(FormatError::Verify(VerifyError::Tokenizer(_, _))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let shallow = Verifier::new().max_depth(1);
+        match formatted_with(&shallow, "[[1]]") {
+            Err(FormatError::Verify(VerifyError::MaxDepthExceeded(_, 1))) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+/// Implements comparisons for newtypes around an `Arc` by identity: two values are equal if they
+/// share the same allocation, i.e. if one is a clone of the other.
+macro_rules! impl_identity_comparisons {
+    ($($name:ident),*) => {$(
+        impl $name {
+            /// The address of the shared value, by which values are ordered and hashed.
+            fn address(&self) -> usize {
+                ::std::sync::Arc::as_ptr(&self.0) as *const () as usize
+            }
+        }
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                ::std::sync::Arc::ptr_eq(&self.0, &other.0)
+            }
+        }
+        impl Eq for $name {}
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+                self.address().cmp(&other.address())
+            }
+        }
+        impl ::std::hash::Hash for $name {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                ::std::hash::Hash::hash(&self.address(), state);
+            }
+        }
+    )*};
+}
+pub(crate) use impl_identity_comparisons;
@@ -3,6 +3,13 @@ use std::io::BufRead;
 
 pub(crate) trait BufReadExt {
     fn peek(&mut self) -> Result<Option<u8>, std::io::Error>;
+
+    /// Returns up to `n` bytes without consuming them, or `None` at EOF.
+    ///
+    /// Only the bytes that are currently buffered are returned, so there may be fewer than `n` of
+    /// them even if more follow.
+    fn peek_n(&mut self, n: usize) -> Result<Option<&[u8]>, std::io::Error>;
+
     fn read_byte(&mut self) -> Result<Option<u8>, std::io::Error>;
 }
 impl<R: BufRead> BufReadExt for R {
@@ -14,6 +21,15 @@ impl<R: BufRead> BufReadExt for R {
             )
     }
 
+    fn peek_n(&mut self, n: usize) -> Result<Option<&[u8]>, std::io::Error> {
+        let buf = self.fill_buf()?;
+        if buf.len() == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(&buf[..n.min(buf.len())]))
+        }
+    }
+
     fn read_byte(&mut self) -> Result<Option<u8>, std::io::Error> {
         match self.peek() {
             Ok(Some(b)) => {
@@ -39,3 +55,28 @@ impl<T> IoResultOptionExt<T> for Result<Option<T>, std::io::Error> {
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use super::BufReadExt;
+
+    #[test]
+    fn test_peek_n() {
+        let mut reader = "abcdef".as_bytes();
+        assert_eq!(reader.peek_n(3).unwrap(), Some(&b"abc"[..]));
+        assert_eq!(reader.peek_n(10).unwrap(), Some(&b"abcdef"[..]));
+        assert_eq!(reader.peek_n(0).unwrap(), Some(&b""[..]));
+        reader.consume(5);
+        assert_eq!(reader.peek_n(3).unwrap(), Some(&b"f"[..]));
+        reader.consume(1);
+        assert_eq!(reader.peek_n(3).unwrap(), None);
+
+        // only what is buffered is returned
+        let mut reader = BufReader::with_capacity(2, "abcdef".as_bytes());
+        assert_eq!(reader.peek_n(3).unwrap(), Some(&b"ab"[..]));
+        assert_eq!(reader.read_byte().unwrap(), Some(b'a'));
+        assert_eq!(reader.peek_n(3).unwrap(), Some(&b"b"[..]));
+    }
+}
@@ -0,0 +1,18 @@
+pub mod canonical;
+pub mod diff;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+pub mod explain;
+pub mod format;
+mod identity;
+mod io_util;
+pub mod number;
+pub mod position;
+#[cfg(feature = "serde_json")]
+pub mod reference;
+pub mod tokenizer;
+pub mod utf8;
+#[cfg(feature = "value")]
+pub mod value;
+pub mod verifier;
+mod visit;
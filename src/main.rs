@@ -1,46 +1,635 @@
-mod io_util;
-mod tokenizer;
-mod verifier;
-
-
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+
+use jsonvfy::position::{Position, PositionReader};
+use jsonvfy::tokenizer::{
+    interpret_string, read_next_spanned_token, read_next_token_into, Error as TokenizerError, JsonToken, SpannedToken,
+    TokenBuffer, TokenizerOptions,
+};
+use jsonvfy::verifier::{Diagnostic, Stats, ValueKind, Verifier, VerifyError};
+
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable messages on standard error.
+    #[default]
+    Text,
+
+    /// A JSON object describing the result on standard output.
+    Json,
+}
+
+
+/// The exit codes of jsonvfy.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Exit {
+    /// The document is valid.
+    Valid = 0,
+
+    /// The document is invalid.
+    Invalid = 1,
+
+    /// The input could not be opened or read.
+    Io = 2,
+
+    /// The command line is invalid.
+    Usage = 3,
+}
+impl Exit {
+    /// Returns the exit code corresponding to the given verification error.
+    fn from_error(error: &VerifyError) -> Self {
+        match error {
+            VerifyError::Tokenizer(_, e) => Self::from_tokenizer_error(e),
+            _ => Self::Invalid,
+        }
+    }
+
+    /// Returns the exit code corresponding to the given tokenizer error.
+    fn from_tokenizer_error(error: &TokenizerError) -> Self {
+        match error {
+            // running out of input midway is the document's fault
+            TokenizerError::Io(e) if e.kind() != io::ErrorKind::UnexpectedEof => Self::Io,
+            _ => Self::Invalid,
+        }
+    }
+}
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
 
-use crate::verifier::verify;
+const EXIT_CODE_HELP: &str = "\
+Exit codes:
+  0  the document is valid
+  1  the document is invalid
+  2  the input could not be opened or read
+  3  the command line is invalid";
 
 
 #[derive(Parser)]
+#[command(after_help = EXIT_CODE_HELP)]
 struct Opts {
     /// Tokenize instead of verifying.
     #[arg(short, long)]
     pub tokenize: bool,
 
-    /// The JSON file to verify.
+    /// When tokenizing, prefix each token with the line and column at which it starts.
+    ///
+    /// Cannot be combined with JSON output, which contains the span of each token anyway.
+    #[arg(short, long, requires = "tokenize", conflicts_with = "json")]
+    pub line_numbers: bool,
+
+    /// The format in which to output the verification result.
+    ///
+    /// When tokenizing, `json` outputs the tokens as a JSON array of objects with the type, value
+    /// and span of each token.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    /// Output JSON; short for `--format json`.
+    #[arg(long, conflicts_with = "format")]
+    pub json: bool,
+
+    /// On failure, show the offending line with a caret under the problem column.
+    ///
+    /// With `--format json`, the line is included in each error object as `context`.
+    #[arg(short, long)]
+    pub context: bool,
+
+    /// Report progress on standard error while verifying.
+    #[arg(short, long)]
+    pub progress: bool,
+
+    /// After successful verification, output how many values of each kind the document contains.
+    #[arg(long, conflicts_with = "tokenize")]
+    pub count: bool,
+
+    /// After successful verification, describe the structure of the document in prose.
+    #[arg(long, conflicts_with_all = ["tokenize", "count"])]
+    pub explain: bool,
+
+    /// Continue verifying after an error and report up to this many errors.
+    #[arg(long, value_name = "N", conflicts_with_all = ["tokenize", "count", "explain"])]
+    pub max_errors: Option<usize>,
+
+    /// Require the root to be an object with the given keys, whose values are of the given kinds
+    /// (object, array, string, number, boolean or null), e.g. `name:string,age:number`.
+    #[arg(long, value_name = "KEY:KIND", value_delimiter = ',', value_parser = parse_requirement, conflicts_with = "tokenize")]
+    pub require: Vec<(String, ValueKind)>,
+
+    /// Map the file into memory instead of reading it through a buffer.
+    ///
+    /// Falls back to reading if the file cannot be mapped or if jsonvfy was built without the
+    /// memmap2 feature.
+    #[arg(short, long)]
+    pub mmap: bool,
+
+    /// The encoding of the file (e.g. `windows-1252`), which is transcoded to UTF-8 before it is
+    /// read.
+    ///
+    /// Requires jsonvfy to be built with the encoding feature.
+    #[arg(short, long, value_name = "LABEL")]
+    pub encoding: Option<String>,
+
+    /// The JSON file to verify, or `-` to read standard input.
+    ///
+    /// If jsonvfy was built with the flate2 feature, gzip-compressed input is decompressed
+    /// transparently.
     pub json_file: PathBuf,
 }
+impl Opts {
+    /// Applies the options that clap cannot express on its own.
+    fn resolve(mut self) -> Result<Self, clap::Error> {
+        if self.json {
+            self.format = OutputFormat::Json;
+        }
+        // clap cannot make an argument conflict with a single value of another one
+        if self.line_numbers && self.format == OutputFormat::Json {
+            return Err(Self::command().error(
+                ErrorKind::ArgumentConflict,
+                "the argument '--line-numbers' cannot be used with '--format json'",
+            ));
+        }
+        Ok(self)
+    }
+}
+
+
+fn json_escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if c < ' ' => ret.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+
+fn parse_requirement(requirement: &str) -> Result<(String, ValueKind), String> {
+    // the key may contain colons but the kind cannot
+    let (key, kind) = requirement.rsplit_once(':')
+        .ok_or_else(|| format!("{:?} is not of the form KEY:KIND", requirement))?;
+    let kind = match kind {
+        "object" => ValueKind::Object,
+        "array" => ValueKind::Array,
+        "string" => ValueKind::String,
+        "number" => ValueKind::Number,
+        "boolean" => ValueKind::Boolean,
+        "null" => ValueKind::Null,
+        other => return Err(format!("unknown kind {:?}", other)),
+    };
+    Ok((key.to_owned(), kind))
+}
+
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+
+#[cfg(feature = "memmap2")]
+fn map_file(file: &File) -> Option<Box<dyn BufRead>> {
+    // SAFETY: the mapping is only ever read; if another process modifies the file in the meantime,
+    // the verdict is meaningless anyway (and truncation may kill us with SIGBUS)
+    let mmap = unsafe { memmap2::Mmap::map(file) }.ok()?;
+    Some(Box::new(io::Cursor::new(mmap)))
+}
+
+#[cfg(not(feature = "memmap2"))]
+fn map_file(_file: &File) -> Option<Box<dyn BufRead>> {
+    None
+}
+
+
+/// Wraps the reader in a gzip decoder if the data begins with the gzip magic bytes; otherwise
+/// returns it unchanged.
+///
+/// The file name is not consulted, so a plain file named `x.json.gz` is read as it is. Also returns
+/// whether the reader has been wrapped.
+#[cfg(feature = "flate2")]
+fn decompress(mut reader: Box<dyn BufRead>) -> io::Result<(Box<dyn BufRead>, bool)> {
+    use std::io::Read;
+
+    const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+    // the buffer might hold fewer bytes than the magic even if more follow; take them out and
+    // put them back in front
+    let mut magic = Vec::with_capacity(GZIP_MAGIC.len());
+    (&mut reader).take(GZIP_MAGIC.len() as u64).read_to_end(&mut magic)?;
+    let is_gzip = magic == GZIP_MAGIC;
+    let reader: Box<dyn BufRead> = Box::new(io::Cursor::new(magic).chain(reader));
+    if !is_gzip {
+        return Ok((reader, false));
+    }
+    let decoder = flate2::bufread::MultiGzDecoder::new(reader);
+    Ok((Box::new(BufReader::new(decoder)), true))
+}
+
+#[cfg(not(feature = "flate2"))]
+fn decompress(reader: Box<dyn BufRead>) -> io::Result<(Box<dyn BufRead>, bool)> {
+    Ok((reader, false))
+}
+
+
+/// Returns the contents of the given (1-based) line, ending lines the same way as `Position`.
+fn read_line<R: BufRead>(reader: R, line_number: u64) -> io::Result<Vec<u8>> {
+    let mut current_line = 1;
+    let mut line_bytes = Vec::new();
+    let mut after_carriage_return = false;
+    for b in reader.bytes() {
+        let b = b?;
+        if b == b'\n' && after_carriage_return {
+            // second half of CRLF
+        } else if b == b'\n' || b == b'\r' {
+            if current_line == line_number {
+                break;
+            }
+            current_line += 1;
+        } else if current_line == line_number {
+            line_bytes.push(b);
+        }
+        after_carriage_return = b == b'\r';
+    }
+    Ok(line_bytes)
+}
+
+
+#[cfg(feature = "encoding")]
+fn transcode(reader: Box<dyn BufRead>, label: &str) -> Result<Box<dyn BufRead>, String> {
+    match jsonvfy::encoding::transcoding_reader(reader, label) {
+        Some(transcoding_reader) => Ok(Box::new(transcoding_reader)),
+        None => Err(format!("unknown encoding {:?}", label)),
+    }
+}
+
+#[cfg(not(feature = "encoding"))]
+fn transcode(_reader: Box<dyn BufRead>, _label: &str) -> Result<Box<dyn BufRead>, String> {
+    Err("jsonvfy was built without the encoding feature".to_owned())
+}
+
+
+/// Returns the line of the file at which the position is.
+///
+/// The file is decompressed and transcoded the same way as for verification, so that the line
+/// numbers match.
+fn context_line(opts: &Opts, position: Position) -> io::Result<String> {
+    // re-read the file to find the line; the verifier has long since consumed it
+    let (mut reader, _) = decompress(Box::new(BufReader::new(File::open(&opts.json_file)?)))?;
+    if let Some(label) = &opts.encoding {
+        reader = transcode(reader, label)
+            .map_err(io::Error::other)?;
+    }
+    let line_bytes = read_line(reader, position.line)?;
+    Ok(String::from_utf8_lossy(&line_bytes).into_owned())
+}
+
+
+fn print_context(opts: &Opts, position: Position, message: &str) -> io::Result<()> {
+    let line = context_line(opts, position)?;
+
+    // keep tabs so that the caret lines up
+    let caret_indent: String = line.chars()
+        .take(usize::try_from(position.column - 1).unwrap_or(usize::MAX))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    eprintln!("{}", line);
+    eprintln!("{}^ {}", caret_indent, message);
+    Ok(())
+}
+
+
+fn print_stats(stats: &Stats) {
+    println!("objects    {}", stats.objects);
+    println!("arrays     {}", stats.arrays);
+    println!("strings    {}", stats.strings);
+    println!("numbers    {}", stats.numbers);
+    println!("booleans   {}", stats.booleans);
+    println!("nulls      {}", stats.nulls);
+    println!("max depth  {}", stats.max_depth);
+    println!("max string {}", stats.longest_string);
+    println!("max number {}", stats.longest_number);
+}
+
+
+fn stats_json(stats: &Stats) -> String {
+    format!(
+        "{{\"objects\":{},\"arrays\":{},\"strings\":{},\"numbers\":{},\"booleans\":{},\"nulls\":{},\"max_depth\":{},\"longest_string\":{},\"longest_number\":{}}}",
+        stats.objects,
+        stats.arrays,
+        stats.strings,
+        stats.numbers,
+        stats.booleans,
+        stats.nulls,
+        stats.max_depth,
+        stats.longest_string,
+        stats.longest_number,
+    )
+}
+
+
+fn error_fields_json(opts: &Opts, diagnostic: &Diagnostic) -> String {
+    let error = &diagnostic.error;
+    let position = error.position();
+    let mut fields = format!(
+        "\"kind\":{},\"offset\":{},\"line\":{},\"column\":{},\"pointer\":{},\"message\":{}",
+        json_escape(error.kind()),
+        position.offset,
+        position.line,
+        position.column,
+        json_escape(&diagnostic.pointer),
+        json_escape(&error.to_string()),
+    );
+    // standard input cannot be re-read; the offset will have to do
+    if opts.context && !is_stdin(&opts.json_file) {
+        if let Ok(line) = context_line(opts, position) {
+            fields.push_str(&format!(",\"context\":{}", json_escape(&line)));
+        }
+    }
+    fields
+}
+
+
+fn report_error(opts: &Opts, verifier: &Verifier, diagnostic: &Diagnostic) -> ExitCode {
+    match opts.format {
+        OutputFormat::Text => {
+            let _ = verifier.report_diagnostic(diagnostic);
+            // standard input cannot be re-read; the offset will have to do
+            if opts.context && !is_stdin(&opts.json_file) {
+                let error = &diagnostic.error;
+                let _ = print_context(opts, error.position(), &error.to_string());
+            }
+        },
+        OutputFormat::Json => {
+            println!("{{\"valid\":false,{}}}", error_fields_json(opts, diagnostic));
+        },
+    }
+    Exit::from_error(&diagnostic.error).into()
+}
+
+
+fn report_errors(opts: &Opts, verifier: &Verifier, errors: &[Diagnostic], max_errors: usize) -> ExitCode {
+    // the first error decides the exit code, even if it is not shown
+    let exit = errors.first()
+        .map(|d| Exit::from_error(&d.error))
+        .unwrap_or(Exit::Valid);
+    let more_errors = errors.len() > max_errors;
+    let errors = &errors[..errors.len().min(max_errors)];
+
+    match opts.format {
+        OutputFormat::Text => {
+            for d in errors {
+                let _ = verifier.report_diagnostic(d);
+                if opts.context && !is_stdin(&opts.json_file) {
+                    let _ = print_context(opts, d.error.position(), &d.error.to_string());
+                }
+            }
+            if more_errors {
+                eprintln!("... and more errors");
+            }
+        },
+        OutputFormat::Json => {
+            if errors.len() == 0 && !more_errors {
+                println!("{{\"valid\":true}}");
+            } else {
+                let error_objects: Vec<String> = errors.iter()
+                    .map(|d| format!("{{{}}}", error_fields_json(opts, d)))
+                    .collect();
+                println!("{{\"valid\":false,\"errors\":[{}],\"more\":{}}}", error_objects.join(","), more_errors);
+            }
+        },
+    }
+
+    exit.into()
+}
+
+
+fn position_json(position: Position) -> String {
+    format!("{{\"offset\":{},\"line\":{},\"column\":{}}}", position.offset, position.line, position.column)
+}
+
+
+fn token_json(spanned: &SpannedToken) -> String {
+    let (token_type, value) = match &spanned.token {
+        JsonToken::OpeningBracket => ("opening-bracket", None),
+        JsonToken::ClosingBracket => ("closing-bracket", None),
+        JsonToken::OpeningBrace => ("opening-brace", None),
+        JsonToken::ClosingBrace => ("closing-brace", None),
+        JsonToken::Colon => ("colon", None),
+        JsonToken::Comma => ("comma", None),
+        JsonToken::String(chars) => {
+            // the tokenizer has already validated the string
+            let decoded = interpret_string(chars).unwrap_or_default();
+            ("string", Some(json_escape(&decoded)))
+        },
+        // numbers are valid JSON as written, and writing them so keeps their precision
+        JsonToken::Number(number) => ("number", Some(String::from_utf8_lossy(number).into_owned())),
+        JsonToken::Identifier(identifier) => ("identifier", Some(json_escape(&String::from_utf8_lossy(identifier)))),
+        JsonToken::Null => ("null", Some("null".to_owned())),
+        JsonToken::False => ("false", Some("false".to_owned())),
+        JsonToken::True => ("true", Some("true".to_owned())),
+    };
+    let value_field = match value {
+        Some(v) => format!(",\"value\":{}", v),
+        None => String::new(),
+    };
+    format!(
+        "{{\"type\":{}{},\"span\":{{\"start\":{},\"end\":{}}}}}",
+        json_escape(token_type),
+        value_field,
+        position_json(spanned.span.start),
+        position_json(spanned.span.end),
+    )
+}
+
+
+fn tokenize<R: BufRead>(mut reader: R, line_numbers: bool, format: OutputFormat) -> Result<(), TokenizerError> {
+    // verification checks the UTF-8 and surrogates within strings too
+    let options = TokenizerOptions {
+        validate_string_utf8: true,
+        validate_surrogates: true,
+        ..TokenizerOptions::default()
+    };
+    if format == OutputFormat::Json {
+        // the array is closed even if tokenizing fails, which keeps the output valid JSON
+        let mut reader = PositionReader::new(reader);
+        let mut token_count = 0usize;
+        println!("[");
+        let result = loop {
+            match read_next_spanned_token(&mut reader, &options) {
+                Ok(Some(spanned)) => {
+                    if token_count > 0 {
+                        println!(",");
+                    }
+                    print!("{}", token_json(&spanned));
+                    token_count += 1;
+                },
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        if token_count > 0 {
+            println!();
+        }
+        println!("]");
+        return result;
+    }
+
+    if line_numbers {
+        let mut reader = PositionReader::new(reader);
+        while let Some(spanned) = read_next_spanned_token(&mut reader, &options)? {
+            println!("{}:{} {:?}", spanned.span.start.line, spanned.span.start.column, spanned.token);
+        }
+    } else {
+        // each token is printed and forgotten, so there is no need to allocate one
+        let mut buffer = TokenBuffer::new();
+        while let Some(tok) = read_next_token_into(&mut reader, &options, &mut buffer)? {
+            println!("{:?}", tok);
+        }
+    }
+    Ok(())
+}
 
 
 fn main() -> ExitCode {
-    let opts = Opts::parse();
+    let opts = match Opts::try_parse().and_then(Opts::resolve) {
+        Ok(o) => o,
+        Err(e) => {
+            let _ = e.print();
+            // --help and --version are not errors
+            let exit = if e.use_stderr() { Exit::Usage } else { Exit::Valid };
+            return exit.into();
+        },
+    };
 
-    let file = File::open(&opts.json_file)
-        .expect("failed to open JSON file");
-    let mut reader = BufReader::new(file);
+    let (reader, total_bytes): (Box<dyn BufRead>, Option<u64>) = if is_stdin(&opts.json_file) {
+        (Box::new(io::stdin().lock()), None)
+    } else {
+        let file = match File::open(&opts.json_file) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("cannot open {}: {}", opts.json_file.display(), e);
+                return Exit::Io.into();
+            },
+        };
+        let total_bytes = file.metadata().ok()
+            .map(|m| m.len());
+        let mapped = if opts.mmap { map_file(&file) } else { None };
+        match mapped {
+            Some(mapped_reader) => (mapped_reader, total_bytes),
+            None => (Box::new(BufReader::new(file)), total_bytes),
+        }
+    };
+    let (mut reader, total_bytes) = match decompress(reader) {
+        // the size of the compressed file says little about the progress
+        Ok((decompressed_reader, true)) => (decompressed_reader, None),
+        Ok((plain_reader, false)) => (plain_reader, total_bytes),
+        Err(e) => {
+            eprintln!("cannot read {}: {}", opts.json_file.display(), e);
+            return Exit::Io.into();
+        },
+    };
+    if let Some(label) = &opts.encoding {
+        reader = match transcode(reader, label) {
+            Ok(r) => r,
+            Err(message) => {
+                eprintln!("{}", message);
+                return Exit::Usage.into();
+            },
+        };
+    }
 
     if opts.tokenize {
-        while let Some(tok) = crate::tokenizer::read_next_token(&mut reader).expect("failed to read") {
-            println!("{:?}", tok);
+        match tokenize(reader, opts.line_numbers, opts.format) {
+            Ok(()) => Exit::Valid.into(),
+            Err(e) => {
+                eprintln!("failed to take next token: {}", e);
+                Exit::from_tokenizer_error(&e).into()
+            },
         }
-        ExitCode::SUCCESS
     } else {
-        if verify(&mut reader) {
-            ExitCode::SUCCESS
-        } else {
-            ExitCode::FAILURE
+        let mut verifier = Verifier::new();
+        for (key, kind) in &opts.require {
+            verifier = verifier.require_key(key.clone(), *kind);
+        }
+        if opts.progress {
+            verifier = verifier.on_progress(move |bytes_read| {
+                match total_bytes {
+                    Some(tb) if tb > 0 => eprint!("\r{}%", bytes_read * 100 / tb),
+                    _ => eprint!("\r{} bytes", bytes_read),
+                }
+            });
+        }
+
+        if let Some(max_errors) = opts.max_errors {
+            // one more to know whether there are more
+            let errors = verifier.diagnose_collecting(&mut reader, max_errors.saturating_add(1));
+            if opts.progress {
+                eprintln!();
+            }
+            return report_errors(&opts, &verifier, &errors, max_errors);
+        }
+
+        if opts.explain {
+            let result = jsonvfy::explain::explain(&verifier, &mut reader);
+            if opts.progress {
+                eprintln!();
+            }
+            return match result {
+                Ok(explanation) => {
+                    match opts.format {
+                        OutputFormat::Text => println!("{}", explanation),
+                        OutputFormat::Json => println!("{{\"valid\":true,\"explanation\":{}}}", json_escape(&explanation)),
+                    }
+                    Exit::Valid.into()
+                },
+                Err(e) => {
+                    // explaining does not keep track of the pointer
+                    report_error(&opts, &verifier, &Diagnostic { error: e, pointer: String::new() })
+                },
+            };
+        }
+
+        let result = verifier.diagnose(&mut reader);
+        if opts.progress {
+            // finish the progress line
+            eprintln!();
+        }
+
+        match result {
+            Ok(stats) => {
+                match opts.format {
+                    OutputFormat::Text => {
+                        if opts.count {
+                            print_stats(&stats);
+                        }
+                    },
+                    OutputFormat::Json => {
+                        if opts.count {
+                            println!("{{\"valid\":true,\"stats\":{}}}", stats_json(&stats));
+                        } else {
+                            println!("{{\"valid\":true}}");
+                        }
+                    },
+                }
+                Exit::Valid.into()
+            },
+            Err(d) => report_error(&opts, &verifier, &d),
         }
     }
 }
@@ -0,0 +1,422 @@
+use std::fmt;
+use std::io::BufRead;
+
+use crate::tokenizer::{Error, read_number_string, TokenizerOptions};
+
+
+/// A number parsed into a numeric type.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Number {
+    /// A number without a fractional part or an exponent that fits into an `i128`.
+    Integer(i128),
+
+    /// A number with a fractional part or an exponent that does not become infinite as an `f64`.
+    Float(f64),
+
+    /// A number that fits into neither, as written.
+    ///
+    /// Integers beyond the range of `i128` end up here instead of being converted to `f64`, which
+    /// would lose precision.
+    BigRaw(Vec<u8>),
+}
+impl TryFrom<&[u8]> for Number {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        parse_number(value)
+    }
+}
+
+
+/// Validates a number written according to RFC 8259 and parses it into the most suitable numeric
+/// type.
+///
+/// The whole slice must consist of the number; anything following it is rejected with
+/// `Error::InvalidNumberCharacter`.
+pub fn parse_number(number: &[u8]) -> Result<Number, Error> {
+    let mut reader = number;
+    let number = read_number_string(&mut reader, &TokenizerOptions::default())?;
+    if let Some(b) = reader.fill_buf()?.first() {
+        return Err(Error::InvalidNumberCharacter(*b));
+    }
+
+    let is_integer = number.iter().all(|b| b.is_ascii_digit() || *b == b'-');
+    let parsed = if is_integer {
+        number_as_i128(&number).map(Number::Integer)
+    } else {
+        number_as_f64(&number).map(Number::Float)
+    };
+    Ok(parsed.unwrap_or(Number::BigRaw(number)))
+}
+
+
+fn number_as_i128(number: &[u8]) -> Option<i128> {
+    let number_str = std::str::from_utf8(number).ok()?;
+    number_str.parse().ok()
+}
+
+
+/// A valid but unusual way of writing a number.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum NumberQuirk {
+    /// The exponent has redundant leading zeros, e.g. `1e007`.
+    ExponentLeadingZeros,
+
+    /// The number is negative zero, e.g. `-0` or `-0.0`.
+    NegativeZero,
+}
+impl fmt::Display for NumberQuirk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExponentLeadingZeros => write!(f, "exponent has leading zeros"),
+            Self::NegativeZero => write!(f, "negative zero"),
+        }
+    }
+}
+
+
+/// Returns the valid but unusual aspects of how the number is written.
+///
+/// The number is expected to be the payload of a `JsonToken::Number`, i.e. already validated.
+/// Hexadecimal numbers (as allowed by JSON5) have no quirks.
+pub fn number_quirks(number: &[u8]) -> Vec<NumberQuirk> {
+    let mut quirks = Vec::new();
+    if number.iter().any(|&b| b == b'x' || b == b'X') {
+        return quirks;
+    }
+
+    let (mantissa, exponent) = match number.iter().position(|&b| b == b'e' || b == b'E') {
+        Some(e_index) => (&number[..e_index], Some(&number[e_index+1..])),
+        None => (number, None),
+    };
+
+    let is_zero = mantissa.iter()
+        .all(|&b| b == b'0' || b == b'.' || b == b'-' || b == b'+');
+    if mantissa.first() == Some(&b'-') && is_zero {
+        quirks.push(NumberQuirk::NegativeZero);
+    }
+
+    if let Some(exponent) = exponent {
+        let exponent_digits = match exponent.first() {
+            Some(b'+') | Some(b'-') => &exponent[1..],
+            _ => exponent,
+        };
+        if exponent_digits.len() > 1 && exponent_digits[0] == b'0' {
+            quirks.push(NumberQuirk::ExponentLeadingZeros);
+        }
+    }
+
+    quirks
+}
+
+
+/// Returns whether the number is written with an exponent although its value is an integer, e.g.
+/// `1e5` or `1.5e1`.
+///
+/// The number is expected to be the payload of a `JsonToken::Number`, i.e. already validated.
+/// Hexadecimal numbers (as allowed by JSON5) have no exponent.
+pub fn is_integer_with_exponent(number: &[u8]) -> bool {
+    if number.iter().any(|&b| b == b'x' || b == b'X') {
+        return false;
+    }
+    let (mantissa, exponent) = match number.iter().position(|&b| b == b'e' || b == b'E') {
+        Some(e_index) => (&number[..e_index], &number[e_index+1..]),
+        None => return false,
+    };
+
+    let (exponent_negative, exponent_digits) = match exponent.first() {
+        Some(b'-') => (true, &exponent[1..]),
+        Some(b'+') => (false, &exponent[1..]),
+        _ => (false, exponent),
+    };
+    // saturating is fine; such exponents dwarf any mantissa
+    let exponent_magnitude = exponent_digits.iter()
+        .fold(0i64, |value, &b| value.saturating_mul(10).saturating_add(i64::from(b - b'0')));
+    let exponent_value = if exponent_negative { -exponent_magnitude } else { exponent_magnitude };
+
+    let fraction_length = match mantissa.iter().position(|&b| b == b'.') {
+        Some(dot_index) => mantissa.len() - dot_index - 1,
+        None => 0,
+    };
+    let digits: Vec<u8> = mantissa.iter()
+        .copied()
+        .filter(|b| b.is_ascii_digit())
+        .collect();
+    if digits.iter().all(|&b| b == b'0') {
+        return true;
+    }
+    let trailing_zeros = digits.iter()
+        .rev()
+        .take_while(|&&b| b == b'0')
+        .count();
+
+    // the value is digits * 10^scale
+    let scale = exponent_value.saturating_sub(fraction_length as i64);
+    scale >= 0 || (trailing_zeros as i64) >= -scale
+}
+
+
+/// How a number lies outside the range of double-precision floating-point values.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DoubleRangeViolation {
+    /// The number is too large in magnitude and becomes infinite, e.g. `1e999`.
+    Overflow,
+
+    /// The number is not zero but too small in magnitude and becomes zero, e.g. `1e-999`.
+    Underflow,
+}
+impl fmt::Display for DoubleRangeViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "becomes infinite"),
+            Self::Underflow => write!(f, "becomes zero"),
+        }
+    }
+}
+
+
+/// Returns whether the number lies outside the range of double-precision floating-point values.
+///
+/// The number is expected to be the payload of a `JsonToken::Number`, i.e. already validated.
+/// Hexadecimal numbers (as allowed by JSON5) and numbers with a decimal comma are not checked.
+pub fn double_range_violation(number: &[u8]) -> Option<DoubleRangeViolation> {
+    let value: f64 = std::str::from_utf8(number).ok()?
+        .parse().ok()?;
+    if value.is_infinite() {
+        return Some(DoubleRangeViolation::Overflow);
+    }
+
+    let mantissa = match number.iter().position(|&b| b == b'e' || b == b'E') {
+        Some(e_index) => &number[..e_index],
+        None => number,
+    };
+    let is_zero = mantissa.iter()
+        .all(|&b| !b.is_ascii_digit() || b == b'0');
+    if value == 0.0 && !is_zero {
+        return Some(DoubleRangeViolation::Underflow);
+    }
+    None
+}
+
+
+/// Returns the value of the number as a double-precision floating-point value.
+///
+/// The number is expected to be the payload of a `JsonToken::Number`, i.e. already validated.
+///
+/// Returns `None` if the number is too large in magnitude to be represented (i.e. it would
+/// become infinite). Numbers that are too precise are rounded to the nearest representable value.
+pub fn number_as_f64(number: &[u8]) -> Option<f64> {
+    let number_str = std::str::from_utf8(number).ok()?;
+    let value: f64 = number_str.parse().ok()?;
+    if value.is_finite() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+
+/// Returns the number in the canonical form defined by ECMAScript's `Number.prototype.toString`,
+/// as required by the JSON Canonicalization Scheme (RFC 8785).
+///
+/// The number is expected to be the payload of a `JsonToken::Number`, i.e. already validated.
+///
+/// Returns `None` if the number cannot be represented as a double-precision floating-point value.
+pub fn canonical_number(number: &[u8]) -> Option<String> {
+    let value = number_as_f64(number)?;
+    if value == 0.0 {
+        // including negative zero
+        return Some("0".to_owned());
+    }
+
+    // Rust outputs the shortest digit sequence that round-trips, just like ECMAScript
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e')?;
+    let digits: String = mantissa.chars()
+        .filter(|&c| c != '.')
+        .collect();
+    let exponent: i64 = exponent.parse().ok()?;
+
+    // value = 0.digits * 10^point
+    let digit_count = digits.len() as i64;
+    let point = exponent + 1;
+
+    let mut canonical = String::new();
+    if value < 0.0 {
+        canonical.push('-');
+    }
+    if digit_count <= point && point <= 21 {
+        canonical.push_str(&digits);
+        for _ in 0..(point - digit_count) {
+            canonical.push('0');
+        }
+    } else if 0 < point && point <= 21 {
+        let (integral, fractional) = digits.split_at(point as usize);
+        canonical.push_str(integral);
+        canonical.push('.');
+        canonical.push_str(fractional);
+    } else if -6 < point && point <= 0 {
+        canonical.push_str("0.");
+        for _ in 0..(-point) {
+            canonical.push('0');
+        }
+        canonical.push_str(&digits);
+    } else {
+        let (first_digit, other_digits) = digits.split_at(1);
+        canonical.push_str(first_digit);
+        if other_digits.len() > 0 {
+            canonical.push('.');
+            canonical.push_str(other_digits);
+        }
+        canonical.push('e');
+        canonical.push(if exponent < 0 { '-' } else { '+' });
+        canonical.push_str(&exponent.abs().to_string());
+    }
+    Some(canonical)
+}
+
+
+/// Returns the value of the number as a 64-bit signed integer.
+///
+/// The number is expected to be the payload of a `JsonToken::Number`, i.e. already validated.
+///
+/// Returns `None` if the number does not fit into an `i64`. This is also the case for every number
+/// with a fractional part or an exponent (e.g. `1.0` or `1e3`), even if its value is an integer.
+pub fn number_as_i64(number: &[u8]) -> Option<i64> {
+    let number_str = std::str::from_utf8(number).ok()?;
+    number_str.parse().ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{canonical_number, double_range_violation, is_integer_with_exponent, number_as_f64, number_as_i64, number_quirks, parse_number, Number, NumberQuirk};
+    use crate::tokenizer::Error;
+
+    #[test]
+    fn test_f64() {
+        assert_eq!(number_as_f64(b"0"), Some(0.0));
+        assert_eq!(number_as_f64(b"-12.5"), Some(-12.5));
+        assert_eq!(number_as_f64(b"1E+3"), Some(1000.0));
+        assert_eq!(number_as_f64(b"2.5e-1"), Some(0.25));
+        assert_eq!(number_as_f64(b"1e400"), None);
+        assert_eq!(number_as_f64(b"-1e400"), None);
+    }
+
+    #[test]
+    fn test_i64() {
+        assert_eq!(number_as_i64(b"0"), Some(0));
+        assert_eq!(number_as_i64(b"-0"), Some(0));
+        assert_eq!(number_as_i64(b"9223372036854775807"), Some(i64::MAX));
+        assert_eq!(number_as_i64(b"-9223372036854775808"), Some(i64::MIN));
+        assert_eq!(number_as_i64(b"9223372036854775808"), None);
+        assert_eq!(number_as_i64(b"1.0"), None);
+        assert_eq!(number_as_i64(b"1e3"), None);
+    }
+
+    #[test]
+    fn test_quirks() {
+        assert_eq!(number_quirks(b"0"), Vec::new());
+        assert_eq!(number_quirks(b"1e7"), Vec::new());
+        assert_eq!(number_quirks(b"1e-7"), Vec::new());
+        assert_eq!(number_quirks(b"1E0"), Vec::new());
+        assert_eq!(number_quirks(b"-0.5"), Vec::new());
+        assert_eq!(number_quirks(b"0xFE0"), Vec::new());
+        assert_eq!(number_quirks(b"1e007"), vec![NumberQuirk::ExponentLeadingZeros]);
+        assert_eq!(number_quirks(b"1E-00"), vec![NumberQuirk::ExponentLeadingZeros]);
+        assert_eq!(number_quirks(b"1e+7"), Vec::new());
+        assert_eq!(number_quirks(b"-0"), vec![NumberQuirk::NegativeZero]);
+        assert_eq!(number_quirks(b"-0.00e+01"), vec![
+            NumberQuirk::NegativeZero,
+            NumberQuirk::ExponentLeadingZeros,
+        ]);
+    }
+
+    #[test]
+    fn test_integer_with_exponent() {
+        assert_eq!(is_integer_with_exponent(b"1e5"), true);
+        assert_eq!(is_integer_with_exponent(b"-1E+5"), true);
+        assert_eq!(is_integer_with_exponent(b"1.5e1"), true);
+        assert_eq!(is_integer_with_exponent(b"1.25e1"), false);
+        assert_eq!(is_integer_with_exponent(b"1500e-2"), true);
+        assert_eq!(is_integer_with_exponent(b"1550e-2"), false);
+        assert_eq!(is_integer_with_exponent(b"1e-5"), false);
+        assert_eq!(is_integer_with_exponent(b"0.0e-300"), true);
+        assert_eq!(is_integer_with_exponent(b"1e99999999999999999999"), true);
+        assert_eq!(is_integer_with_exponent(b"1e-99999999999999999999"), false);
+        assert_eq!(is_integer_with_exponent(b"100"), false);
+        assert_eq!(is_integer_with_exponent(b"1.5"), false);
+        assert_eq!(is_integer_with_exponent(b"0xE5"), false);
+    }
+
+    #[test]
+    fn test_double_range_violation() {
+        use super::DoubleRangeViolation;
+
+        assert_eq!(double_range_violation(b"1e308"), None);
+        assert_eq!(double_range_violation(b"-1.7976931348623157e308"), None);
+        assert_eq!(double_range_violation(b"1.8e308"), Some(DoubleRangeViolation::Overflow));
+        assert_eq!(double_range_violation(b"-1e999999999"), Some(DoubleRangeViolation::Overflow));
+        assert_eq!(double_range_violation(b"1e99999999999999999999999"), Some(DoubleRangeViolation::Overflow));
+        assert_eq!(double_range_violation(&[b'9'; 400]), Some(DoubleRangeViolation::Overflow));
+
+        assert_eq!(double_range_violation(b"5e-324"), None);
+        assert_eq!(double_range_violation(b"2e-324"), Some(DoubleRangeViolation::Underflow));
+        assert_eq!(double_range_violation(b"-1E-999999999"), Some(DoubleRangeViolation::Underflow));
+        assert_eq!(double_range_violation(b"0.000e-999999999"), None);
+        assert_eq!(double_range_violation(b"0e999999999"), None);
+
+        assert_eq!(double_range_violation(b"0"), None);
+        assert_eq!(double_range_violation(b"0x1F"), None);
+    }
+
+    #[test]
+    fn test_canonical() {
+        fn canonical(number: &str) -> Option<String> {
+            canonical_number(number.as_bytes())
+        }
+
+        assert_eq!(canonical("0").as_deref(), Some("0"));
+        assert_eq!(canonical("-0.0").as_deref(), Some("0"));
+        assert_eq!(canonical("4.50").as_deref(), Some("4.5"));
+        assert_eq!(canonical("-12").as_deref(), Some("-12"));
+        assert_eq!(canonical("2e-3").as_deref(), Some("0.002"));
+        assert_eq!(canonical("0.000001").as_deref(), Some("0.000001"));
+        assert_eq!(canonical("1e-7").as_deref(), Some("1e-7"));
+        assert_eq!(canonical("1E30").as_deref(), Some("1e+30"));
+        assert_eq!(canonical("1e20").as_deref(), Some("100000000000000000000"));
+        assert_eq!(canonical("1e21").as_deref(), Some("1e+21"));
+        assert_eq!(canonical("-1.5e21").as_deref(), Some("-1.5e+21"));
+        assert_eq!(canonical("333333333.33333329").as_deref(), Some("333333333.3333333"));
+        assert_eq!(canonical("295147905179352830000").as_deref(), Some("295147905179352830000"));
+        assert_eq!(canonical("0.000000000000000000000000001").as_deref(), Some("1e-27"));
+        assert_eq!(canonical("1e400"), None);
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number(b"0").unwrap(), Number::Integer(0));
+        assert_eq!(parse_number(b"-42").unwrap(), Number::Integer(-42));
+        assert_eq!(parse_number(b"170141183460469231731687303715884105727").unwrap(), Number::Integer(i128::MAX));
+        assert_eq!(parse_number(b"-170141183460469231731687303715884105728").unwrap(), Number::Integer(i128::MIN));
+        assert_eq!(parse_number(b"1.5").unwrap(), Number::Float(1.5));
+        assert_eq!(parse_number(b"1e3").unwrap(), Number::Float(1000.0));
+        assert_eq!(parse_number(b"-2.5E-1").unwrap(), Number::Float(-0.25));
+
+        // out of range
+        assert_eq!(
+            parse_number(b"170141183460469231731687303715884105728").unwrap(),
+            Number::BigRaw(b"170141183460469231731687303715884105728".to_vec()),
+        );
+        assert_eq!(parse_number(b"1e400").unwrap(), Number::BigRaw(b"1e400".to_vec()));
+
+        assert_eq!(Number::try_from(&b"7"[..]).unwrap(), Number::Integer(7));
+
+        assert!(matches!(parse_number(b""), Err(Error::IncompleteNumber(_))));
+        assert!(matches!(parse_number(b"01"), Err(Error::InvalidNumberCharacter(b'1'))));
+        assert!(matches!(parse_number(b"1."), Err(Error::IncompleteNumber(_))));
+        assert!(matches!(parse_number(b"+1"), Err(Error::InvalidNumberCharacter(b'+'))));
+        assert!(matches!(parse_number(b"1 "), Err(Error::InvalidNumberCharacter(b' '))));
+        assert!(matches!(parse_number(b"1]"), Err(Error::InvalidNumberCharacter(b']'))));
+    }
+}
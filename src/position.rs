@@ -0,0 +1,175 @@
+use std::fmt;
+use std::io::{BufRead, Read};
+use std::ops::Range;
+
+
+/// A position within a JSON document.
+///
+/// The offset is counted in bytes from the beginning of the document. Lines and columns are
+/// 1-based; columns are counted in characters (UTF-8 continuation bytes do not advance them).
+/// CRLF, a lone CR and a lone LF each end a line.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Position {
+    pub offset: u64,
+    pub line: u64,
+    pub column: u64,
+}
+impl Position {
+    /// Advances the position past the bytes.
+    ///
+    /// Since an LF directly after a CR does not end another line, the caller keeps track of whether
+    /// the last byte advanced past was a CR; `after_carriage_return` is read and updated
+    /// accordingly.
+    pub fn advance(&mut self, bytes: &[u8], after_carriage_return: &mut bool) {
+        for &b in bytes {
+            self.offset += 1;
+            if b == b'\n' && *after_carriage_return {
+                // second half of CRLF; the line has already been counted
+            } else if b == b'\n' || b == b'\r' {
+                self.line += 1;
+                self.column = 1;
+            } else if b & 0b1100_0000 != 0b1000_0000 {
+                self.column += 1;
+            }
+            *after_carriage_return = b == b'\r';
+        }
+    }
+}
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {} (offset {})", self.line, self.column, self.offset)
+    }
+}
+
+
+/// The area of a document occupied by a token.
+///
+/// The start position is inclusive, the end position exclusive.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+impl Span {
+    /// The range of byte offsets occupied by the token, e.g. for copying it verbatim.
+    pub fn byte_range(&self) -> Range<u64> {
+        self.start.offset..self.end.offset
+    }
+}
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} to {}", self.start, self.end)
+    }
+}
+
+
+/// Wraps a reader and keeps track of the position of the next byte to be read.
+pub struct PositionReader<R> {
+    inner: R,
+    position: Position,
+    after_carriage_return: bool,
+}
+impl<R> PositionReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            position: Position::default(),
+            after_carriage_return: false,
+        }
+    }
+
+    /// Creates a position reader whose first byte is at the given position.
+    ///
+    /// The byte before that position is assumed not to be a CR.
+    pub fn starting_at(inner: R, position: Position) -> Self {
+        Self {
+            inner,
+            position,
+            after_carriage_return: false,
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+impl<R: BufRead> Read for PositionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read_count = self.inner.read(buf)?;
+        self.position.advance(&buf[..read_count], &mut self.after_carriage_return);
+        Ok(read_count)
+    }
+}
+impl<R: BufRead> BufRead for PositionReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // the buffer has already been filled, so this does not perform any I/O
+        if let Ok(buf) = self.inner.fill_buf() {
+            let consumed_count = amt.min(buf.len());
+            self.position.advance(&buf[..consumed_count], &mut self.after_carriage_return);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use super::{Position, PositionReader};
+
+    fn line_column(bytes: &[u8]) -> (u64, u64) {
+        let mut position = Position::default();
+        position.advance(bytes, &mut false);
+        (position.line, position.column)
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_eq!(line_column(b"ab"), (1, 3));
+        assert_eq!(line_column(b"a\nb"), (2, 2));
+        assert_eq!(line_column(b"a\r\nb"), (2, 2));
+        assert_eq!(line_column(b"a\rb"), (2, 2));
+        assert_eq!(line_column(b"a\n\rb"), (3, 2));
+        assert_eq!(line_column(b"a\r\rb"), (3, 2));
+        assert_eq!(line_column(b"a\r\n\r\nb"), (3, 2));
+        assert_eq!(line_column("\u{E9}t\u{E9}".as_bytes()), (1, 4));
+    }
+
+    #[test]
+    fn test_crlf_across_calls() {
+        let mut position = Position::default();
+        let mut after_carriage_return = false;
+        position.advance(b"a\r", &mut after_carriage_return);
+        position.advance(b"\nb", &mut after_carriage_return);
+        assert_eq!((position.offset, position.line, position.column), (4, 2, 2));
+
+        // the reader keeps track of the CR across buffer refills
+        let mut reader = PositionReader::new(BufReader::with_capacity(2, "a\r\nb".as_bytes()));
+        while reader.fill_buf().unwrap().len() > 0 {
+            reader.consume(1);
+        }
+        let position = reader.position();
+        assert_eq!((position.offset, position.line, position.column), (4, 2, 2));
+
+        // positions only consist of the offset, line and column
+        let position = Position { offset: 4, line: 2, column: 2 };
+        assert_eq!(reader.position(), position);
+    }
+}
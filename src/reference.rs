@@ -0,0 +1,70 @@
+use crate::verifier::verify_detailed;
+
+
+/// Returns whether the verdict of `verify_detailed` on the document matches whether
+/// `serde_json::from_slice::<serde_json::Value>` can parse it.
+///
+/// The verdicts intentionally differ in the following cases:
+///
+/// * Duplicate keys are rejected by jsonvfy but accepted by `serde_json` (the last one wins).
+/// * A document without a value (empty or only whitespace) is accepted by jsonvfy by default but
+///   rejected by `serde_json`.
+/// * Nesting deeper than 128 levels is accepted by jsonvfy by default but rejected by
+///   `serde_json`.
+/// * Unescaped control characters within strings are accepted by jsonvfy but rejected by
+///   `serde_json`.
+pub fn agrees_with_serde(bytes: &[u8]) -> bool {
+    let jsonvfy_verdict = verify_detailed(bytes).is_ok();
+    let serde_verdict = serde_json::from_slice::<serde_json::Value>(bytes).is_ok();
+    jsonvfy_verdict == serde_verdict
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::agrees_with_serde;
+
+    #[test]
+    fn test_agreement() {
+        let documents: [&[u8]; 24] = [
+            b"{}",
+            b"[]",
+            b" [1, 2.5, -3e4, true, false, null] ",
+            br#"{"a": {"b": ["c", {"d": "\u00e9\ud83d\ude00"}]}}"#,
+            b"\"\\n\\t\\\\\\/\"",
+            b"0",
+            b"-0.0e+00",
+            b"[1,]",
+            b"{\"a\" 1}",
+            b"{\"a\": 1,}",
+            b"[01]",
+            b"[1.]",
+            b"[.5]",
+            b"[+5]",
+            b"[0x10]",
+            b"[tru]",
+            b"[truex]",
+            b"[\"unterminated]",
+            b"[\"\\x\"]",
+            b"[\"\\ud800\"]",
+            b"[1] [2]",
+            b"[1",
+            b"[\"\xFF\"]",
+            b"{'a': 1}",
+        ];
+        for document in documents {
+            assert_eq!(agrees_with_serde(document), true, "disagreement on {:?}", String::from_utf8_lossy(document));
+        }
+    }
+
+    #[test]
+    fn test_known_divergences() {
+        assert_eq!(agrees_with_serde(br#"{"a": 1, "a": 2}"#), false);
+        assert_eq!(agrees_with_serde(b""), false);
+        assert_eq!(agrees_with_serde(b"  \n"), false);
+        assert_eq!(agrees_with_serde(b"\"a\x01b\""), false);
+
+        let deep = format!("{}{}", "[".repeat(200), "]".repeat(200));
+        assert_eq!(agrees_with_serde(deep.as_bytes()), false);
+    }
+}
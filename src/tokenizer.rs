@@ -1,7 +1,11 @@
+use std::borrow::Cow;
 use std::fmt;
-use std::io::BufRead;
+use std::io::{BufRead, Read};
+use std::sync::Arc;
 
+use crate::identity::impl_identity_comparisons;
 use crate::io_util::{BufReadExt, IoResultOptionExt};
+use crate::position::{PositionReader, Span};
 
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -14,10 +18,123 @@ pub enum JsonToken {
     Comma,
     String(Vec<JsonChar>),
     Number(Vec<u8>),
+    /// An unquoted identifier; only produced if `TokenizerOptions::allow_unquoted_keys` is set.
+    Identifier(Vec<u8>),
     Null,
     False,
     True,
 }
+impl JsonToken {
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Self::OpeningBracket => TokenKind::OpeningBracket,
+            Self::ClosingBracket => TokenKind::ClosingBracket,
+            Self::OpeningBrace => TokenKind::OpeningBrace,
+            Self::ClosingBrace => TokenKind::ClosingBrace,
+            Self::Colon => TokenKind::Colon,
+            Self::Comma => TokenKind::Comma,
+            Self::String(_) => TokenKind::String,
+            Self::Number(_) => TokenKind::Number,
+            Self::Identifier(_) => TokenKind::Identifier,
+            Self::Null => TokenKind::Null,
+            Self::False => TokenKind::False,
+            Self::True => TokenKind::True,
+        }
+    }
+}
+
+/// The kind of a token, without its contents.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TokenKind {
+    OpeningBracket,
+    ClosingBracket,
+    OpeningBrace,
+    ClosingBrace,
+    Colon,
+    Comma,
+    String,
+    Number,
+    Identifier,
+    Null,
+    False,
+    True,
+}
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OpeningBracket => write!(f, "an opening bracket"),
+            Self::ClosingBracket => write!(f, "a closing bracket"),
+            Self::OpeningBrace => write!(f, "an opening brace"),
+            Self::ClosingBrace => write!(f, "a closing brace"),
+            Self::Colon => write!(f, "a colon"),
+            Self::Comma => write!(f, "a comma"),
+            Self::String => write!(f, "a string"),
+            Self::Number => write!(f, "a number"),
+            Self::Identifier => write!(f, "an identifier"),
+            Self::Null => write!(f, "null"),
+            Self::False => write!(f, "false"),
+            Self::True => write!(f, "true"),
+        }
+    }
+}
+
+/// A token whose contents are borrowed from a `TokenBuffer`; see `read_next_token_into`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TokenView<'b> {
+    OpeningBracket,
+    ClosingBracket,
+    OpeningBrace,
+    ClosingBrace,
+    Colon,
+    Comma,
+    String(&'b [JsonChar]),
+    Number(&'b [u8]),
+    Identifier(&'b [u8]),
+    Null,
+    False,
+    True,
+}
+impl TokenView<'_> {
+    /// Copies the token out of the buffer.
+    pub fn to_token(&self) -> JsonToken {
+        match self {
+            Self::OpeningBracket => JsonToken::OpeningBracket,
+            Self::ClosingBracket => JsonToken::ClosingBracket,
+            Self::OpeningBrace => JsonToken::OpeningBrace,
+            Self::ClosingBrace => JsonToken::ClosingBrace,
+            Self::Colon => JsonToken::Colon,
+            Self::Comma => JsonToken::Comma,
+            Self::String(chars) => JsonToken::String(chars.to_vec()),
+            Self::Number(number) => JsonToken::Number(number.to_vec()),
+            Self::Identifier(identifier) => JsonToken::Identifier(identifier.to_vec()),
+            Self::Null => JsonToken::Null,
+            Self::False => JsonToken::False,
+            Self::True => JsonToken::True,
+        }
+    }
+}
+
+/// Storage for the contents of tokens that is reused from one token to the next.
+///
+/// Once the buffers have grown to the size of the longest string and number, reading further
+/// tokens with `read_next_token_into` allocates nothing.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TokenBuffer {
+    chars: Vec<JsonChar>,
+    bytes: Vec<u8>,
+}
+impl TokenBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// A token along with the area of the document it occupies.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SpannedToken {
+    pub token: JsonToken,
+    pub span: Span,
+}
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum JsonChar {
@@ -32,17 +149,221 @@ pub enum JsonChar {
     EscapedTab,
     UnicodeEscape(u16),
 }
+impl TryFrom<JsonChar> for char {
+    type Error = CharConversionError;
+
+    /// Converts a JSON character that stands on its own into a character.
+    ///
+    /// Fails for bytes that are part of a multi-byte UTF-8 sequence and for escaped surrogates.
+    fn try_from(value: JsonChar) -> Result<Self, Self::Error> {
+        match value {
+            JsonChar::Byte(b) => if b < 0x80 {
+                Ok(char::from(b))
+            } else {
+                Err(CharConversionError::NonAsciiByte(b))
+            },
+            JsonChar::EscapedQuote => Ok('"'),
+            JsonChar::EscapedBackslash => Ok('\\'),
+            JsonChar::EscapedSlash => Ok('/'),
+            JsonChar::EscapedBackspace => Ok('\u{08}'),
+            JsonChar::EscapedFormFeed => Ok('\u{0C}'),
+            JsonChar::EscapedLineFeed => Ok('\n'),
+            JsonChar::EscapedCarriageReturn => Ok('\r'),
+            JsonChar::EscapedTab => Ok('\t'),
+            JsonChar::UnicodeEscape(u) => char::from_u32(u.into())
+                .ok_or(CharConversionError::Surrogate(u)),
+        }
+    }
+}
+impl TryFrom<char> for JsonChar {
+    type Error = CharConversionError;
+
+    /// Converts a character into the JSON character that represents it in a string.
+    ///
+    /// Characters that must be escaped are escaped (using the short form if there is one) and
+    /// non-ASCII characters are escaped as `\uXXXX`. Fails for characters beyond the Basic
+    /// Multilingual Plane, which can only be represented by a pair of escaped surrogates; this is
+    /// why there is no infallible `From<char>`.
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '"' => Ok(JsonChar::EscapedQuote),
+            '\\' => Ok(JsonChar::EscapedBackslash),
+            '\u{08}' => Ok(JsonChar::EscapedBackspace),
+            '\u{0C}' => Ok(JsonChar::EscapedFormFeed),
+            '\n' => Ok(JsonChar::EscapedLineFeed),
+            '\r' => Ok(JsonChar::EscapedCarriageReturn),
+            '\t' => Ok(JsonChar::EscapedTab),
+            c if c < ' ' => Ok(JsonChar::UnicodeEscape(c as u16)),
+            c if c.is_ascii() => Ok(JsonChar::Byte(c as u8)),
+            c => u16::try_from(u32::from(c))
+                .map(JsonChar::UnicodeEscape)
+                .map_err(|_| CharConversionError::OutsideBasicMultilingualPlane(c)),
+        }
+    }
+}
+
+/// An error converting between `JsonChar` and `char`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum CharConversionError {
+    NonAsciiByte(u8),
+    Surrogate(u16),
+    OutsideBasicMultilingualPlane(char),
+}
+impl fmt::Display for CharConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonAsciiByte(b) => write!(f, "byte 0x{:02X} is part of a multi-byte UTF-8 sequence", b),
+            Self::Surrogate(u) => write!(f, "escape 0x{:04X} is a surrogate", u),
+            Self::OutsideBasicMultilingualPlane(c) => write!(f, "character {:?} requires a surrogate pair", c),
+        }
+    }
+}
+impl std::error::Error for CharConversionError {
+}
+
+bitflags::bitflags! {
+    /// ASCII control characters that are accepted as whitespace in addition to the four that
+    /// RFC 8259 allows (space, tab, line feed and carriage return).
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct ExtraWhitespace: u8 {
+        /// U+000C FORM FEED.
+        const FORM_FEED = 0x01;
+
+        /// U+000B LINE TABULATION (vertical tab).
+        const VERTICAL_TAB = 0x02;
+    }
+}
+
+/// Options that relax the JSON grammar accepted by the tokenizer.
+///
+/// The default options accept exactly the grammar of RFC 8259.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TokenizerOptions {
+    /// Whether `// line comments` and `/* block comments */` are skipped like whitespace.
+    pub allow_comments: bool,
+
+    /// Whether strings may also be delimited by single quotes (as in JSON5).
+    ///
+    /// Within such a string, double quotes need not be escaped; in both kinds of strings, a single
+    /// quote may then be escaped as `\'`.
+    pub allow_single_quotes: bool,
+
+    /// Whether identifiers (`[A-Za-z_$][A-Za-z0-9_$]*`) are returned as `JsonToken::Identifier`
+    /// instead of being rejected as invalid barewords, to support unquoted object keys.
+    ///
+    /// The identifiers `true`, `false` and `null` are still returned as the respective tokens.
+    pub allow_unquoted_keys: bool,
+
+    /// Whether numbers may begin with a plus sign (e.g. `+5`), which is then kept as part of the
+    /// number.
+    pub allow_leading_plus: bool,
+
+    /// Whether numbers may additionally be written in the forms allowed by JSON5: hexadecimal with
+    /// a `0x` prefix (e.g. `0xFF`), with a leading decimal point (e.g. `.5`) or with a trailing
+    /// decimal point (e.g. `5.`).
+    ///
+    /// The number is returned as written; `number_as_f64` and `number_as_i64` do not understand
+    /// hexadecimal numbers.
+    pub allow_json5_numbers: bool,
+
+    /// Whether non-ASCII whitespace (such as U+00A0 NO-BREAK SPACE) and U+FEFF ZERO WIDTH
+    /// NO-BREAK SPACE are skipped between tokens like whitespace.
+    ///
+    /// Otherwise, such characters are rejected with `Error::IllegalWhitespace`.
+    pub allow_unicode_whitespace: bool,
+
+    /// The ASCII control characters that are accepted as whitespace in addition to the standard
+    /// ones.
+    pub extra_whitespace: ExtraWhitespace,
+
+    /// Whether the UTF-8 within strings is validated while reading them.
+    ///
+    /// By default, invalid UTF-8 is only detected once the string is passed to `interpret_string`.
+    /// With this option, reading the string fails with the same error instead, as soon as the
+    /// invalid sequence has been read.
+    pub validate_string_utf8: bool,
+
+    /// Whether the pairing of UTF-16 surrogates in `\u` escapes is validated while reading strings.
+    ///
+    /// By default, a lone or mismatched surrogate is only detected once the string is passed to
+    /// `interpret_string`. With this option, reading the string fails with
+    /// `Error::InvalidUtf16SurrogateSequence` instead.
+    pub validate_surrogates: bool,
+
+    /// The maximum number of Unicode escapes (`\uXXXX`) in a single string.
+    ///
+    /// Reading a string with more of them fails with `Error::TooManyUnicodeEscapes`.
+    pub max_escapes_per_string: Option<usize>,
+
+    /// Decides whether a byte that the number grammar would not accept at that point is taken as
+    /// part of the number after all, given the number read so far and the byte.
+    ///
+    /// For example, a predicate that accepts `_` after a digit allows digit separators as in
+    /// `1_000`. Careless predicates can make the tokenizer accept numbers that are not valid JSON
+    /// (or even swallow the following tokens), and `number_as_f64` and friends do not understand
+    /// such numbers.
+    pub accept_number_byte: Option<NumberBytePredicate>,
+}
+
+/// The function behind a `NumberBytePredicate`, given the number read so far and the byte.
+pub type NumberBytePredicateFn = dyn Fn(&[u8], u8) -> bool + Send + Sync;
+
+/// A predicate that decides whether an otherwise invalid byte is taken as part of a number; see
+/// `TokenizerOptions::accept_number_byte`.
+///
+/// Predicates are compared by identity: two predicates are equal if they share the same
+/// allocation, i.e. if one is a clone of the other.
+#[derive(Clone)]
+pub struct NumberBytePredicate(pub Arc<NumberBytePredicateFn>);
+impl NumberBytePredicate {
+    pub fn new<F: Fn(&[u8], u8) -> bool + Send + Sync + 'static>(predicate: F) -> Self {
+        Self(Arc::new(predicate))
+    }
+}
+impl fmt::Debug for NumberBytePredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NumberBytePredicate")
+            .finish_non_exhaustive()
+    }
+}
+impl_identity_comparisons!(NumberBytePredicate);
+
+/// A part of a number that must contain at least one digit.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum NumberPart {
+    Mantissa,
+    Fraction,
+    Exponent,
+}
+impl fmt::Display for NumberPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mantissa => write!(f, "mantissa"),
+            Self::Fraction => write!(f, "fraction"),
+            Self::Exponent => write!(f, "exponent"),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum Error {
     Io(std::io::Error),
     UnknownEscape(u8),
     InvalidUnicodeEscape([u8; 4]),
+    IncompleteUnicodeEscape(Vec<u8>),
+    UnterminatedString(Vec<JsonChar>),
     InvalidNumberCharacter(u8),
+    IncompleteNumber(NumberPart),
     InvalidBarewordBeginning(String),
     InvalidUtf8Sequence(Vec<JsonChar>),
     Utf8SequenceProducedSurrogate(u32),
+    CodePointOutOfRange(u32),
     InvalidUtf16SurrogateSequence(Vec<JsonChar>),
+    InvalidCommentBeginning(u8),
+    UnterminatedComment,
+    UnexpectedByte(u8),
+    IllegalWhitespace(char),
+    TooManyUnicodeEscapes(usize),
 }
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -50,11 +371,20 @@ impl fmt::Display for Error {
             Self::Io(e) => write!(f, "I/O error: {}", e),
             Self::UnknownEscape(c) => write!(f, "unknown escape character {:?}", c),
             Self::InvalidUnicodeEscape(c) => write!(f, "invalid Unicode escape value {}{}{}{}", c[0], c[1], c[2], c[3]),
+            Self::IncompleteUnicodeEscape(c) => write!(f, "input ends within Unicode escape after {:?}", bareword_to_string(c)),
+            Self::UnterminatedString(s) => write!(f, "input ends within string after {} characters", s.len()),
             Self::InvalidNumberCharacter(c) => write!(f, "invalid number character {:?}", c),
+            Self::IncompleteNumber(part) => write!(f, "input ends before the {} of a number", part),
             Self::InvalidBarewordBeginning(s) => write!(f, "invalid bareword beginning {:?}", s),
             Self::InvalidUtf8Sequence(seq) => write!(f, "invalid UTF-8 sequence {:?}", seq),
             Self::Utf8SequenceProducedSurrogate(sur) => write!(f, "UTF-8 sequence produced surrogate 0x{:04X}", sur),
+            Self::CodePointOutOfRange(cp) => write!(f, "UTF-8 sequence produced code point 0x{:X} beyond U+10FFFF", cp),
             Self::InvalidUtf16SurrogateSequence(seq) => write!(f, "invalid UTF-16 surrogate sequence {:?}", seq),
+            Self::InvalidCommentBeginning(c) => write!(f, "invalid comment beginning: '/' followed by {:?}", c),
+            Self::UnterminatedComment => write!(f, "unterminated block comment"),
+            Self::UnexpectedByte(b) => write!(f, "unexpected byte {:?}", b),
+            Self::IllegalWhitespace(c) => write!(f, "illegal whitespace character U+{:04X}", u32::from(*c)),
+            Self::TooManyUnicodeEscapes(max_escapes) => write!(f, "string contains more than the maximum of {} Unicode escapes", max_escapes),
         }
     }
 }
@@ -64,11 +394,20 @@ impl std::error::Error for Error {
             Self::Io(e) => Some(e),
             Self::UnknownEscape(_) => None,
             Self::InvalidUnicodeEscape(_) => None,
+            Self::IncompleteUnicodeEscape(_) => None,
+            Self::UnterminatedString(_) => None,
             Self::InvalidNumberCharacter(_) => None,
+            Self::IncompleteNumber(_) => None,
             Self::InvalidBarewordBeginning(_) => None,
             Self::InvalidUtf8Sequence(_) => None,
             Self::Utf8SequenceProducedSurrogate(_) => None,
+            Self::CodePointOutOfRange(_) => None,
             Self::InvalidUtf16SurrogateSequence(_) => None,
+            Self::InvalidCommentBeginning(_) => None,
+            Self::UnterminatedComment => None,
+            Self::UnexpectedByte(_) => None,
+            Self::IllegalWhitespace(_) => None,
+            Self::TooManyUnicodeEscapes(_) => None,
         }
     }
 }
@@ -77,7 +416,17 @@ impl From<std::io::Error> for Error {
 }
 
 
-fn do_skip_whitespace<R: BufRead>(mut json_reader: R) -> Result<bool, std::io::Error> {
+fn is_whitespace(b: u8, options: &TokenizerOptions) -> bool {
+    b == 0x20
+    || b == 0x09
+    || b == 0x0A
+    || b == 0x0D
+    || (b == 0x0C && options.extra_whitespace.contains(ExtraWhitespace::FORM_FEED))
+    || (b == 0x0B && options.extra_whitespace.contains(ExtraWhitespace::VERTICAL_TAB))
+}
+
+
+fn do_skip_whitespace<R: BufRead>(mut json_reader: R, options: &TokenizerOptions) -> Result<bool, std::io::Error> {
     let peeked = json_reader.fill_buf()?;
     let peeked_len = peeked.len();
     if peeked_len == 0 {
@@ -86,12 +435,7 @@ fn do_skip_whitespace<R: BufRead>(mut json_reader: R) -> Result<bool, std::io::E
     }
 
     let first_non_whitespace = peeked.iter()
-        .position(|&b|
-            b != 0x20
-            && b != 0x09
-            && b != 0x0A
-            && b != 0x0D
-        );
+        .position(|&b| !is_whitespace(b, options));
     if let Some(fnw) = first_non_whitespace {
         // consume all the bytes until then
         json_reader.consume(fnw);
@@ -104,42 +448,422 @@ fn do_skip_whitespace<R: BufRead>(mut json_reader: R) -> Result<bool, std::io::E
     }
 }
 
-pub(crate) fn skip_whitespace<R: BufRead>(mut json_reader: R) -> Result<(), std::io::Error> {
+pub(crate) fn skip_whitespace<R: BufRead>(mut json_reader: R, options: &TokenizerOptions) -> Result<(), std::io::Error> {
     let mut repeat = true;
     while repeat {
-        repeat = do_skip_whitespace(&mut json_reader)?;
+        repeat = do_skip_whitespace(&mut json_reader, options)?;
+    }
+    Ok(())
+}
+
+
+fn skip_comment<R: BufRead>(mut json_reader: R) -> Result<(), Error> {
+    // the comment obviously starts with a slash
+    // (unless the reader returns different data on each call)
+    let slash = json_reader.read_byte().unwrap_eof()?;
+    if slash != b'/' {
+        return Err(Error::UnexpectedByte(slash));
+    }
+
+    match json_reader.read_byte()? {
+        Some(b'/') => {
+            // line comment; runs until the end of the line (the line break is whitespace)
+            loop {
+                match json_reader.peek()? {
+                    Some(b'\n') | Some(b'\r') | None => return Ok(()),
+                    Some(_) => json_reader.consume(1),
+                }
+            }
+        },
+        Some(b'*') => {
+            // block comment; runs until the first "*/"
+            let mut previous_was_star = false;
+            loop {
+                match json_reader.read_byte()? {
+                    Some(b'/') if previous_was_star => return Ok(()),
+                    Some(b) => previous_was_star = b == b'*',
+                    None => return Err(Error::UnterminatedComment),
+                }
+            }
+        },
+        Some(other) => Err(Error::InvalidCommentBeginning(other)),
+        None => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+    }
+}
+
+/// Returns whether the non-ASCII character looks like whitespace.
+fn is_unicode_whitespace(c: char) -> bool {
+    c.is_whitespace() || c == '\u{FEFF}'
+}
+
+/// Returns the number of bytes in the UTF-8 sequence that begins with the given byte.
+fn utf8_sequence_length(first_byte: u8) -> usize {
+    if first_byte & 0b1110_0000 == 0b1100_0000 {
+        2
+    } else if first_byte & 0b1111_0000 == 0b1110_0000 {
+        3
+    } else if first_byte & 0b1111_1000 == 0b1111_0000 {
+        4
+    } else {
+        1
+    }
+}
+
+/// Returns the length of the non-ASCII whitespace character at the beginning of the buffer.
+///
+/// Returns `None` if the buffer does not begin with such a character or ends in the middle of it.
+fn unicode_whitespace_length(buf: &[u8]) -> Option<usize> {
+    let first_byte = *buf.first()?;
+    if first_byte < 0x80 {
+        return None;
+    }
+    let length = utf8_sequence_length(first_byte);
+    let c = std::str::from_utf8(buf.get(..length)?).ok()?
+        .chars().next()?;
+    if is_unicode_whitespace(c) {
+        Some(length)
+    } else {
+        None
+    }
+}
+
+/// Skips whitespace and, if the options allow them, comments and non-ASCII whitespace.
+pub(crate) fn skip_insignificant<R: BufRead>(mut json_reader: R, options: &TokenizerOptions) -> Result<(), Error> {
+    while skip_insignificant_once(&mut json_reader, options)? {
     }
     Ok(())
 }
 
+/// Skips a single run of whitespace, comment or non-ASCII whitespace character, returning whether
+/// anything was skipped.
+pub(crate) fn skip_insignificant_once<R: BufRead>(mut json_reader: R, options: &TokenizerOptions) -> Result<bool, Error> {
+    let b = match json_reader.peek()? {
+        Some(b) => b,
+        None => return Ok(false),
+    };
+    if is_whitespace(b, options) {
+        skip_whitespace(&mut json_reader, options)?;
+        return Ok(true);
+    }
+    if options.allow_comments && b == b'/' {
+        skip_comment(&mut json_reader)?;
+        return Ok(true);
+    }
+    if options.allow_unicode_whitespace {
+        // a character split across buffer refills is not recognized here,
+        // but read_next_token_into will still skip it
+        if let Some(buffered) = json_reader.peek_n(3)? {
+            if let Some(length) = unicode_whitespace_length(buffered) {
+                json_reader.consume(length);
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+
+/// Validates the UTF-8 within a string as its characters are read, failing with the same errors as
+/// `interpret_string`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Utf8Validation {
+    sequence: [u8; 4],
+    // how many bytes of the sequence have been read, and how many it consists of
+    length: u8,
+    expected: u8,
+}
+impl Utf8Validation {
+    fn sequence_chars(&self) -> Vec<JsonChar> {
+        self.sequence[..usize::from(self.length)].iter()
+            .map(|b| JsonChar::Byte(*b))
+            .collect()
+    }
+
+    fn push_byte(&mut self, b: u8) -> Result<(), Error> {
+        if self.expected == 0 {
+            self.expected = if b & 0b1000_0000 == 0b0000_0000 {
+                return Ok(());
+            } else if b & 0b1110_0000 == 0b1100_0000 {
+                2
+            } else if b & 0b1111_0000 == 0b1110_0000 {
+                3
+            } else if b & 0b1111_1000 == 0b1111_0000 {
+                4
+            } else {
+                return Err(Error::InvalidUtf8Sequence(vec![JsonChar::Byte(b)]));
+            };
+            self.sequence[0] = b;
+            self.length = 1;
+            return Ok(());
+        }
+
+        if b & 0b1100_0000 != 0b1000_0000 {
+            // invalid continuation of a UTF-8 sequence
+            let mut sequence_chars = self.sequence_chars();
+            sequence_chars.push(JsonChar::Byte(b));
+            return Err(Error::InvalidUtf8Sequence(sequence_chars));
+        }
+        self.sequence[usize::from(self.length)] = b;
+        self.length += 1;
+        if self.length < self.expected {
+            return Ok(());
+        }
+
+        let sequence = &self.sequence[..usize::from(self.length)];
+        let char_value = sequence[1..].iter()
+            .fold(u32::from(sequence[0] & (0b0111_1111 >> self.expected)), |value, b| value << 6 | u32::from(b & 0b0011_1111));
+        *self = Self::default();
+        if char_value > 0x10_FFFF {
+            // four bytes can encode more than Unicode has to offer
+            return Err(Error::CodePointOutOfRange(char_value));
+        }
+        if char::from_u32(char_value).is_none() {
+            // value represents a UTF-16 surrogate -- invalid in UTF-8
+            return Err(Error::Utf8SequenceProducedSurrogate(char_value));
+        }
+        Ok(())
+    }
+
+    fn push_char(&mut self, json_char: JsonChar) -> Result<(), Error> {
+        match json_char {
+            JsonChar::Byte(b) => self.push_byte(b),
+            _ if self.expected == 0 => Ok(()),
+            other => {
+                // an escape cannot continue a UTF-8 sequence
+                let mut sequence_chars = self.sequence_chars();
+                sequence_chars.push(other);
+                Err(Error::InvalidUtf8Sequence(sequence_chars))
+            },
+        }
+    }
+
+    /// Checks that the string does not end within a UTF-8 sequence.
+    fn finish(&self) -> Result<(), Error> {
+        if self.expected == 0 {
+            Ok(())
+        } else {
+            Err(Error::InvalidUtf8Sequence(self.sequence_chars()))
+        }
+    }
+}
+
+
+/// How far a token that has not been completely fed yet has been scanned.
+///
+/// The tokenizer can only start reading a token from its beginning. When a token arrives in many
+/// chunks, it is therefore only read again once the scan finds a byte at which the tokenizer might
+/// stop (successfully or with an error); scanning only looks at the bytes that are new.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct TokenScan {
+    scanned: usize,
+    // within a string: 0 outside an escape, 1 after a backslash, n > 1 if n - 1 hex digits follow
+    escape: u8,
+    unicode_escapes: usize,
+    // within a string if options.validate_string_utf8 is set; once validation has failed, the
+    // tokenizer might stop at any byte
+    utf8: Utf8Validation,
+    utf8_failed: bool,
+}
+impl TokenScan {
+    /// Scans the bytes of the token (or comment) that have not been scanned before and returns
+    /// whether the tokenizer might stop at one of them.
+    pub fn might_end(&mut self, token: &[u8], options: &TokenizerOptions) -> bool {
+        while self.scanned < token.len() {
+            let index = self.scanned;
+            self.scanned += 1;
+            if !self.continues(token, index, options) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns whether the tokenizer certainly reads past the byte at the given index.
+    fn continues(&mut self, token: &[u8], index: usize, options: &TokenizerOptions) -> bool {
+        let b = token[index];
+        if index == 0 {
+            // nothing can end before the first byte
+            return true;
+        }
+        let previous = token[index - 1];
+        match token[0] {
+            quote@(b'"'|b'\'') if quote == b'"' || options.allow_single_quotes => match self.escape {
+                0 => {
+                    if b == b'\\' {
+                        self.escape = 1;
+                    }
+                    if options.validate_string_utf8 && b != quote {
+                        // an escape within a UTF-8 sequence fails once it has been read
+                        let fails = if b == b'\\' {
+                            self.utf8.expected != 0
+                        } else {
+                            self.utf8.push_byte(b).is_err()
+                        };
+                        self.utf8_failed |= fails;
+                        if self.utf8_failed {
+                            return false;
+                        }
+                    }
+                    b != quote
+                },
+                1 => match b {
+                    b'"'|b'\\'|b'/'|b'b'|b'f'|b'n'|b'r'|b't' => {
+                        self.escape = 0;
+                        true
+                    },
+                    b'\'' if options.allow_single_quotes => {
+                        self.escape = 0;
+                        true
+                    },
+                    b'u' => {
+                        self.unicode_escapes += 1;
+                        self.escape = 5;
+                        options.max_escapes_per_string
+                            .map(|max_escapes| self.unicode_escapes <= max_escapes)
+                            .unwrap_or(true)
+                    },
+                    _ => false,
+                },
+                n => {
+                    self.escape = if n == 2 { 0 } else { n - 1 };
+                    b.is_ascii_hexdigit()
+                },
+            },
+            b'/' if options.allow_comments => match token[1] {
+                _ if index == 1 => b == b'/' || b == b'*',
+                b'/' => b != b'\n' && b != b'\r',
+                // the star that opens the comment does not close it
+                _ => !(b == b'/' && previous == b'*' && index >= 3),
+            },
+            first if options.allow_unquoted_keys && is_identifier_start(first) => is_identifier_continuation(b),
+            // a digit following a digit continues the number, unless that is a leading zero
+            b'-'|b'+'|b'.'|b'0'..=b'9' => {
+                b.is_ascii_digit() && previous.is_ascii_digit() && !matches!(&token[..index], b"0"|b"-0"|b"+0")
+            },
+            _ => false,
+        }
+    }
+}
+
+
+/// The record separator that precedes each JSON text in an RFC 7464 JSON text sequence.
+pub(crate) const RECORD_SEPARATOR: u8 = 0x1E;
+
+/// Skips bytes until the next record separator (which is not consumed) or EOF.
+pub(crate) fn skip_to_record_separator<R: BufRead>(mut json_reader: R) -> Result<(), std::io::Error> {
+    loop {
+        let peeked = json_reader.fill_buf()?;
+        let peeked_len = peeked.len();
+        if peeked_len == 0 {
+            // EOF
+            return Ok(());
+        }
+
+        if let Some(rs_index) = peeked.iter().position(|&b| b == RECORD_SEPARATOR) {
+            json_reader.consume(rs_index);
+            return Ok(());
+        }
+        json_reader.consume(peeked_len);
+    }
+}
+
+/// Wraps a reader and pretends that it ends at the next record separator.
+///
+/// This allows verifying a single record of a JSON text sequence without the tokenizer knowing
+/// about record separators.
+pub(crate) struct RecordReader<R> {
+    inner: R,
+}
+impl<R> RecordReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+impl<R: BufRead> Read for RecordReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        let available = self.fill_buf()?;
+        let read_count = available.len().min(buf.len());
+        buf[..read_count].copy_from_slice(&available[..read_count]);
+        self.consume(read_count);
+        Ok(read_count)
+    }
+}
+impl<R: BufRead> BufRead for RecordReader<R> {
+    fn fill_buf(&mut self) -> Result<&[u8], std::io::Error> {
+        let buf = self.inner.fill_buf()?;
+        match buf.iter().position(|&b| b == RECORD_SEPARATOR) {
+            Some(rs_index) => Ok(&buf[..rs_index]),
+            None => Ok(buf),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
 
-fn get_simple_token(peek: &[u8]) -> Option<JsonToken> {
-    assert!(peek.len() > 0);
-    match peek[0] {
-        b'[' => Some(JsonToken::OpeningBracket),
-        b']' => Some(JsonToken::ClosingBracket),
-        b'{' => Some(JsonToken::OpeningBrace),
-        b'}' => Some(JsonToken::ClosingBrace),
-        b':' => Some(JsonToken::Colon),
-        b',' => Some(JsonToken::Comma),
+fn get_simple_token(peek: &[u8]) -> Option<TokenView<'static>> {
+    match peek.first()? {
+        b'[' => Some(TokenView::OpeningBracket),
+        b']' => Some(TokenView::ClosingBracket),
+        b'{' => Some(TokenView::OpeningBrace),
+        b'}' => Some(TokenView::ClosingBrace),
+        b':' => Some(TokenView::Colon),
+        b',' => Some(TokenView::Comma),
         _ => None,
     }
 }
 
 
-fn read_string<R: BufRead>(mut json_reader: R) -> Result<Vec<JsonChar>, Error> {
+/// Reads a string into the buffer, which is cleared first.
+fn read_string<R: BufRead>(mut json_reader: R, options: &TokenizerOptions, string: &mut Vec<JsonChar>) -> Result<(), Error> {
     // the string obviously starts with quotation marks
+    // (unless the reader returns different data on each call)
     let start_quote = json_reader.read_byte().unwrap_eof()?;
-    assert_eq!(start_quote, b'"');
+    if start_quote != b'"' && !(options.allow_single_quotes && start_quote == b'\'') {
+        return Err(Error::UnexpectedByte(start_quote));
+    }
+
+    // reserve room for the rest of the string if it is buffered, otherwise for the whole buffer
+    // (escapes only make the string shorter than its representation)
+    let buffered = json_reader.fill_buf()?;
+    let estimated_length = buffered.iter()
+        .position(|&b| b == start_quote)
+        .unwrap_or(buffered.len());
 
     let mut escaping = false;
-    let mut string = Vec::new();
+    let mut unicode_escapes = 0;
+    let mut utf8 = Utf8Validation::default();
+    string.clear();
+    string.reserve(estimated_length);
     loop {
+        if !escaping {
+            // take the bytes up to the next quote or backslash at once
+            let buffered = json_reader.fill_buf()?;
+            let run_length = buffered.iter()
+                .position(|&b| b == start_quote || b == b'\\')
+                .unwrap_or(buffered.len());
+            if options.validate_string_utf8 {
+                for &b in &buffered[..run_length] {
+                    utf8.push_byte(b)?;
+                }
+            }
+            string.extend(buffered[..run_length].iter().map(|&b| JsonChar::Byte(b)));
+            json_reader.consume(run_length);
+        }
+
         // read a byte
-        let b = json_reader.read_byte().unwrap_eof()?;
+        let b = match json_reader.read_byte()? {
+            Some(b) => b,
+            None => return Err(Error::UnterminatedString(std::mem::take(string))),
+        };
         if escaping {
             match b {
                 b'"' => string.push(JsonChar::EscapedQuote),
+                b'\'' if options.allow_single_quotes => string.push(JsonChar::Byte(b'\'')),
                 b'\\' => string.push(JsonChar::EscapedBackslash),
                 b'/' => string.push(JsonChar::EscapedSlash),
                 b'b' => string.push(JsonChar::EscapedBackspace),
@@ -149,15 +873,29 @@ fn read_string<R: BufRead>(mut json_reader: R) -> Result<Vec<JsonChar>, Error> {
                 b't' => string.push(JsonChar::EscapedTab),
                 b'u' => {
                     // Unicode escape
+                    unicode_escapes += 1;
+                    if let Some(max_escapes) = options.max_escapes_per_string {
+                        if unicode_escapes > max_escapes {
+                            return Err(Error::TooManyUnicodeEscapes(max_escapes));
+                        }
+                    }
+
                     let mut escape_buf = [0u8; 4];
-                    json_reader.read_exact(&mut escape_buf)?;
+                    for i in 0..escape_buf.len() {
+                        match json_reader.read_byte()? {
+                            Some(b) => escape_buf[i] = b,
+                            None => return Err(Error::IncompleteUnicodeEscape(escape_buf[..i].to_vec())),
+                        }
+                    }
 
+                    // (from_str_radix alone would also accept a leading plus sign)
                     if !escape_buf.iter().all(|b| b.is_ascii_hexdigit()) {
                         return Err(Error::InvalidUnicodeEscape(escape_buf));
                     }
 
-                    let escape_str = std::str::from_utf8(&escape_buf).unwrap();
-                    let escape_value = u16::from_str_radix(escape_str, 16).unwrap();
+                    let escape_value = std::str::from_utf8(&escape_buf).ok()
+                        .and_then(|escape_str| u16::from_str_radix(escape_str, 16).ok())
+                        .ok_or(Error::InvalidUnicodeEscape(escape_buf))?;
                     string.push(JsonChar::UnicodeEscape(escape_value));
                 },
                 other => return Err(Error::UnknownEscape(other)),
@@ -165,17 +903,103 @@ fn read_string<R: BufRead>(mut json_reader: R) -> Result<Vec<JsonChar>, Error> {
             escaping = false;
         } else {
             match b {
-                b'"' => break,
+                b if b == start_quote => break,
                 b'\\' => escaping = true,
                 other => string.push(JsonChar::Byte(other)),
             }
         }
+
+        // UTF-16 errors are left to interpret_string
+        if options.validate_string_utf8 && !escaping {
+            let last = *string.last()
+                .expect("character read but not stored");
+            utf8.push_char(last)?;
+        }
+    }
+
+    if options.validate_string_utf8 {
+        utf8.finish()?;
+    }
+    if options.validate_surrogates {
+        check_surrogates(string)?;
+    }
+    Ok(())
+}
+
+
+/// Checks that each leading surrogate escape is immediately followed by a trailing surrogate escape
+/// and that each trailing surrogate escape is preceded by a leading one.
+fn check_surrogates(json_chars: &[JsonChar]) -> Result<(), Error> {
+    let mut iter = json_chars.iter();
+    while let Some(json_char) = iter.next() {
+        let u = match json_char {
+            JsonChar::UnicodeEscape(u) => *u,
+            _ => continue,
+        };
+        if u >= 0xD800 && u <= 0xDBFF {
+            match iter.next() {
+                Some(JsonChar::UnicodeEscape(u2)) if *u2 >= 0xDC00 && *u2 <= 0xDFFF => {},
+                Some(other) => return Err(Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u), *other])),
+                None => return Err(Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u)])),
+            }
+        } else if u >= 0xDC00 && u <= 0xDFFF {
+            return Err(Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u)]));
+        }
+    }
+    Ok(())
+}
+
+
+/// Appends the decimal digits at the current position to the buffer and advances past them.
+///
+/// Whole runs of digits are taken from the reader's buffer at once, which is faster than going
+/// through the number state machine for each digit.
+fn take_digits<R: BufRead>(mut json_reader: R, number_buf: &mut Vec<u8>) -> Result<(), std::io::Error> {
+    loop {
+        let buf = json_reader.fill_buf()?;
+        let buf_len = buf.len();
+        let digit_count = buf.iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        number_buf.extend_from_slice(&buf[..digit_count]);
+        json_reader.consume(digit_count);
+        if digit_count < buf_len || buf_len == 0 {
+            // a non-digit or EOF follows
+            return Ok(());
+        }
     }
-    Ok(string)
 }
 
 
-fn read_number_string<R: BufRead>(mut json_reader: R) -> Result<Vec<u8>, Error> {
+/// Continues a number across a decimal comma (e.g. `3,14`) if a comma directly follows it.
+///
+/// A comma that is not directly followed by a digit is consumed nonetheless; `Ok(true)` is then
+/// returned to signal that the comma was a separator after all. The comma is kept as written.
+pub(crate) fn take_decimal_comma<R: BufRead>(mut json_reader: R, number_buf: &mut Vec<u8>) -> Result<bool, std::io::Error> {
+    if json_reader.peek()? != Some(b',') {
+        return Ok(false);
+    }
+    json_reader.consume(1);
+    match json_reader.peek()? {
+        Some(b) if b.is_ascii_digit() => {
+            number_buf.push(b',');
+            take_digits(&mut json_reader, number_buf)?;
+            Ok(false)
+        },
+        _ => Ok(true),
+    }
+}
+
+
+pub(crate) fn read_number_string<R: BufRead>(json_reader: R, options: &TokenizerOptions) -> Result<Vec<u8>, Error> {
+    let mut number_buf = Vec::new();
+    read_number_string_into(json_reader, options, &mut number_buf)?;
+    Ok(number_buf)
+}
+
+
+/// Reads a number into the buffer, which is cleared first.
+fn read_number_string_into<R: BufRead>(mut json_reader: R, options: &TokenizerOptions, number_buf: &mut Vec<u8>) -> Result<(), Error> {
     enum ParserState {
         ExpectMinusOrZeroOrInitialMantissa,
         ExpectInitialMantissa,
@@ -186,19 +1010,36 @@ fn read_number_string<R: BufRead>(mut json_reader: R) -> Result<Vec<u8>, Error>
         ExpectEPlusMinusOrInitialExponent,
         ExpectInitialExponent,
         ExpectExponent,
+        ExpectInitialHexDigit,
+        ExpectHexDigit,
     }
     let mut state = ParserState::ExpectMinusOrZeroOrInitialMantissa;
 
-    let mut number_buf = Vec::new();
+    // JSON5 allows a decimal point after the integer part without any fractional digits
+    let trailing_dot_state = || if options.allow_json5_numbers {
+        ParserState::ExpectFractionalOrE
+    } else {
+        ParserState::ExpectFractional
+    };
+
+    number_buf.clear();
+    let accepts_anyway = |number_buf: &[u8], b: u8| {
+        matches!(&options.accept_number_byte, Some(NumberBytePredicate(accept)) if accept(number_buf, b))
+    };
 
     loop {
         match state {
             ParserState::ExpectMinusOrZeroOrInitialMantissa => {
                 // in this state, a character is required
-                let b = json_reader.read_byte().unwrap_eof()?;
-                if b == b'-' {
+                let b = json_reader.read_byte()?
+                    .ok_or(Error::IncompleteNumber(NumberPart::Mantissa))?;
+                if b == b'-' || (options.allow_leading_plus && b == b'+') {
                     number_buf.push(b);
                     state = ParserState::ExpectInitialMantissa;
+                } else if options.allow_json5_numbers && b == b'.' {
+                    // leading decimal point => the fractional part must contain a digit
+                    number_buf.push(b);
+                    state = ParserState::ExpectFractional;
                 } else if b == b'0' {
                     // no leading zeroes => this must be followed by dot or E (or EOF)
                     number_buf.push(b);
@@ -206,20 +1047,30 @@ fn read_number_string<R: BufRead>(mut json_reader: R) -> Result<Vec<u8>, Error>
                 } else if b >= b'1' && b <= b'9' {
                     number_buf.push(b);
                     state = ParserState::ExpectMantissaOrDotOrE;
+                } else if accepts_anyway(number_buf, b) {
+                    number_buf.push(b);
+                    // same state
                 } else {
                     return Err(Error::InvalidNumberCharacter(b));
                 }
             },
             ParserState::ExpectInitialMantissa => {
                 // in this state, a character is required
-                let b = json_reader.read_byte().unwrap_eof()?;
-                if b == b'0' {
+                let b = json_reader.read_byte()?
+                    .ok_or(Error::IncompleteNumber(NumberPart::Mantissa))?;
+                if options.allow_json5_numbers && b == b'.' {
+                    number_buf.push(b);
+                    state = ParserState::ExpectFractional;
+                } else if b == b'0' {
                     // no leading zeroes => this must be followed by dot or E (or EOF)
                     number_buf.push(b);
                     state = ParserState::ExpectDotOrE;
                 } else if b >= b'1' && b <= b'9' {
                     number_buf.push(b);
                     state = ParserState::ExpectMantissaOrDotOrE;
+                } else if accepts_anyway(number_buf, b) {
+                    number_buf.push(b);
+                    // same state
                 } else {
                     return Err(Error::InvalidNumberCharacter(b));
                 }
@@ -231,106 +1082,154 @@ fn read_number_string<R: BufRead>(mut json_reader: R) -> Result<Vec<u8>, Error>
                         if b == b'.' {
                             number_buf.push(b);
                             json_reader.consume(1);
-                            state = ParserState::ExpectFractional;
+                            state = trailing_dot_state();
+                        } else if options.allow_json5_numbers && (b == b'x' || b == b'X') {
+                            number_buf.push(b);
+                            json_reader.consume(1);
+                            state = ParserState::ExpectInitialHexDigit;
                         } else if b == b'E' || b == b'e' {
                             number_buf.push(b);
                             json_reader.consume(1);
                             state = ParserState::ExpectEPlusMinusOrInitialExponent;
+                        } else if accepts_anyway(number_buf, b) {
+                            number_buf.push(b);
+                            json_reader.consume(1);
+                            // same state
                         } else {
-                            return Ok(number_buf);
+                            return Ok(());
                         }
                     },
-                    None => return Ok(number_buf),
+                    None => return Ok(()),
                 }
             },
             ParserState::ExpectMantissaOrDotOrE => {
                 // in this state, a character is optional
+                take_digits(&mut json_reader, number_buf)?;
                 match json_reader.peek()? {
                     Some(b) => {
-                        if b >= b'0' && b <= b'9' {
-                            number_buf.push(b);
-                            json_reader.consume(1);
-                            state = ParserState::ExpectMantissaOrDotOrE;
-                        } else if b == b'.' {
+                        if b == b'.' {
                             number_buf.push(b);
                             json_reader.consume(1);
-                            state = ParserState::ExpectFractional;
+                            state = trailing_dot_state();
                         } else if b == b'E' || b == b'e' {
                             number_buf.push(b);
                             json_reader.consume(1);
                             state = ParserState::ExpectEPlusMinusOrInitialExponent;
+                        } else if accepts_anyway(number_buf, b) {
+                            number_buf.push(b);
+                            json_reader.consume(1);
+                            // same state
                         } else {
-                            return Ok(number_buf);
+                            return Ok(());
                         }
                     },
-                    None => return Ok(number_buf),
+                    None => return Ok(()),
                 }
             },
             ParserState::ExpectFractional => {
                 // in this state, a character is required
-                let b = json_reader.read_byte().unwrap_eof()?;
+                let b = json_reader.read_byte()?
+                    .ok_or(Error::IncompleteNumber(NumberPart::Fraction))?;
                 if b >= b'0' && b <= b'9' {
                     number_buf.push(b);
                     state = ParserState::ExpectFractionalOrE;
+                } else if accepts_anyway(number_buf, b) {
+                    number_buf.push(b);
+                    // same state
                 } else {
                     return Err(Error::InvalidNumberCharacter(b));
                 }
             },
             ParserState::ExpectFractionalOrE => {
                 // in this state, a character is optional
+                take_digits(&mut json_reader, number_buf)?;
                 match json_reader.peek()? {
                     Some(b) => {
-                        if b >= b'0' && b <= b'9' {
+                        if b == b'E' || b == b'e' {
                             number_buf.push(b);
                             json_reader.consume(1);
-                            // same state
-                        } else if b == b'E' || b == b'e' {
+                            state = ParserState::ExpectEPlusMinusOrInitialExponent;
+                        } else if accepts_anyway(number_buf, b) {
                             number_buf.push(b);
                             json_reader.consume(1);
-                            state = ParserState::ExpectEPlusMinusOrInitialExponent;
+                            // same state
                         } else {
-                            return Ok(number_buf);
+                            return Ok(());
                         }
                     },
-                    None => return Ok(number_buf),
+                    None => return Ok(()),
                 }
             },
             ParserState::ExpectEPlusMinusOrInitialExponent => {
                 // in this state, a character is required
-                let b = json_reader.read_byte().unwrap_eof()?;
+                let b = json_reader.read_byte()?
+                    .ok_or(Error::IncompleteNumber(NumberPart::Exponent))?;
                 if b == b'+' || b == b'-' {
                     number_buf.push(b);
                     state = ParserState::ExpectInitialExponent;
                 } else if b >= b'0' && b <= b'9' {
                     number_buf.push(b);
                     state = ParserState::ExpectExponent;
+                } else if accepts_anyway(number_buf, b) {
+                    number_buf.push(b);
+                    // same state
                 } else {
                     return Err(Error::InvalidNumberCharacter(b));
                 }
             },
             ParserState::ExpectInitialExponent => {
                 // in this state, a character is required
-                let b = json_reader.read_byte().unwrap_eof()?;
+                let b = json_reader.read_byte()?
+                    .ok_or(Error::IncompleteNumber(NumberPart::Exponent))?;
                 if b >= b'0' && b <= b'9' {
                     number_buf.push(b);
                     state = ParserState::ExpectExponent;
+                } else if accepts_anyway(number_buf, b) {
+                    number_buf.push(b);
+                    // same state
                 } else {
                     return Err(Error::InvalidNumberCharacter(b));
                 }
             },
             ParserState::ExpectExponent => {
+                // in this state, a character is optional
+                take_digits(&mut json_reader, number_buf)?;
+                match json_reader.peek()? {
+                    Some(b) if accepts_anyway(number_buf, b) => {
+                        number_buf.push(b);
+                        json_reader.consume(1);
+                        // same state
+                    },
+                    _ => return Ok(()),
+                }
+            },
+            ParserState::ExpectInitialHexDigit => {
+                // in this state, a character is required
+                let b = json_reader.read_byte()?
+                    .ok_or(Error::IncompleteNumber(NumberPart::Mantissa))?;
+                if b.is_ascii_hexdigit() {
+                    number_buf.push(b);
+                    state = ParserState::ExpectHexDigit;
+                } else if accepts_anyway(number_buf, b) {
+                    number_buf.push(b);
+                    // same state
+                } else {
+                    return Err(Error::InvalidNumberCharacter(b));
+                }
+            },
+            ParserState::ExpectHexDigit => {
                 // in this state, a character is optional
                 match json_reader.peek()? {
                     Some(b) => {
-                        if b >= b'0' && b <= b'9' {
+                        if b.is_ascii_hexdigit() || accepts_anyway(number_buf, b) {
                             number_buf.push(b);
                             json_reader.consume(1);
                             // same state
                         } else {
-                            return Ok(number_buf);
+                            return Ok(());
                         }
                     },
-                    None => return Ok(number_buf),
+                    None => return Ok(()),
                 }
             },
         }
@@ -338,61 +1237,446 @@ fn read_number_string<R: BufRead>(mut json_reader: R) -> Result<Vec<u8>, Error>
 }
 
 
-pub fn read_next_token<R: BufRead>(mut json_reader: R) -> Result<Option<JsonToken>, Error> {
-    skip_whitespace(&mut json_reader)?;
-    let peek = json_reader.fill_buf()?;
-    if peek.len() == 0 {
-        // EOF
-        return Ok(None);
+fn is_identifier_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'$'
+}
+
+fn is_identifier_continuation(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
+
+/// Reads an identifier into the buffer, which is cleared first. Returns a keyword token if the
+/// identifier is a keyword.
+fn read_identifier<R: BufRead>(mut json_reader: R, identifier: &mut Vec<u8>) -> Result<TokenView<'_>, Error> {
+    identifier.clear();
+    while let Some(b) = json_reader.peek()? {
+        let valid = if identifier.len() == 0 {
+            is_identifier_start(b)
+        } else {
+            is_identifier_continuation(b)
+        };
+        if !valid {
+            break;
+        }
+        identifier.push(b);
+        json_reader.consume(1);
     }
 
-    if let Some(simple_token) = get_simple_token(peek) {
+    match identifier.as_slice() {
+        b"" => Err(Error::InvalidBarewordBeginning(String::new())),
+        b"true" => Ok(TokenView::True),
+        b"false" => Ok(TokenView::False),
+        b"null" => Ok(TokenView::Null),
+        identifier => Ok(TokenView::Identifier(identifier)),
+    }
+}
+
+
+fn bareword_to_string(bareword: &[u8]) -> String {
+    bareword.iter()
+        .map(|&b| char::from(b))
+        .collect()
+}
+
+
+/// Returns whether the byte may directly follow a bareword.
+fn ends_bareword(b: u8, options: &TokenizerOptions) -> bool {
+    is_whitespace(b, options)
+    || b == b'[' || b == b']'
+    || b == b'{' || b == b'}'
+    || b == b':' || b == b','
+    || (options.allow_comments && b == b'/')
+}
+
+
+/// Skips the remainder of a malformed token, i.e. everything up to the next whitespace or
+/// punctuation.
+pub(crate) fn skip_to_token_boundary<R: BufRead>(mut json_reader: R, options: &TokenizerOptions) -> Result<(), std::io::Error> {
+    while let Some(b) = json_reader.peek()? {
+        if ends_bareword(b, options) {
+            break;
+        }
         json_reader.consume(1);
-        return Ok(Some(simple_token));
     }
+    Ok(())
+}
+
 
-    if peek[0] == b'"' {
-        // a string begins!
-        let string = read_string(json_reader)?;
-        return Ok(Some(JsonToken::String(string)));
+/// Reads a non-ASCII character that appears where a token should begin.
+fn read_stray_character<R: BufRead>(mut json_reader: R) -> Result<char, Error> {
+    let first_byte = json_reader.read_byte().unwrap_eof()?;
+    let mut bytes = vec![first_byte];
+    for _ in 1..utf8_sequence_length(first_byte) {
+        match json_reader.peek()? {
+            Some(b) if b & 0b1100_0000 == 0b1000_0000 => {
+                bytes.push(b);
+                json_reader.consume(1);
+            },
+            _ => break,
+        }
     }
+    std::str::from_utf8(&bytes).ok()
+        .and_then(|s| s.chars().next())
+        .ok_or_else(|| Error::InvalidBarewordBeginning(bareword_to_string(&bytes)))
+}
 
-    // a number always begins with either a minus or a decimal digit
-    if peek[0] == b'-' || (peek[0] >= b'0' && peek[0] <= b'9') {
-        let number = read_number_string(json_reader)?;
-        return Ok(Some(JsonToken::Number(number)));
+
+fn read_bareword<R: BufRead>(mut json_reader: R, options: &TokenizerOptions, bareword: &mut Vec<u8>) -> Result<TokenView<'static>, Error> {
+    const BAREWORDS: [&[u8]; 3] = [b"true", b"false", b"null"];
+    const MAX_REPORTED_BAREWORD_LENGTH: usize = 64;
+
+    // read byte by byte as long as we are on the way to a known bareword
+    bareword.clear();
+    loop {
+        let token = match bareword.as_slice() {
+            b"true" => Some(TokenView::True),
+            b"false" => Some(TokenView::False),
+            b"null" => Some(TokenView::Null),
+            _ => None,
+        };
+        if let Some(token) = token {
+            // the bareword must not continue (e.g. "trueish")
+            let known_length = bareword.len();
+            loop {
+                match json_reader.peek()? {
+                    Some(b) if !ends_bareword(b, options) => {
+                        if bareword.len() >= MAX_REPORTED_BAREWORD_LENGTH {
+                            return Err(Error::InvalidBarewordBeginning(bareword_to_string(bareword)));
+                        }
+                        bareword.push(b);
+                        json_reader.consume(1);
+                    },
+                    _ => break,
+                }
+            }
+            if bareword.len() > known_length {
+                return Err(Error::InvalidBarewordBeginning(bareword_to_string(bareword)));
+            }
+            return Ok(token);
+        }
+
+        let is_known_prefix = BAREWORDS.iter()
+            .any(|known| known.starts_with(bareword));
+        if !is_known_prefix {
+            // e.g. "falsx"
+            return Err(Error::InvalidBarewordBeginning(bareword_to_string(bareword)));
+        }
+
+        match json_reader.peek()? {
+            Some(b) => {
+                // a delimiter is reported as part of the bareword but left for the next token
+                bareword.push(b);
+                if !ends_bareword(b, options) {
+                    json_reader.consume(1);
+                }
+            },
+            None => {
+                // e.g. "tr" at the end of the input
+                return Err(Error::InvalidBarewordBeginning(bareword_to_string(bareword)));
+            },
+        }
     }
+}
+
+
+pub fn read_next_token<R: BufRead>(json_reader: R) -> Result<Option<JsonToken>, Error> {
+    read_next_token_with(json_reader, &TokenizerOptions::default())
+}
+
 
-    // otherwise, it must be a bareword
-    // the shortest barewords are 4 characters long (true or null)
-    let mut buf = [0u8; 4];
-    json_reader.read_exact(&mut buf)?;
-    if &buf == b"true" {
-        return Ok(Some(JsonToken::True));
-    } else if &buf == b"null" {
-        return Ok(Some(JsonToken::Null));
-    } else if &buf == b"fals" {
-        let mut sub_buf = [0u8];
-        json_reader.read_exact(&mut sub_buf)?;
-        if sub_buf[0] == b'e' {
-            return Ok(Some(JsonToken::False));
+pub fn read_next_token_with<R: BufRead>(json_reader: R, options: &TokenizerOptions) -> Result<Option<JsonToken>, Error> {
+    // the contents are moved out of the buffer, so each token still allocates only once
+    let mut buffer = TokenBuffer::new();
+    let token = match read_next_token_into(json_reader, options, &mut buffer)? {
+        None => return Ok(None),
+        Some(TokenView::String(_)) => JsonToken::String(std::mem::take(&mut buffer.chars)),
+        Some(TokenView::Number(_)) => JsonToken::Number(std::mem::take(&mut buffer.bytes)),
+        Some(TokenView::Identifier(_)) => JsonToken::Identifier(std::mem::take(&mut buffer.bytes)),
+        Some(view) => view.to_token(),
+    };
+    Ok(Some(token))
+}
+
+
+/// Reads the next token into the buffer and returns a view of it.
+///
+/// Unlike `read_next_token_with`, strings, numbers and identifiers are not returned in freshly
+/// allocated vectors but in the buffer, which can be reused for each token.
+pub fn read_next_token_into<'b, R: BufRead>(mut json_reader: R, options: &TokenizerOptions, buffer: &'b mut TokenBuffer) -> Result<Option<TokenView<'b>>, Error> {
+    loop {
+        skip_insignificant(&mut json_reader, options)?;
+        let peek = json_reader.fill_buf()?;
+        if peek.len() == 0 {
+            // EOF
+            return Ok(None);
         }
 
-        // e.g. "falsx"
-        let mut bareword_begin = "fals".to_owned();
-        bareword_begin.push(char::from_u32(sub_buf[0] as u32).unwrap());
-        return Err(Error::InvalidBarewordBeginning(bareword_begin));
-    } else {
-        // some completely different bareword or sequence of symbols
-        let mut bareword_begin = String::with_capacity(4);
-        for b in buf {
-            bareword_begin.push(char::from_u32(b as u32).unwrap());
+        if let Some(simple_token) = get_simple_token(peek) {
+            json_reader.consume(1);
+            return Ok(Some(simple_token));
+        }
+
+        if peek[0] == b'"' || (options.allow_single_quotes && peek[0] == b'\'') {
+            // a string begins!
+            read_string(&mut json_reader, options, &mut buffer.chars)?;
+            return Ok(Some(TokenView::String(&buffer.chars)));
+        }
+
+        // a number always begins with either a minus or a decimal digit
+        // (or a plus or decimal point, if allowed)
+        if peek[0] == b'-' || (peek[0] >= b'0' && peek[0] <= b'9')
+                || (options.allow_leading_plus && peek[0] == b'+')
+                || (options.allow_json5_numbers && peek[0] == b'.') {
+            read_number_string_into(&mut json_reader, options, &mut buffer.bytes)?;
+            return Ok(Some(TokenView::Number(&buffer.bytes)));
         }
-        return Err(Error::InvalidBarewordBeginning(bareword_begin));
+
+        if options.allow_unquoted_keys && is_identifier_start(peek[0]) {
+            let identifier = read_identifier(&mut json_reader, &mut buffer.bytes)?;
+            return Ok(Some(identifier));
+        }
+
+        if peek[0] >= 0x80 {
+            // no token begins with a non-ASCII character; perhaps it is whitespace from outside ASCII
+            let c = read_stray_character(&mut json_reader)?;
+            if !is_unicode_whitespace(c) {
+                return Err(Error::InvalidBarewordBeginning(c.to_string()));
+            }
+            if !options.allow_unicode_whitespace {
+                return Err(Error::IllegalWhitespace(c));
+            }
+            // a whitespace character split across buffer refills; look again
+            continue;
+        }
+
+        // otherwise, it must be a bareword
+        let bareword = read_bareword(&mut json_reader, options, &mut buffer.bytes)?;
+        return Ok(Some(bareword));
     }
 }
 
 
+/// How the tokenizer proceeds after encountering a malformed token.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RecoveryStrategy {
+    /// Return the error.
+    #[default]
+    Fail,
+
+    /// Report the error, skip the remainder of the malformed token (the rest of a string up to its
+    /// closing quote, otherwise everything up to the next whitespace or punctuation) and continue
+    /// with the next token.
+    SkipToBoundary,
+}
+
+
+/// Reads the next token, proceeding after malformed tokens according to the recovery strategy.
+///
+/// Each error that is recovered from is passed to the callback. I/O errors and errors caused by the
+/// input ending within a token (`Error::UnterminatedString`, `Error::IncompleteUnicodeEscape` and
+/// `Error::UnterminatedComment`) are always returned, since nothing follows that could be
+/// recovered, as is every error if the strategy is `RecoveryStrategy::Fail`.
+pub fn read_next_token_recovering<R: BufRead, F: FnMut(Error)>(
+    mut json_reader: R,
+    options: &TokenizerOptions,
+    recovery: RecoveryStrategy,
+    mut on_error: F,
+) -> Result<Option<JsonToken>, Error> {
+    if recovery == RecoveryStrategy::Fail {
+        return read_next_token_with(json_reader, options);
+    }
+
+    loop {
+        let mut string_closed = false;
+        let result = match skip_insignificant(&mut json_reader, options) {
+            Err(e) => Err(e),
+            Ok(()) => match json_reader.peek()? {
+                Some(quote) if quote == b'"' || (options.allow_single_quotes && quote == b'\'') => {
+                    // take the whole string first; whitespace and punctuation within it are no
+                    // boundaries to recover at
+                    let raw_string;
+                    (raw_string, string_closed) = read_raw_string(&mut json_reader, quote)?;
+                    read_next_token_with(&raw_string[..], options)
+                },
+                _ => read_next_token_with(&mut json_reader, options),
+            },
+        };
+        let error = match result {
+            Err(Error::Io(e)) => return Err(Error::Io(e)),
+            // the remainder of the string has been skipped already
+            Err(e) if string_closed => {
+                on_error(e);
+                continue;
+            },
+            Err(e @ (Error::UnterminatedString(_)|Error::IncompleteUnicodeEscape(_)|Error::UnterminatedComment)) => return Err(e),
+            Err(e) => e,
+            other => return other,
+        };
+        on_error(error);
+        skip_to_token_boundary(&mut json_reader, options)?;
+    }
+}
+
+
+/// Reads a string as it is written, up to and including the closing quote, returning it along with
+/// whether the closing quote was found before EOF.
+fn read_raw_string<R: BufRead>(mut json_reader: R, quote: u8) -> Result<(Vec<u8>, bool), std::io::Error> {
+    let mut raw_string = Vec::new();
+    let mut escaping = false;
+    while let Some(b) = json_reader.read_byte()? {
+        raw_string.push(b);
+        if escaping {
+            escaping = false;
+        } else if b == b'\\' {
+            escaping = true;
+        } else if b == quote && raw_string.len() > 1 {
+            return Ok((raw_string, true));
+        }
+    }
+    Ok((raw_string, false))
+}
+
+
+/// A stream of tokens with one token of lookahead.
+pub struct TokenStream<R> {
+    json_reader: R,
+    options: TokenizerOptions,
+    peeked: Option<Result<Option<JsonToken>, Error>>,
+}
+impl<R: BufRead> TokenStream<R> {
+    pub fn new(json_reader: R) -> Self {
+        Self::with_options(json_reader, TokenizerOptions::default())
+    }
+
+    pub fn with_options(json_reader: R, options: TokenizerOptions) -> Self {
+        Self {
+            json_reader,
+            options,
+            peeked: None,
+        }
+    }
+
+    /// Returns the next token without consuming it.
+    ///
+    /// If reading the token fails, the error is returned again by the next call to this function or
+    /// to `next_token`.
+    pub fn peek_token(&mut self) -> Result<Option<&JsonToken>, &Error> {
+        let json_reader = &mut self.json_reader;
+        let options = &self.options;
+        self.peeked
+            .get_or_insert_with(|| read_next_token_with(json_reader, options))
+            .as_ref()
+            .map(|token| token.as_ref())
+    }
+
+    /// Returns the next token and advances past it.
+    pub fn next_token(&mut self) -> Result<Option<JsonToken>, Error> {
+        match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => read_next_token_with(&mut self.json_reader, &self.options),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.json_reader
+    }
+
+    /// Returns an iterator over the remaining tokens in which strings are decoded.
+    pub fn decoded(self) -> DecodedTokens<R> {
+        DecodedTokens {
+            tokens: self,
+        }
+    }
+}
+impl<R: BufRead> Iterator for TokenStream<R> {
+    type Item = Result<JsonToken, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+
+/// A token whose string, if it is one, has been decoded.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DecodedToken {
+    OpeningBracket,
+    ClosingBracket,
+    OpeningBrace,
+    ClosingBrace,
+    Colon,
+    Comma,
+    String(String),
+    Number(Vec<u8>),
+    Identifier(Vec<u8>),
+    Null,
+    False,
+    True,
+}
+impl TryFrom<JsonToken> for DecodedToken {
+    type Error = Error;
+
+    fn try_from(value: JsonToken) -> Result<Self, Self::Error> {
+        Ok(match value {
+            JsonToken::OpeningBracket => Self::OpeningBracket,
+            JsonToken::ClosingBracket => Self::ClosingBracket,
+            JsonToken::OpeningBrace => Self::OpeningBrace,
+            JsonToken::ClosingBrace => Self::ClosingBrace,
+            JsonToken::Colon => Self::Colon,
+            JsonToken::Comma => Self::Comma,
+            JsonToken::String(s) => Self::String(interpret_string(&s)?),
+            JsonToken::Number(n) => Self::Number(n),
+            JsonToken::Identifier(i) => Self::Identifier(i),
+            JsonToken::Null => Self::Null,
+            JsonToken::False => Self::False,
+            JsonToken::True => Self::True,
+        })
+    }
+}
+
+
+/// An iterator over tokens that decodes each string as it is reached; see `TokenStream::decoded`.
+pub struct DecodedTokens<R> {
+    tokens: TokenStream<R>,
+}
+impl<R> DecodedTokens<R> {
+    pub fn into_inner(self) -> TokenStream<R> {
+        self.tokens
+    }
+}
+impl<R: BufRead> Iterator for DecodedTokens<R> {
+    type Item = Result<DecodedToken, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.tokens.next()?;
+        Some(token.and_then(DecodedToken::try_from))
+    }
+}
+
+
+/// Reads the next token along with its span.
+///
+/// Since the span of a token is the exact range of bytes it occupies in the document (excluding any
+/// surrounding whitespace), it can be used to reproduce the token exactly as it was written.
+pub fn read_next_spanned_token<R: BufRead>(json_reader: &mut PositionReader<R>, options: &TokenizerOptions) -> Result<Option<SpannedToken>, Error> {
+    skip_insignificant(&mut *json_reader, options)?;
+    let start = json_reader.position();
+    let token = match read_next_token_with(&mut *json_reader, options)? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let end = json_reader.position();
+    Ok(Some(SpannedToken {
+        token,
+        span: Span {
+            start,
+            end,
+        },
+    }))
+}
+
+
 fn get_next_json_char_byte<'a, I: Iterator<Item = &'a JsonChar>>(previous_bytes: &[u8], iter: &mut I) -> Result<u8, Error> {
     match iter.next() {
         Some(JsonChar::Byte(b2)) if *b2 & 0b1100_0000 == 0b1000_0000 => Ok(*b2),
@@ -415,20 +1699,88 @@ fn get_next_json_char_byte<'a, I: Iterator<Item = &'a JsonChar>>(previous_bytes:
 }
 
 
+/// How `interpret_string_with` handles a `\u` escape of a surrogate that is not part of a pair.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SurrogatePolicy {
+    /// Fail with `Error::InvalidUtf16SurrogateSequence`.
+    #[default]
+    Reject,
+
+    /// Replace the surrogate with U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+}
+
+
+/// Options for decoding string tokens.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct InterpretOptions {
+    /// How a leading surrogate without a trailing surrogate, or a trailing surrogate without a
+    /// leading surrogate, is handled.
+    pub surrogate_policy: SurrogatePolicy,
+}
+
+
+/// Decodes the characters of a string token into a Rust string.
+///
+/// This function never panics; invalid UTF-8 and UTF-16 sequences are reported as errors.
 pub fn interpret_string(json_chars: &[JsonChar]) -> Result<String, Error> {
-    let mut chars = Vec::with_capacity(json_chars.len());
+    interpret_string_with(json_chars, &InterpretOptions::default())
+}
+
+
+/// Decodes the characters of a string token into a Rust string using the given options.
+pub fn interpret_string_with(json_chars: &[JsonChar], options: &InterpretOptions) -> Result<String, Error> {
+    decode_json_chars(&mut json_chars.iter(), options)
+}
+
+
+/// Returns the number of bytes the character occupies within a string token.
+///
+/// An escaped single quote (`\'`) cannot be told apart from an unescaped one and is counted as one
+/// byte.
+fn encoded_length(json_char: &JsonChar) -> u64 {
+    match json_char {
+        JsonChar::Byte(_) => 1,
+        JsonChar::UnicodeEscape(_) => 6,
+        _ => 2,
+    }
+}
+
+
+/// Returns the offset of the invalid UTF-8 sequence reported by `interpret_string` relative to the
+/// beginning of the string token (including its opening quote).
+///
+/// Returns `None` if `interpret_string` does not fail with `Error::InvalidUtf8Sequence`.
+pub(crate) fn invalid_utf8_offset(json_chars: &[JsonChar]) -> Option<u64> {
+    let mut iter = json_chars.iter();
+    match decode_json_chars(&mut iter, &InterpretOptions::default()) {
+        Err(Error::InvalidUtf8Sequence(sequence)) => {
+            // the sequence ends at the last character taken from the iterator
+            let taken_count = json_chars.len() - iter.as_slice().len();
+            let sequence_index = taken_count - sequence.len();
+            let preceding_length: u64 = json_chars[..sequence_index].iter()
+                .map(encoded_length)
+                .sum();
+            Some(1 + preceding_length)
+        },
+        _ => None,
+    }
+}
+
+
+fn decode_json_chars(iter: &mut std::slice::Iter<'_, JsonChar>, options: &InterpretOptions) -> Result<String, Error> {
+    let mut chars = Vec::with_capacity(iter.len());
 
-    let mut iter = json_chars.into_iter();
     while let Some(json_char) = iter.next() {
         match *json_char {
             JsonChar::Byte(b) => {
                 // process as UTF-8
                 if b & 0b1000_0000 == 0b0000_0000 {
                     // 0bbb_bbbb
-                    chars.push(char::from_u32(b.into()).unwrap());
+                    chars.push(char::from(b));
                 } else if b & 0b1110_0000 == 0b1100_0000 {
                     // 110b_bbbb 10bb_bbbb
-                    let b2 = get_next_json_char_byte(&[b], &mut iter)?;
+                    let b2 = get_next_json_char_byte(&[b], iter)?;
                     let char_value =
                         u32::from(b & 0b0001_1111) << 6
                         | u32::from(b2 & 0b0011_1111) << 0
@@ -443,8 +1795,8 @@ pub fn interpret_string(json_chars: &[JsonChar]) -> Result<String, Error> {
                     chars.push(c);
                 } else if b & 0b1111_0000 == 0b1110_0000 {
                     // 1110_bbbb 10bb_bbbb 10bb_bbbb
-                    let b2 = get_next_json_char_byte(&[b], &mut iter)?;
-                    let b3 = get_next_json_char_byte(&[b, b2], &mut iter)?;
+                    let b2 = get_next_json_char_byte(&[b], iter)?;
+                    let b3 = get_next_json_char_byte(&[b, b2], iter)?;
                     let char_value =
                         u32::from(b & 0b0000_1111) << 12
                         | u32::from(b2 & 0b0011_1111) << 6
@@ -460,15 +1812,19 @@ pub fn interpret_string(json_chars: &[JsonChar]) -> Result<String, Error> {
                     chars.push(c);
                 } else if b & 0b1111_1000 == 0b1111_0000 {
                     // 1111_0bbb 10bb_bbbb 10bb_bbbb 10bb_bbbb
-                    let b2 = get_next_json_char_byte(&[b], &mut iter)?;
-                    let b3 = get_next_json_char_byte(&[b, b2], &mut iter)?;
-                    let b4 = get_next_json_char_byte(&[b, b2, b3], &mut iter)?;
+                    let b2 = get_next_json_char_byte(&[b], iter)?;
+                    let b3 = get_next_json_char_byte(&[b, b2], iter)?;
+                    let b4 = get_next_json_char_byte(&[b, b2, b3], iter)?;
                     let char_value =
                         u32::from(b & 0b0000_0111) << 18
                         | u32::from(b2 & 0b0011_1111) << 12
                         | u32::from(b3 & 0b0011_1111) << 6
                         | u32::from(b4 & 0b0011_1111) << 0
                     ;
+                    if char_value > 0x10_FFFF {
+                        // four bytes can encode more than Unicode has to offer
+                        return Err(Error::CodePointOutOfRange(char_value));
+                    }
                     let c = match char::from_u32(char_value) {
                         Some(c) => c,
                         None => {
@@ -509,26 +1865,939 @@ pub fn interpret_string(json_chars: &[JsonChar]) -> Result<String, Error> {
                 // process as UTF-16
                 if u >= 0xD800 && u <= 0xDBFF {
                     // leading surrogate; check for trailing surrogate
-                    let u2 = match iter.next() {
-                        Some(JsonChar::UnicodeEscape(u2)) if *u2 >= 0xDC00 && u <= 0xDFFF => *u2,
+                    let u2 = match iter.as_slice().first() {
+                        Some(JsonChar::UnicodeEscape(u2)) if *u2 >= 0xDC00 && *u2 <= 0xDFFF => *u2,
+                        _ if options.surrogate_policy == SurrogatePolicy::Replace => {
+                            // the following character is processed normally
+                            chars.push(char::REPLACEMENT_CHARACTER);
+                            continue;
+                        },
                         Some(other) => return Err(Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u), *other])),
                         None => return Err(Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u)])),
                     };
+                    iter.next();
                     let char_value =
                         0x1_0000
                         + (u32::from(u - 0xD800) << 10)
                         + u32::from(u2 - 0xDC00)
                     ;
-                    chars.push(char::from_u32(char_value).unwrap());
+                    let c = char::from_u32(char_value)
+                        .ok_or_else(|| Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u), JsonChar::UnicodeEscape(u2)]))?;
+                    chars.push(c);
                 } else if u >= 0xDC00 && u <= 0xDFFF {
                     // trailing surrogate without a leading surrogate
+                    if options.surrogate_policy == SurrogatePolicy::Replace {
+                        chars.push(char::REPLACEMENT_CHARACTER);
+                        continue;
+                    }
                     return Err(Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u)]));
                 } else {
                     // non-surrogate BMP UTF-16 escape
-                    chars.push(char::from_u32(u.into()).unwrap());
+                    let c = char::from_u32(u.into())
+                        .ok_or_else(|| Error::InvalidUtf16SurrogateSequence(vec![JsonChar::UnicodeEscape(u)]))?;
+                    chars.push(c);
                 }
             },
         }
     }
     Ok(String::from_iter(chars.into_iter()))
 }
+
+
+/// Decodes the characters of a string token into a Rust string, avoiding work where possible.
+///
+/// A string without escapes is validated and copied in one go instead of character by character.
+/// Since a slice of `JsonChar`s cannot be reinterpreted as a `str`, only the empty string is
+/// currently borrowed; once strings can be represented as byte slices, strings without escapes
+/// can be borrowed as well.
+///
+/// The results (including errors) are the same as those of `interpret_string`.
+pub fn interpret_string_cow(json_chars: &[JsonChar]) -> Result<Cow<'_, str>, Error> {
+    if json_chars.len() == 0 {
+        return Ok(Cow::Borrowed(""));
+    }
+
+    let mut bytes = Vec::with_capacity(json_chars.len());
+    for json_char in json_chars {
+        match json_char {
+            JsonChar::Byte(b) => bytes.push(*b),
+            _ => {
+                // escapes require the full treatment
+                return interpret_string(json_chars).map(Cow::Owned);
+            },
+        }
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(string) => Ok(Cow::Owned(string)),
+        Err(_) => {
+            // let interpret_string find the offending sequence
+            interpret_string(json_chars).map(Cow::Owned)
+        },
+    }
+}
+
+
+/// Returns whether the token is a string that decodes to the given text.
+///
+/// Tokens other than strings never compare equal. A malformed string is reported as an error
+/// instead of comparing unequal, so that it is not mistaken for a mere mismatch.
+pub fn token_eq_str(token: &JsonToken, text: &str) -> Result<bool, Error> {
+    match token {
+        JsonToken::String(json_chars) => Ok(interpret_string_cow(json_chars)? == text),
+        _ => Ok(false),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        interpret_string, interpret_string_cow, JsonChar, JsonToken, NumberBytePredicate, NumberPart,
+        read_next_token, read_next_token_into, read_next_token_recovering, read_next_token_with,
+        RecoveryStrategy, TokenBuffer, TokenizerOptions, TokenScan, TokenStream, TokenView,
+    };
+
+    fn tokenize(json: &str, options: &TokenizerOptions) -> Result<Vec<JsonToken>, super::Error> {
+        let mut cursor = std::io::Cursor::new(json);
+        let mut tokens = Vec::new();
+        while let Some(token) = read_next_token_with(&mut cursor, options)? {
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    #[test]
+    fn test_comments() {
+        let options = TokenizerOptions {
+            allow_comments: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(
+            tokenize("[ // one\n1, /* two */ 2 /**/]// end", &options).unwrap(),
+            vec![
+                JsonToken::OpeningBracket,
+                JsonToken::Number(b"1".to_vec()),
+                JsonToken::Comma,
+                JsonToken::Number(b"2".to_vec()),
+                JsonToken::ClosingBracket,
+            ],
+        );
+        assert_eq!(tokenize("[/* unterminated *", &options).is_err(), true);
+        assert_eq!(tokenize("[/ not a comment", &options).is_err(), true);
+
+        // comments are not skipped by default
+        let mut cursor = std::io::Cursor::new("// comment\n1");
+        assert_eq!(read_next_token(&mut cursor).is_err(), true);
+    }
+
+    #[test]
+    fn test_bareword() {
+        let options = TokenizerOptions::default();
+        assert_eq!(tokenize("true", &options).unwrap(), vec![JsonToken::True]);
+        assert_eq!(tokenize("false", &options).unwrap(), vec![JsonToken::False]);
+        assert_eq!(tokenize("null", &options).unwrap(), vec![JsonToken::Null]);
+
+        // the error names exactly the bytes that were read
+        assert!(matches!(
+            tokenize("tr", &options),
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "tr"
+        ));
+        assert!(matches!(
+            tokenize("falsx", &options),
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "falsx"
+        ));
+        assert!(matches!(
+            tokenize("[a]", &options),
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "a"
+        ));
+    }
+
+    #[test]
+    fn test_bareword_suffix() {
+        let options = TokenizerOptions::default();
+        assert_eq!(
+            tokenize("[true,false ,null]", &options).unwrap(),
+            vec![
+                JsonToken::OpeningBracket,
+                JsonToken::True,
+                JsonToken::Comma,
+                JsonToken::False,
+                JsonToken::Comma,
+                JsonToken::Null,
+                JsonToken::ClosingBracket,
+            ],
+        );
+
+        assert!(matches!(
+            tokenize("truefoo", &options),
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "truefoo"
+        ));
+        assert!(matches!(
+            tokenize("[nullable]", &options),
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "nullable"
+        ));
+        assert!(matches!(
+            tokenize("false0", &options),
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "false0"
+        ));
+    }
+
+    #[test]
+    fn test_surrogate_pair_bounds() {
+        use super::{interpret_string, JsonChar};
+
+        assert_eq!(
+            interpret_string(&[JsonChar::UnicodeEscape(0xD83D), JsonChar::UnicodeEscape(0xDE00)]).unwrap(),
+            "\u{1F600}",
+        );
+
+        // a leading surrogate followed by a non-surrogate must not panic
+        assert_eq!(interpret_string(&[JsonChar::UnicodeEscape(0xD800), JsonChar::UnicodeEscape(0xE000)]).is_err(), true);
+        assert_eq!(interpret_string(&[JsonChar::UnicodeEscape(0xDBFF), JsonChar::UnicodeEscape(0xFFFF)]).is_err(), true);
+    }
+
+    #[test]
+    fn test_unicode_escape() {
+        let options = TokenizerOptions::default();
+        assert_eq!(
+            tokenize("\"\\u00e9\\u00C9\"", &options).unwrap(),
+            vec![JsonToken::String(vec![JsonChar::UnicodeEscape(0x00E9), JsonChar::UnicodeEscape(0x00C9)])],
+        );
+
+        assert!(matches!(
+            tokenize("\"\\u12G4\"", &options),
+            Err(super::Error::InvalidUnicodeEscape(e)) if &e == b"12G4"
+        ));
+        assert!(matches!(
+            tokenize("\"\\u+123\"", &options),
+            Err(super::Error::InvalidUnicodeEscape(e)) if &e == b"+123"
+        ));
+        assert!(matches!(
+            tokenize("\"\\u\u{e9}00\"", &options),
+            Err(super::Error::InvalidUnicodeEscape(_))
+        ));
+        assert!(matches!(
+            tokenize("\"\\u00", &options),
+            Err(super::Error::IncompleteUnicodeEscape(e)) if &e == b"00"
+        ));
+        assert!(matches!(
+            tokenize("\"\\u", &options),
+            Err(super::Error::IncompleteUnicodeEscape(e)) if e.len() == 0
+        ));
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let options = TokenizerOptions::default();
+        assert!(matches!(
+            tokenize("[\"a", &options),
+            Err(super::Error::UnterminatedString(s)) if s == vec![JsonChar::Byte(b'a')]
+        ));
+        assert!(matches!(
+            tokenize("\"", &options),
+            Err(super::Error::UnterminatedString(s)) if s.len() == 0
+        ));
+        assert!(matches!(
+            tokenize("\"ab\\", &options),
+            Err(super::Error::UnterminatedString(s)) if s == vec![JsonChar::Byte(b'a'), JsonChar::Byte(b'b')]
+        ));
+        assert!(matches!(
+            tokenize("\"a\\n", &options),
+            Err(super::Error::UnterminatedString(s)) if s == vec![JsonChar::Byte(b'a'), JsonChar::EscapedLineFeed]
+        ));
+    }
+
+    #[test]
+    fn test_single_quotes() {
+        let options = TokenizerOptions {
+            allow_single_quotes: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(
+            tokenize("['a\"b', \"c'd\", 'e\\'f']", &options).unwrap(),
+            vec![
+                JsonToken::OpeningBracket,
+                JsonToken::String(vec![JsonChar::Byte(b'a'), JsonChar::Byte(b'"'), JsonChar::Byte(b'b')]),
+                JsonToken::Comma,
+                JsonToken::String(vec![JsonChar::Byte(b'c'), JsonChar::Byte(b'\''), JsonChar::Byte(b'd')]),
+                JsonToken::Comma,
+                JsonToken::String(vec![JsonChar::Byte(b'e'), JsonChar::Byte(b'\''), JsonChar::Byte(b'f')]),
+                JsonToken::ClosingBracket,
+            ],
+        );
+
+        // strict mode rejects single quotes, also as an escape
+        let strict = TokenizerOptions::default();
+        assert_eq!(tokenize("['a']", &strict).is_err(), true);
+        assert_eq!(tokenize("[\"\\'\"]", &strict).is_err(), true);
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let options = TokenizerOptions {
+            allow_unquoted_keys: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(
+            tokenize("{a_1:true,$b:null}", &options).unwrap(),
+            vec![
+                JsonToken::OpeningBrace,
+                JsonToken::Identifier(b"a_1".to_vec()),
+                JsonToken::Colon,
+                JsonToken::True,
+                JsonToken::Comma,
+                JsonToken::Identifier(b"$b".to_vec()),
+                JsonToken::Colon,
+                JsonToken::Null,
+                JsonToken::ClosingBrace,
+            ],
+        );
+
+        assert_eq!(tokenize("{a:1}", &TokenizerOptions::default()).is_err(), true);
+    }
+
+    #[test]
+    fn test_spans() {
+        use crate::position::PositionReader;
+        use super::read_next_spanned_token;
+
+        let json = "{\"a\" :\n  -1.5e3 }";
+        let mut reader = PositionReader::new(std::io::Cursor::new(json));
+        let mut ranges = Vec::new();
+        while let Some(spanned) = read_next_spanned_token(&mut reader, &TokenizerOptions::default()).unwrap() {
+            let range = spanned.span.byte_range();
+            ranges.push(&json[range.start as usize..range.end as usize]);
+        }
+        assert_eq!(ranges, vec!["{", "\"a\"", ":", "-1.5e3", "}"]);
+    }
+
+    #[test]
+    fn test_char_conversion() {
+        assert_eq!(char::try_from(JsonChar::Byte(b'a')), Ok('a'));
+        assert_eq!(char::try_from(JsonChar::EscapedTab), Ok('\t'));
+        assert_eq!(char::try_from(JsonChar::UnicodeEscape(0x00E9)), Ok('\u{E9}'));
+        assert_eq!(char::try_from(JsonChar::Byte(0xC3)).is_err(), true);
+        assert_eq!(char::try_from(JsonChar::UnicodeEscape(0xD800)).is_err(), true);
+
+        assert_eq!(JsonChar::try_from('a'), Ok(JsonChar::Byte(b'a')));
+        assert_eq!(JsonChar::try_from('/'), Ok(JsonChar::Byte(b'/')));
+        assert_eq!(JsonChar::try_from('"'), Ok(JsonChar::EscapedQuote));
+        assert_eq!(JsonChar::try_from('\n'), Ok(JsonChar::EscapedLineFeed));
+        assert_eq!(JsonChar::try_from('\u{01}'), Ok(JsonChar::UnicodeEscape(0x0001)));
+        assert_eq!(JsonChar::try_from('\u{E9}'), Ok(JsonChar::UnicodeEscape(0x00E9)));
+        assert_eq!(JsonChar::try_from('\u{1F600}').is_err(), true);
+    }
+
+    #[test]
+    fn test_incomplete_number() {
+        let options = TokenizerOptions::default();
+        let cases = [
+            ("-", NumberPart::Mantissa),
+            ("1.", NumberPart::Fraction),
+            ("1e", NumberPart::Exponent),
+            ("1e+", NumberPart::Exponent),
+            ("1e-", NumberPart::Exponent),
+            ("[-0.", NumberPart::Fraction),
+            ("[12E", NumberPart::Exponent),
+        ];
+        for (json, part) in cases {
+            match tokenize(json, &options) {
+                Err(super::Error::IncompleteNumber(p)) => assert_eq!(p, part),
+                other => panic!("unexpected result for {:?}: {:?}", json, other),
+            }
+        }
+        assert_eq!(tokenize("1.5e+3", &options).is_ok(), true);
+
+        let json5 = TokenizerOptions {
+            allow_json5_numbers: true,
+            allow_leading_plus: true,
+            ..TokenizerOptions::default()
+        };
+        let json5_cases = [
+            ("+", NumberPart::Mantissa),
+            (".", NumberPart::Fraction),
+            ("-.", NumberPart::Fraction),
+            ("0x", NumberPart::Mantissa),
+            ("1.e", NumberPart::Exponent),
+        ];
+        for (json, part) in json5_cases {
+            match tokenize(json, &json5) {
+                Err(super::Error::IncompleteNumber(p)) => assert_eq!(p, part),
+                other => panic!("unexpected result for {:?}: {:?}", json, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_number_io_error() {
+        /// Returns the given bytes, then fails.
+        struct FailingReader<'a>(&'a [u8]);
+        impl<'a> std::io::Read for FailingReader<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.len() == 0 {
+                    return Err(std::io::Error::other("disk on fire"));
+                }
+                let read_count = self.0.len().min(buf.len());
+                buf[..read_count].copy_from_slice(&self.0[..read_count]);
+                self.0 = &self.0[read_count..];
+                Ok(read_count)
+            }
+        }
+
+        // a failing read is not mistaken for the end of the input
+        for json in ["1e-", "1.", "-", "12"] {
+            let mut reader = std::io::BufReader::new(FailingReader(json.as_bytes()));
+            match read_next_token(&mut reader) {
+                Err(super::Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::Other),
+                other => panic!("unexpected result for {:?}: {:?}", json, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_leading_plus() {
+        let strict = TokenizerOptions::default();
+        assert_eq!(tokenize("+5", &strict).is_err(), true);
+        assert_eq!(tokenize("[+5]", &strict).is_err(), true);
+
+        let lenient = TokenizerOptions {
+            allow_leading_plus: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(tokenize("[+5, -5, +0.5e+1]", &lenient).unwrap(), vec![
+            JsonToken::OpeningBracket,
+            JsonToken::Number(b"+5".to_vec()),
+            JsonToken::Comma,
+            JsonToken::Number(b"-5".to_vec()),
+            JsonToken::Comma,
+            JsonToken::Number(b"+0.5e+1".to_vec()),
+            JsonToken::ClosingBracket,
+        ]);
+        assert_eq!(tokenize("+-5", &lenient).is_err(), true);
+        assert_eq!(tokenize("+", &lenient).is_err(), true);
+    }
+
+    #[test]
+    fn test_json5_numbers() {
+        let json = "[0xFF, .5, 5., -0x1a, -.5e3, 5.e3, 0.5]";
+        assert_eq!(tokenize(json, &TokenizerOptions::default()).is_err(), true);
+        assert_eq!(tokenize("[.5]", &TokenizerOptions::default()).is_err(), true);
+        assert_eq!(tokenize("[5.]", &TokenizerOptions::default()).is_err(), true);
+
+        let options = TokenizerOptions {
+            allow_json5_numbers: true,
+            ..TokenizerOptions::default()
+        };
+        let numbers: Vec<JsonToken> = tokenize(json, &options).unwrap()
+            .into_iter()
+            .filter(|t| matches!(t, JsonToken::Number(_)))
+            .collect();
+        assert_eq!(numbers, vec![
+            JsonToken::Number(b"0xFF".to_vec()),
+            JsonToken::Number(b".5".to_vec()),
+            JsonToken::Number(b"5.".to_vec()),
+            JsonToken::Number(b"-0x1a".to_vec()),
+            JsonToken::Number(b"-.5e3".to_vec()),
+            JsonToken::Number(b"5.e3".to_vec()),
+            JsonToken::Number(b"0.5".to_vec()),
+        ]);
+        assert_eq!(tokenize(".", &options).is_err(), true);
+        assert_eq!(tokenize("0x", &options).is_err(), true);
+        assert_eq!(tokenize("0xG", &options).is_err(), true);
+        assert_eq!(tokenize("1x5", &options).is_err(), true);
+    }
+
+    #[test]
+    fn test_token_stream() {
+        let mut stream = TokenStream::new("[1]".as_bytes());
+        assert_eq!(stream.peek_token().unwrap(), Some(&JsonToken::OpeningBracket));
+        assert_eq!(stream.peek_token().unwrap(), Some(&JsonToken::OpeningBracket));
+        assert_eq!(stream.next_token().unwrap(), Some(JsonToken::OpeningBracket));
+        assert_eq!(stream.next_token().unwrap(), Some(JsonToken::Number(b"1".to_vec())));
+        assert_eq!(stream.peek_token().unwrap(), Some(&JsonToken::ClosingBracket));
+        assert_eq!(stream.next_token().unwrap(), Some(JsonToken::ClosingBracket));
+        assert_eq!(stream.peek_token().unwrap(), None);
+        assert_eq!(stream.next_token().unwrap(), None);
+
+        // a peeked error is not lost
+        let mut stream = TokenStream::new("[nope]".as_bytes());
+        assert_eq!(stream.next_token().unwrap(), Some(JsonToken::OpeningBracket));
+        assert_eq!(stream.peek_token().is_err(), true);
+        assert_eq!(stream.peek_token().is_err(), true);
+        match stream.next_token() {
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "no" => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decoded() {
+        use super::DecodedToken;
+
+        let tokens: Vec<DecodedToken> = TokenStream::new(r#"{"a\u00e9": ["x\ny", 1, null]}"#.as_bytes())
+            .decoded()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tokens, vec![
+            DecodedToken::OpeningBrace,
+            DecodedToken::String("a\u{E9}".to_owned()),
+            DecodedToken::Colon,
+            DecodedToken::OpeningBracket,
+            DecodedToken::String("x\ny".to_owned()),
+            DecodedToken::Comma,
+            DecodedToken::Number(b"1".to_vec()),
+            DecodedToken::Comma,
+            DecodedToken::Null,
+            DecodedToken::ClosingBracket,
+            DecodedToken::ClosingBrace,
+        ]);
+
+        // decoding errors are passed on in place of the token
+        let mut tokens = TokenStream::new(r#"["\ud800", 2]"#.as_bytes()).decoded();
+        assert_eq!(tokens.next().unwrap().unwrap(), DecodedToken::OpeningBracket);
+        assert!(matches!(tokens.next(), Some(Err(super::Error::InvalidUtf16SurrogateSequence(_)))));
+        assert_eq!(tokens.next().unwrap().unwrap(), DecodedToken::Comma);
+    }
+
+    #[test]
+    fn test_unicode_whitespace() {
+        let strict = TokenizerOptions::default();
+        match tokenize("[1,\u{A0}2]", &strict) {
+            Err(super::Error::IllegalWhitespace('\u{A0}')) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match tokenize("\u{FEFF}[]", &strict) {
+            Err(super::Error::IllegalWhitespace('\u{FEFF}')) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match tokenize("[\u{E9}]", &strict) {
+            Err(super::Error::InvalidBarewordBeginning(s)) if s == "\u{E9}" => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let lenient = TokenizerOptions {
+            allow_unicode_whitespace: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(tokenize("\u{FEFF}[1,\u{A0}2\u{3000}]\u{2028}", &lenient).unwrap(), vec![
+            JsonToken::OpeningBracket,
+            JsonToken::Number(b"1".to_vec()),
+            JsonToken::Comma,
+            JsonToken::Number(b"2".to_vec()),
+            JsonToken::ClosingBracket,
+        ]);
+        assert_eq!(tokenize("[\u{E9}]", &lenient).is_err(), true);
+
+        // a character split across buffer refills is skipped as well
+        let mut reader = std::io::BufReader::with_capacity(2, "1 \u{A0}2".as_bytes());
+        assert_eq!(read_next_token_with(&mut reader, &lenient).unwrap(), Some(JsonToken::Number(b"1".to_vec())));
+        assert_eq!(read_next_token_with(&mut reader, &lenient).unwrap(), Some(JsonToken::Number(b"2".to_vec())));
+
+        // however many of them there are
+        let json = format!("{}1", "\u{3000}".repeat(500_000));
+        let mut reader = std::io::BufReader::with_capacity(2, json.as_bytes());
+        assert_eq!(read_next_token_with(&mut reader, &lenient).unwrap(), Some(JsonToken::Number(b"1".to_vec())));
+    }
+
+    #[test]
+    fn test_token_eq_str() {
+        use super::token_eq_str;
+
+        let tokens = tokenize(r#"["abc", "a\u0062c", "", "\ud800", 1]"#, &TokenizerOptions::default()).unwrap();
+        assert_eq!(token_eq_str(&tokens[1], "abc").unwrap(), true);
+        assert_eq!(token_eq_str(&tokens[1], "abd").unwrap(), false);
+        assert_eq!(token_eq_str(&tokens[3], "abc").unwrap(), true);
+        assert_eq!(token_eq_str(&tokens[5], "").unwrap(), true);
+        assert_eq!(token_eq_str(&tokens[7], "\u{FFFD}").is_err(), true);
+        assert_eq!(token_eq_str(&tokens[9], "1").unwrap(), false);
+        assert_eq!(token_eq_str(&tokens[0], "[").unwrap(), false);
+    }
+
+    #[test]
+    fn test_code_point_out_of_range() {
+        let largest = [JsonChar::Byte(0xF4), JsonChar::Byte(0x8F), JsonChar::Byte(0xBF), JsonChar::Byte(0xBF)];
+        assert_eq!(interpret_string(&largest).unwrap(), "\u{10FFFF}");
+
+        let too_large = [JsonChar::Byte(0xF4), JsonChar::Byte(0x90), JsonChar::Byte(0x80), JsonChar::Byte(0x80)];
+        match interpret_string(&too_large) {
+            Err(super::Error::CodePointOutOfRange(0x11_0000)) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let largest_encodable = [JsonChar::Byte(0xF7), JsonChar::Byte(0xBF), JsonChar::Byte(0xBF), JsonChar::Byte(0xBF)];
+        match interpret_string(&largest_encodable) {
+            Err(super::Error::CodePointOutOfRange(0x1F_FFFF)) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_surrogate_policy() {
+        use super::{interpret_string_with, InterpretOptions, SurrogatePolicy};
+
+        let reject = InterpretOptions::default();
+        let replace = InterpretOptions {
+            surrogate_policy: SurrogatePolicy::Replace,
+        };
+
+        let lone_leading = [JsonChar::UnicodeEscape(0xD800)];
+        assert!(matches!(
+            interpret_string_with(&lone_leading, &reject),
+            Err(super::Error::InvalidUtf16SurrogateSequence(_))
+        ));
+        assert_eq!(interpret_string_with(&lone_leading, &replace).unwrap(), "\u{FFFD}");
+
+        let lone_trailing = [JsonChar::Byte(b'a'), JsonChar::UnicodeEscape(0xDC00), JsonChar::Byte(b'b')];
+        assert_eq!(interpret_string_with(&lone_trailing, &reject).is_err(), true);
+        assert_eq!(interpret_string_with(&lone_trailing, &replace).unwrap(), "a\u{FFFD}b");
+
+        // the character after a lone leading surrogate is kept, even if it is another leading one
+        let mismatched = [
+            JsonChar::UnicodeEscape(0xD800), JsonChar::Byte(b'x'),
+            JsonChar::UnicodeEscape(0xD800), JsonChar::UnicodeEscape(0xD83D), JsonChar::UnicodeEscape(0xDE00),
+        ];
+        assert_eq!(interpret_string_with(&mismatched, &replace).unwrap(), "\u{FFFD}x\u{FFFD}\u{1F600}");
+    }
+
+    #[test]
+    fn test_interpret_string_cow() {
+        use std::borrow::Cow;
+
+        assert_eq!(matches!(interpret_string_cow(&[]), Ok(Cow::Borrowed(""))), true);
+
+        let strings: [&[u8]; 5] = [
+            b"\"plain\"",
+            b"\"caf\xC3\xA9\"",
+            b"\"esc\\naped \\u00e9\"",
+            b"\"broken \xC3(\"",
+            b"\"lone \\ud800\"",
+        ];
+        for string in strings {
+            let json_chars = match read_next_token(string).unwrap() {
+                Some(JsonToken::String(json_chars)) => json_chars,
+                other => panic!("unexpected token: {:?}", other),
+            };
+            let owned = interpret_string(&json_chars);
+            let cow = interpret_string_cow(&json_chars);
+            match (owned, cow) {
+                (Ok(o), Ok(c)) => assert_eq!(o, c),
+                (Err(o), Err(c)) => assert_eq!(o.to_string(), c.to_string()),
+                other => panic!("results differ: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_one_byte_reads() {
+        fn tokenize_slowly(json: &str) -> Result<Vec<JsonToken>, super::Error> {
+            // every fill_buf returns at most one byte
+            let mut reader = std::io::BufReader::with_capacity(1, json.as_bytes());
+            let mut tokens = Vec::new();
+            while let Some(token) = read_next_token(&mut reader)? {
+                tokens.push(token);
+            }
+            Ok(tokens)
+        }
+
+        assert_eq!(
+            tokenize_slowly("[true, false,null, \"\\u00e9x\", -1.5e3]").unwrap(),
+            vec![
+                JsonToken::OpeningBracket,
+                JsonToken::True,
+                JsonToken::Comma,
+                JsonToken::False,
+                JsonToken::Comma,
+                JsonToken::Null,
+                JsonToken::Comma,
+                JsonToken::String(vec![JsonChar::UnicodeEscape(0x00E9), JsonChar::Byte(b'x')]),
+                JsonToken::Comma,
+                JsonToken::Number(b"-1.5e3".to_vec()),
+                JsonToken::ClosingBracket,
+            ],
+        );
+
+        for bareword in ["f", "fa", "fal", "fals", "t", "tru", "n", "nul"] {
+            match tokenize_slowly(bareword) {
+                Err(super::Error::InvalidBarewordBeginning(s)) => assert_eq!(s, bareword),
+                other => panic!("unexpected result for {:?}: {:?}", bareword, other),
+            }
+        }
+        assert!(matches!(
+            tokenize_slowly("\"\\u0"),
+            Err(super::Error::IncompleteUnicodeEscape(e)) if &e == b"0"
+        ));
+        assert!(matches!(
+            tokenize_slowly("\"\\u00e9"),
+            Err(super::Error::UnterminatedString(s)) if s == vec![JsonChar::UnicodeEscape(0x00E9)]
+        ));
+    }
+
+    #[test]
+    fn test_validate_string_utf8() {
+        fn tokenize_bytes(json: &[u8], options: &TokenizerOptions) -> Result<Vec<JsonToken>, super::Error> {
+            let mut cursor = std::io::Cursor::new(json);
+            let mut tokens = Vec::new();
+            while let Some(token) = read_next_token_with(&mut cursor, options)? {
+                tokens.push(token);
+            }
+            Ok(tokens)
+        }
+
+        let lenient = TokenizerOptions::default();
+        let eager = TokenizerOptions {
+            validate_string_utf8: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokenize_bytes(b"[\"a\xFFb\"]", &lenient).is_ok(), true);
+        assert!(matches!(
+            tokenize_bytes(b"[\"a\xFFb\"]", &eager),
+            Err(super::Error::InvalidUtf8Sequence(_))
+        ));
+        assert!(matches!(
+            tokenize_bytes(b"\"\xED\xA0\x80\"", &eager),
+            Err(super::Error::Utf8SequenceProducedSurrogate(0xD800))
+        ));
+        assert!(matches!(
+            tokenize_bytes(b"\"\xF4\x90\x80\x80\"", &eager),
+            Err(super::Error::CodePointOutOfRange(0x11_0000))
+        ));
+
+        // valid UTF-8 and lone surrogate escapes pass
+        assert_eq!(tokenize_bytes("[\"\u{E9}\u{1F600}\"]".as_bytes(), &eager).is_ok(), true);
+        assert_eq!(tokenize_bytes(b"\"\\ud800\"", &eager).is_ok(), true);
+
+        // reading fails at the invalid sequence, not at the end of the string
+        assert!(matches!(
+            tokenize_bytes(b"\"a\xFFb", &eager),
+            Err(super::Error::InvalidUtf8Sequence(s)) if s == vec![JsonChar::Byte(0xFF)]
+        ));
+        assert!(matches!(
+            tokenize_bytes(b"\"\xC3\\n", &eager),
+            Err(super::Error::InvalidUtf8Sequence(s)) if s == vec![JsonChar::Byte(0xC3), JsonChar::EscapedLineFeed]
+        ));
+        assert!(matches!(
+            tokenize_bytes(b"\"\xC3\"", &eager),
+            Err(super::Error::InvalidUtf8Sequence(s)) if s == vec![JsonChar::Byte(0xC3)]
+        ));
+
+        // and so might a token that is being fed in chunks
+        let mut scan = TokenScan::default();
+        assert_eq!(scan.might_end(b"\"a\xC3", &eager), false);
+        assert_eq!(scan.might_end(b"\"a\xC3\xA9b", &eager), false);
+        assert_eq!(scan.might_end(b"\"a\xC3\xA9b\xFF", &eager), true);
+        let mut scan = TokenScan::default();
+        assert_eq!(scan.might_end(b"\"\xC3\\", &eager), true);
+        let mut scan = TokenScan::default();
+        assert_eq!(scan.might_end(b"\"a\xFF", &lenient), false);
+    }
+
+    #[test]
+    fn test_validate_surrogates() {
+        use super::Error;
+
+        let lenient = TokenizerOptions::default();
+        let eager = TokenizerOptions {
+            validate_surrogates: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokenize("[\"\\udc00\"]", &lenient).is_ok(), true);
+        assert!(matches!(
+            tokenize("[\"\\udc00\"]", &eager),
+            Err(Error::InvalidUtf16SurrogateSequence(seq)) if seq == vec![JsonChar::UnicodeEscape(0xDC00)]
+        ));
+        assert!(matches!(
+            tokenize("\"\\ud800\"", &eager),
+            Err(Error::InvalidUtf16SurrogateSequence(seq)) if seq == vec![JsonChar::UnicodeEscape(0xD800)]
+        ));
+        assert!(matches!(
+            tokenize("\"\\ud800x\"", &eager),
+            Err(Error::InvalidUtf16SurrogateSequence(seq)) if seq == vec![JsonChar::UnicodeEscape(0xD800), JsonChar::Byte(b'x')]
+        ));
+        assert!(matches!(
+            tokenize("\"\\ud800\\ud800\"", &eager),
+            Err(Error::InvalidUtf16SurrogateSequence(seq)) if seq == vec![JsonChar::UnicodeEscape(0xD800), JsonChar::UnicodeEscape(0xD800)]
+        ));
+
+        // proper pairs and other escapes pass, as does invalid UTF-8
+        assert_eq!(tokenize("[\"\\ud83d\\ude00\\u00e9\\n\"]", &eager).is_ok(), true);
+        let mut cursor = std::io::Cursor::new(b"\"\xFF\"");
+        assert_eq!(read_next_token_with(&mut cursor, &eager).is_ok(), true);
+    }
+
+    #[test]
+    fn test_string_across_buffers() {
+        let text = "abc\u{E9}def'ghi".repeat(50);
+        let json = format!("[\"{}\\t{}\", \"\"]", text, text);
+        for capacity in [1, 2, 3, 7, 64, 8192] {
+            let mut reader = std::io::BufReader::with_capacity(capacity, json.as_bytes());
+            let mut tokens = Vec::new();
+            while let Some(token) = read_next_token(&mut reader).unwrap() {
+                tokens.push(token);
+            }
+            assert_eq!(tokens.len(), 5, "capacity {}", capacity);
+            match &tokens[1] {
+                JsonToken::String(chars) => {
+                    assert_eq!(interpret_string(chars).unwrap(), format!("{}\t{}", text, text));
+                },
+                other => panic!("unexpected token {:?}", other),
+            }
+            assert_eq!(tokens[3], JsonToken::String(Vec::new()));
+        }
+    }
+
+    #[test]
+    fn test_max_escapes_per_string() {
+        let options = TokenizerOptions {
+            max_escapes_per_string: Some(2),
+            ..TokenizerOptions::default()
+        };
+
+        let tok = read_next_token_with(r#""\u0041\n\u0042\t""#.as_bytes(), &options).unwrap();
+        assert!(tok.is_some());
+
+        // other escapes do not count
+        let tok = read_next_token_with(r#""\n\t\r\\\"\/""#.as_bytes(), &options).unwrap();
+        assert!(tok.is_some());
+
+        let result = read_next_token_with(r#""\u0041\u0042\u0043""#.as_bytes(), &options);
+        assert!(matches!(result, Err(super::Error::TooManyUnicodeEscapes(2))));
+
+        // the limit applies per string
+        let tokens = tokenize(r#"["\u0041\u0042", "\u0043\u0044"]"#, &options).unwrap();
+        assert_eq!(tokens.len(), 5);
+    }
+
+    #[test]
+    fn test_accept_number_byte() {
+        let options = TokenizerOptions {
+            accept_number_byte: Some(NumberBytePredicate::new(|number, b| b == b'_' && number.last().is_some_and(|d| d.is_ascii_digit()))),
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(
+            tokenize("[1_000, -2_5.0_1e1_0]", &options).unwrap(),
+            vec![
+                JsonToken::OpeningBracket,
+                JsonToken::Number(b"1_000".to_vec()),
+                JsonToken::Comma,
+                JsonToken::Number(b"-2_5.0_1e1_0".to_vec()),
+                JsonToken::ClosingBracket,
+            ],
+        );
+
+        // only where the predicate agrees
+        assert!(tokenize("[_1]", &options).is_err());
+        assert!(tokenize("[1._5]", &options).is_err());
+        assert!(tokenize("[1_000]", &TokenizerOptions::default()).is_err());
+
+        // predicates are compared by identity
+        assert_eq!(options.clone(), options);
+        let other_options = TokenizerOptions {
+            accept_number_byte: Some(NumberBytePredicate::new(|number, b| b == b'_' && number.last().is_some_and(|d| d.is_ascii_digit()))),
+            ..TokenizerOptions::default()
+        };
+        assert_ne!(other_options, options);
+    }
+
+    #[test]
+    fn test_read_next_token_into() {
+        let json = r#"{"a": [1, -2.5e3, "b\u00e9", true, false, null], key: 'c'}"#;
+        let options = TokenizerOptions {
+            allow_unquoted_keys: true,
+            allow_single_quotes: true,
+            ..TokenizerOptions::default()
+        };
+
+        let mut cursor = std::io::Cursor::new(json);
+        let mut buffer = TokenBuffer::new();
+        let mut tokens = Vec::new();
+        while let Some(view) = read_next_token_into(&mut cursor, &options, &mut buffer).unwrap() {
+            tokens.push(view.to_token());
+        }
+        assert_eq!(tokens, tokenize(json, &options).unwrap());
+
+        // the buffer is overwritten by each token
+        let mut cursor = std::io::Cursor::new(r#""long string" "s" 12345 6"#);
+        let view = read_next_token_into(&mut cursor, &options, &mut buffer).unwrap();
+        assert_eq!(view.map(|v| v.to_token()), Some(JsonToken::String(b"long string".iter().map(|&b| JsonChar::Byte(b)).collect())));
+        assert_eq!(read_next_token_into(&mut cursor, &options, &mut buffer).unwrap(), Some(TokenView::String(&[JsonChar::Byte(b's')])));
+        assert_eq!(read_next_token_into(&mut cursor, &options, &mut buffer).unwrap(), Some(TokenView::Number(b"12345")));
+        assert_eq!(read_next_token_into(&mut cursor, &options, &mut buffer).unwrap(), Some(TokenView::Number(b"6")));
+        assert_eq!(read_next_token_into(&mut cursor, &options, &mut buffer).unwrap(), None);
+
+        // errors are the same as when allocating
+        let mut cursor = std::io::Cursor::new("\"unterminated");
+        assert!(matches!(
+            read_next_token_into(&mut cursor, &options, &mut buffer),
+            Err(super::Error::UnterminatedString(chars)) if chars.len() == 12
+        ));
+    }
+
+    #[test]
+    fn test_read_next_token_recovering() {
+        use super::Error;
+
+        fn tokenize_recovering(json: &str, recovery: RecoveryStrategy) -> (Result<Vec<JsonToken>, Error>, Vec<Error>) {
+            let mut cursor = std::io::Cursor::new(json);
+            let mut tokens = Vec::new();
+            let mut errors = Vec::new();
+            let options = TokenizerOptions::default();
+            loop {
+                match read_next_token_recovering(&mut cursor, &options, recovery, |e| errors.push(e)) {
+                    Ok(Some(token)) => tokens.push(token),
+                    Ok(None) => return (Ok(tokens), errors),
+                    Err(e) => return (Err(e), errors),
+                }
+            }
+        }
+
+        let (tokens, errors) = tokenize_recovering(r#"[1.x, "a\qb", 2, nul]"#, RecoveryStrategy::SkipToBoundary);
+        assert_eq!(tokens.unwrap(), vec![
+            JsonToken::OpeningBracket,
+            JsonToken::Comma,
+            JsonToken::Comma,
+            JsonToken::Number(b"2".to_vec()),
+            JsonToken::Comma,
+            JsonToken::ClosingBracket,
+        ]);
+        assert!(matches!(errors[..], [
+            Error::InvalidNumberCharacter(b'x'),
+            Error::UnknownEscape(b'q'),
+            Error::InvalidBarewordBeginning(_),
+        ]));
+
+        // a malformed string is skipped up to its closing quote, even across whitespace and
+        // punctuation
+        let (tokens, errors) = tokenize_recovering(r#"["a\q b", 1]"#, RecoveryStrategy::SkipToBoundary);
+        assert_eq!(tokens.unwrap(), vec![
+            JsonToken::OpeningBracket,
+            JsonToken::Comma,
+            JsonToken::Number(b"1".to_vec()),
+            JsonToken::ClosingBracket,
+        ]);
+        assert!(matches!(errors[..], [Error::UnknownEscape(b'q')]));
+        let (tokens, errors) = tokenize_recovering(r#"["a\q [x]", "\u12", "\\\z\"{"]"#, RecoveryStrategy::SkipToBoundary);
+        assert_eq!(tokens.unwrap(), vec![
+            JsonToken::OpeningBracket,
+            JsonToken::Comma,
+            JsonToken::Comma,
+            JsonToken::ClosingBracket,
+        ]);
+        assert!(matches!(errors[..], [Error::UnknownEscape(b'q'), Error::IncompleteUnicodeEscape(_), Error::UnknownEscape(b'z')]));
+
+        // the input ending within a token cannot be recovered from
+        let (tokens, errors) = tokenize_recovering("[1, \"a", RecoveryStrategy::SkipToBoundary);
+        assert!(matches!(tokens, Err(Error::UnterminatedString(_))));
+        assert_eq!(errors.len(), 0);
+        let (tokens, errors) = tokenize_recovering("[1, \"\\u00", RecoveryStrategy::SkipToBoundary);
+        assert!(matches!(tokens, Err(Error::IncompleteUnicodeEscape(_))));
+        assert_eq!(errors.len(), 0);
+
+        // failing is the default
+        let (tokens, errors) = tokenize_recovering("[1.x]", RecoveryStrategy::default());
+        assert!(matches!(tokens, Err(Error::InvalidNumberCharacter(b'x'))));
+        assert_eq!(errors.len(), 0);
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt;
+use std::io::{BufRead, Read};
+
+
+const BUFFER_SIZE: usize = 8192;
+
+
+/// The error reported by `Utf8Reader` (wrapped in an `std::io::Error` of kind `InvalidData`) when
+/// the input is not valid UTF-8.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct InvalidUtf8Error {
+    /// The byte offset at which the first invalid sequence begins.
+    pub offset: u64,
+}
+impl fmt::Display for InvalidUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid UTF-8 sequence at offset {}", self.offset)
+    }
+}
+impl std::error::Error for InvalidUtf8Error {
+}
+
+
+/// Wraps a reader and ensures that everything read from it is valid UTF-8.
+///
+/// The input is validated as it is read. All bytes before the first invalid sequence are passed
+/// through; the attempt to read the invalid sequence fails with an `InvalidUtf8Error`.
+pub struct Utf8Reader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    // buf[pos..valid_end] has been validated but not consumed
+    // buf[valid_end..filled] is an incomplete (or invalid) sequence
+    pos: usize,
+    valid_end: usize,
+    filled: usize,
+    buf_offset: u64,
+    invalid: bool,
+}
+impl<R> Utf8Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: vec![0; BUFFER_SIZE],
+            pos: 0,
+            valid_end: 0,
+            filled: 0,
+            buf_offset: 0,
+            invalid: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn invalid_utf8_error(&self) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            InvalidUtf8Error { offset: self.buf_offset + (self.valid_end as u64) },
+        )
+    }
+}
+impl<R: Read> Read for Utf8Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let read_count = available.len().min(buf.len());
+        buf[..read_count].copy_from_slice(&available[..read_count]);
+        self.consume(read_count);
+        Ok(read_count)
+    }
+}
+impl<R: Read> BufRead for Utf8Reader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        while self.pos == self.valid_end {
+            if self.invalid {
+                return Err(self.invalid_utf8_error());
+            }
+
+            // keep the incomplete sequence (if any) and read more bytes after it
+            self.buf.copy_within(self.valid_end..self.filled, 0);
+            self.buf_offset += self.valid_end as u64;
+            self.filled -= self.valid_end;
+            self.pos = 0;
+            self.valid_end = 0;
+
+            let read_count = self.inner.read(&mut self.buf[self.filled..])?;
+            if read_count == 0 {
+                if self.filled > 0 {
+                    // the input ends within a sequence
+                    return Err(self.invalid_utf8_error());
+                }
+                break;
+            }
+            self.filled += read_count;
+
+            match std::str::from_utf8(&self.buf[..self.filled]) {
+                Ok(_) => self.valid_end = self.filled,
+                Err(e) => {
+                    self.valid_end = e.valid_up_to();
+                    // without an error length, the sequence might just be incomplete
+                    self.invalid = e.error_len().is_some();
+                },
+            }
+        }
+        Ok(&self.buf[self.pos..self.valid_end])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.valid_end);
+    }
+}
+
+
+/// A reader that is validated as UTF-8 only if requested.
+pub(crate) enum MaybeUtf8Reader<R> {
+    Unchecked(R),
+    Checked(Utf8Reader<R>),
+}
+impl<R: BufRead> Read for MaybeUtf8Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Unchecked(r) => r.read(buf),
+            Self::Checked(r) => r.read(buf),
+        }
+    }
+}
+impl<R: BufRead> BufRead for MaybeUtf8Reader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        match self {
+            Self::Unchecked(r) => r.fill_buf(),
+            Self::Checked(r) => r.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Unchecked(r) => r.consume(amt),
+            Self::Checked(r) => r.consume(amt),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, Read};
+    use super::{InvalidUtf8Error, Utf8Reader};
+
+    /// Returns one byte per read, to split multi-byte sequences.
+    struct OneByteReader<'a>(&'a [u8]);
+    impl<'a> Read for OneByteReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0.len() == 0 || buf.len() == 0 {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    fn read_all<R: Read>(reader: R) -> (Vec<u8>, Option<u64>) {
+        let mut reader = Utf8Reader::new(reader);
+        let mut bytes = Vec::new();
+        loop {
+            let buf = match reader.fill_buf() {
+                Ok(b) => b,
+                Err(e) => {
+                    let offset = e.get_ref()
+                        .and_then(|inner| inner.downcast_ref::<InvalidUtf8Error>())
+                        .map(|inner| inner.offset)
+                        .expect("unexpected error");
+                    return (bytes, Some(offset));
+                },
+            };
+            if buf.len() == 0 {
+                return (bytes, None);
+            }
+            let buf_len = buf.len();
+            bytes.extend_from_slice(buf);
+            reader.consume(buf_len);
+        }
+    }
+
+    #[test]
+    fn test_valid() {
+        let text = "[\"a\u{E9}\u{20AC}\u{1F600}\"]".as_bytes();
+        assert_eq!(read_all(text), (text.to_vec(), None));
+        assert_eq!(read_all(OneByteReader(text)), (text.to_vec(), None));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let text = b"[\"a\xE2\x82\"]";
+        assert_eq!(read_all(&text[..]), (b"[\"a".to_vec(), Some(3)));
+        assert_eq!(read_all(OneByteReader(text)), (b"[\"a".to_vec(), Some(3)));
+
+        let text = b"[1,\xFF]";
+        assert_eq!(read_all(&text[..]), (b"[1,".to_vec(), Some(3)));
+
+        // cut off at the end
+        let text = b"[\"\xF0\x9F\x98";
+        assert_eq!(read_all(&text[..]), (b"[\"".to_vec(), Some(2)));
+        assert_eq!(read_all(OneByteReader(text)), (b"[\"".to_vec(), Some(2)));
+    }
+}
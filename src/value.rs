@@ -0,0 +1,140 @@
+use std::io::BufRead;
+
+use crate::position::Position;
+use crate::tokenizer::{interpret_string, JsonToken};
+use crate::verifier::{Verifier, VerifyError};
+use crate::visit::{visit_values, ValueVisitor};
+
+
+/// A JSON value built while verifying a document.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// A number, kept exactly as written to preserve its precision.
+    Number(Vec<u8>),
+    String(String),
+    Array(Vec<Value>),
+    /// An object, with its members in the order in which they appear in the document.
+    Object(Vec<(String, Value)>),
+}
+
+
+/// Builds a `Value` from each value.
+struct Builder;
+impl ValueVisitor for Builder {
+    type Value = Value;
+    type Array = Vec<Value>;
+    type Object = Vec<(String, Value)>;
+    type Error = VerifyError;
+
+    fn begin_array(&mut self) -> Self::Array {
+        Vec::new()
+    }
+
+    fn begin_object(&mut self) -> Self::Object {
+        Vec::new()
+    }
+
+    fn array_element(&mut self, elements: &mut Self::Array, element: Value) {
+        elements.push(element);
+    }
+
+    fn object_member(&mut self, members: &mut Self::Object, key: String, value: Value) {
+        members.push((key, value));
+    }
+
+    fn end_array(&mut self, elements: Self::Array) -> Value {
+        Value::Array(elements)
+    }
+
+    fn end_object(&mut self, members: Self::Object) -> Value {
+        Value::Object(members)
+    }
+
+    fn scalar(&mut self, tok: JsonToken, position: Position) -> Result<Value, VerifyError> {
+        match tok {
+            JsonToken::String(chars) => interpret_string(&chars)
+                .map(Value::String)
+                .map_err(|e| VerifyError::from_tokenizer(position, e)),
+            JsonToken::Number(number) => Ok(Value::Number(number)),
+            JsonToken::True => Ok(Value::Bool(true)),
+            JsonToken::False => Ok(Value::Bool(false)),
+            _ => Ok(Value::Null),
+        }
+    }
+}
+
+
+/// Verifies a JSON document with the default options and returns its value.
+///
+/// Fails if the document contains no value.
+pub fn build_value<R: BufRead>(json_reader: R) -> Result<Value, VerifyError> {
+    build_value_with(&Verifier::new(), json_reader)
+}
+
+
+/// Verifies a JSON document following the options of the given verifier and returns its value.
+///
+/// Unlike verification, building a value fails if the document contains no value, regardless of
+/// `Verifier::allow_empty`.
+pub fn build_value_with<R: BufRead>(verifier: &Verifier, json_reader: R) -> Result<Value, VerifyError> {
+    let verifier = verifier.clone().allow_empty(false);
+    let root = visit_values(&verifier, json_reader, &mut Builder)?;
+    Ok(root.expect("verifier accepted a document without a value"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{build_value, build_value_with, Value};
+    use crate::verifier::{Verifier, VerifyError};
+
+    fn built(json: &str) -> Result<Value, VerifyError> {
+        build_value(json.as_bytes())
+    }
+
+    #[test]
+    fn test_build_value() {
+        assert_eq!(built("null").unwrap(), Value::Null);
+        assert_eq!(built(" true ").unwrap(), Value::Bool(true));
+        assert_eq!(built("12345678901234567890.000000000000000000001").unwrap(), Value::Number(b"12345678901234567890.000000000000000000001".to_vec()));
+        assert_eq!(built(r#""café""#).unwrap(), Value::String("café".to_owned()));
+        assert_eq!(built("[]").unwrap(), Value::Array(Vec::new()));
+        assert_eq!(
+            built(r#"{"b": [1, false, {}], "a": {"c": null}}"#).unwrap(),
+            Value::Object(vec![
+                ("b".to_owned(), Value::Array(vec![
+                    Value::Number(b"1".to_vec()),
+                    Value::Bool(false),
+                    Value::Object(Vec::new()),
+                ])),
+                ("a".to_owned(), Value::Object(vec![("c".to_owned(), Value::Null)])),
+            ]),
+        );
+
+        // invalid documents are rejected as by verification
+        assert!(built("[1,]").is_err());
+        assert!(matches!(built(r#"{"a": 1, "a": 2}"#), Err(VerifyError::DuplicateKey(_, _, _))));
+        assert!(matches!(built("[1] 2"), Err(VerifyError::TrailingGarbage(_, _))));
+        assert!(matches!(built(" "), Err(VerifyError::NoValue(_))));
+
+        let verifier = Verifier::new().allow_unquoted_keys(true);
+        assert_eq!(
+            build_value_with(&verifier, "{a: 1, null: 2}".as_bytes()).unwrap(),
+            Value::Object(vec![
+                ("a".to_owned(), Value::Number(b"1".to_vec())),
+                ("null".to_owned(), Value::Number(b"2".to_vec())),
+            ]),
+        );
+
+        let verifier = Verifier::new().decimal_comma(true);
+        assert_eq!(
+            build_value_with(&verifier, r#"{"pi": 3,14, "e": 2}"#.as_bytes()).unwrap(),
+            Value::Object(vec![
+                ("pi".to_owned(), Value::Number(b"3,14".to_vec())),
+                ("e".to_owned(), Value::Number(b"2".to_vec())),
+            ]),
+        );
+    }
+}
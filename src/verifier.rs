@@ -1,8 +1,26 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::BTreeSet;
-use std::io::BufRead;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Take, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::identity::impl_identity_comparisons;
 use crate::io_util::BufReadExt;
-use crate::tokenizer::{interpret_string, JsonToken, read_next_token, skip_whitespace};
+use crate::number::{double_range_violation, is_integer_with_exponent, number_quirks, DoubleRangeViolation, NumberQuirk};
+use crate::position::{Position, PositionReader, Span};
+use crate::tokenizer::{
+    Error as TokenizerError, ExtraWhitespace, NumberBytePredicate, interpret_string, invalid_utf8_offset, JsonChar, JsonToken, read_next_token_with, RECORD_SEPARATOR,
+    RecordReader, skip_insignificant, skip_insignificant_once, skip_to_record_separator, skip_to_token_boundary, take_decimal_comma,
+    TokenKind, TokenScan, TokenizerOptions,
+};
+use crate::utf8::{InvalidUtf8Error, MaybeUtf8Reader, Utf8Reader};
 
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -18,10 +36,55 @@ struct JsonArray {
 
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 struct JsonObject {
-    pub known_keys: BTreeSet<String>,
+    /// The keys of the object, in the order in which they appear.
+    pub keys: Vec<String>,
+    /// The forms in which the keys are compared to find duplicates, derived from `keys`.
+    pub key_identities: BTreeSet<KeyIdentity>,
     pub current_key: Option<String>,
 }
 
+/// The form in which a key is compared to find duplicates; see `DuplicateKeyComparison`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum KeyIdentity {
+    Decoded(String),
+    Raw(Vec<JsonChar>),
+}
+
+/// Bytes fed to a `ParserState` that have not been turned into tokens yet.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct PendingBytes {
+    pub bytes: Vec<u8>,
+    pub position: Position,
+    pub complete: bool,
+    pub scan: Option<TokenScan>,
+    pub after_carriage_return: bool,
+}
+impl PendingBytes {
+    pub fn outcome(&self) -> StepOutcome {
+        if self.complete { StepOutcome::Complete } else { StepOutcome::NeedMore }
+    }
+}
+
+/// Returns the JSON Pointer (RFC 6901) to the value currently being processed.
+fn json_pointer(json_stack: &[JsonStackValue]) -> String {
+    let mut pointer = String::new();
+    for value in json_stack {
+        match value {
+            JsonStackValue::Array(arr) => {
+                pointer.push('/');
+                pointer.push_str(&arr.current_index.to_string());
+            },
+            JsonStackValue::Object(obj) => {
+                if let Some(key) = &obj.current_key {
+                    pointer.push('/');
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                }
+            },
+        }
+    }
+    pointer
+}
+
 bitflags::bitflags! {
     #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
     pub struct ParserExpects: u8 {
@@ -33,282 +96,3698 @@ bitflags::bitflags! {
         const CLOSING_BRACE = 0x20;
     }
 }
+impl fmt::Display for ParserExpects {
+    /// Lists the expected tokens, e.g. "a comma or closing bracket".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.iter()
+            .map(|flag| match flag {
+                Self::VALUE => "value",
+                Self::KEY => "key",
+                Self::COMMA => "comma",
+                Self::COLON => "colon",
+                Self::CLOSING_BRACKET => "closing bracket",
+                _ => "closing brace",
+            })
+            .collect();
+        match names.len() {
+            0 => write!(f, "the end of the document"),
+            1 => write!(f, "a {}", names[0]),
+            n => write!(f, "a {} or {}", names[..n-1].join(", "), names[n-1]),
+        }
+    }
+}
 
 
-pub fn verify<R: BufRead>(mut json_reader: R) -> bool {
-    let mut json_stack = Vec::new();
-    let mut expects = ParserExpects::VALUE;
-
-    loop {
-        // take a token
-        let tok = match read_next_token(&mut json_reader) {
-            Ok(Some(t)) => t,
-            Ok(None) => break,
-            Err(e) => {
-                eprintln!("failed to take next token: {}", e);
-                return false;
+#[derive(Debug)]
+pub enum VerifyError {
+    Tokenizer(Position, TokenizerError),
+    InvalidString(Position, TokenizerError),
+    InvalidUtf8InString(Position, u64, TokenizerError),
+    UnexpectedToken(Position, JsonToken, ParserExpects),
+    DuplicateKey(Position, String, Vec<String>),
+    UnclosedDocument(Position, usize),
+    TrailingGarbage(Position, Option<TokenKind>),
+    MissingRecordSeparator(Position),
+    MaxDepthExceeded(Position, usize),
+    DocumentTooLarge(Position, u64),
+    WrongRootKind(Position, RootKind, JsonToken),
+    ScalarRoot(Position, JsonToken),
+    InvalidUtf8(Position, u64),
+    TooManyKeys(Position, usize),
+    TooManyElements(Position, usize),
+    NoValue(Position),
+    UnsortedKey(Position, String, String),
+    MissingKey(Position, String),
+    MistypedKey(Position, String, ValueKind, ValueKind),
+    TrailingWhitespace(Position),
+    UnexpectedByteOrderMark(Position),
+    EmptyRoot(Position),
+    MissingDelimiter(Position, u8),
+}
+impl VerifyError {
+    /// Wraps a tokenizer error, singling out invalid UTF-8 detected by a `Utf8Reader` and byte
+    /// order marks after the beginning of the input.
+    pub(crate) fn from_tokenizer(position: Position, error: TokenizerError) -> Self {
+        match &error {
+            TokenizerError::Io(io_error) => {
+                let invalid_utf8 = io_error.get_ref()
+                    .and_then(|inner| inner.downcast_ref::<InvalidUtf8Error>());
+                if let Some(invalid_utf8) = invalid_utf8 {
+                    return Self::InvalidUtf8(position, invalid_utf8.offset);
+                }
             },
-        };
+            // a leading byte order mark is merely not allowed; any other is most likely a mistake
+            TokenizerError::IllegalWhitespace('\u{FEFF}') if position.offset > 0 => {
+                return Self::UnexpectedByteOrderMark(position);
+            },
+            _ => {},
+        }
+        Self::Tokenizer(position, error)
+    }
 
-        match &tok {
-            JsonToken::String(s) => {
-                let processed_string = match interpret_string(s) {
-                    Ok(ps) => ps,
-                    Err(e) => {
-                        eprintln!("invalid string: {}", e);
-                        return false;
-                    },
-                };
+    /// Describes the garbage that begins at the given position by reading the token there.
+    ///
+    /// Only a bounded prefix of the garbage is read, so describing it does not take arbitrarily
+    /// long even if it is e.g. a huge string.
+    pub(crate) fn trailing_garbage<R: BufRead>(position: Position, json_reader: R, options: &TokenizerOptions) -> Self {
+        const MAX_GARBAGE_PREFIX: u64 = 64;
 
-                // strings can be keys or values
-                if expects.contains(ParserExpects::KEY) {
-                    match json_stack.last_mut() {
-                        Some(JsonStackValue::Object(obj)) => {
-                            if obj.known_keys.contains(&processed_string) {
-                                eprintln!("duplicate key {:?} at {:?}", processed_string, json_stack);
-                                return false;
-                            }
-                            obj.known_keys.insert(processed_string.clone());
-                            obj.current_key = Some(processed_string);
-                        },
-                        other => {
-                            panic!("parser expects KEY but top stack value is {:?}", other);
-                        },
-                    }
-                    expects = ParserExpects::COLON;
-                } else if expects.contains(ParserExpects::VALUE) {
-                    // what's next?
-                    match json_stack.last() {
-                        Some(JsonStackValue::Array(_)) => {
-                            expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
-                        },
-                        Some(JsonStackValue::Object(_)) => {
-                            expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
-                        },
-                        None => {
-                            // end of document
-                            break;
-                        },
+        let mut prefix = json_reader.take(MAX_GARBAGE_PREFIX);
+        match read_next_token_with(&mut prefix, options) {
+            Ok(tok) => Self::TrailingGarbage(position, tok.map(|t| t.kind())),
+            Err(TokenizerError::IllegalWhitespace('\u{FEFF}')) => Self::UnexpectedByteOrderMark(position),
+            // the string goes on beyond the prefix
+            Err(TokenizerError::UnterminatedString(_)) if prefix.limit() == 0 => Self::TrailingGarbage(position, Some(TokenKind::String)),
+            // if not even a token can be read, the garbage stays unspecified
+            Err(_) => Self::TrailingGarbage(position, None),
+        }
+    }
+
+    /// Wraps an error from interpreting the string that begins at the given position, pinpointing
+    /// invalid UTF-8 within the document.
+    pub(crate) fn from_string(position: Position, json_chars: &[JsonChar], error: TokenizerError) -> Self {
+        if let TokenizerError::InvalidUtf8Sequence(_) = &error {
+            if let Some(offset) = invalid_utf8_offset(json_chars) {
+                return Self::InvalidUtf8InString(position, position.offset + offset, error);
+            }
+        }
+        Self::InvalidString(position, error)
+    }
+
+    /// The position in the document at which the error was detected.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::Tokenizer(p, _) => *p,
+            Self::InvalidString(p, _) => *p,
+            Self::InvalidUtf8InString(p, _, _) => *p,
+            Self::UnexpectedToken(p, _, _) => *p,
+            Self::DuplicateKey(p, _, _) => *p,
+            Self::UnclosedDocument(p, _) => *p,
+            Self::TrailingGarbage(p, _) => *p,
+            Self::MissingRecordSeparator(p) => *p,
+            Self::MaxDepthExceeded(p, _) => *p,
+            Self::DocumentTooLarge(p, _) => *p,
+            Self::WrongRootKind(p, _, _) => *p,
+            Self::ScalarRoot(p, _) => *p,
+            Self::InvalidUtf8(p, _) => *p,
+            Self::TooManyKeys(p, _) => *p,
+            Self::TooManyElements(p, _) => *p,
+            Self::NoValue(p) => *p,
+            Self::UnsortedKey(p, _, _) => *p,
+            Self::MissingKey(p, _) => *p,
+            Self::MistypedKey(p, _, _, _) => *p,
+            Self::TrailingWhitespace(p) => *p,
+            Self::UnexpectedByteOrderMark(p) => *p,
+            Self::EmptyRoot(p) => *p,
+            Self::MissingDelimiter(p, _) => *p,
+        }
+    }
+
+    /// A short, stable, machine-readable identifier of the kind of error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Tokenizer(_, _) => "tokenizer",
+            Self::InvalidString(_, _) => "invalid-string",
+            Self::InvalidUtf8InString(_, _, _) => "invalid-utf8-in-string",
+            Self::UnexpectedToken(_, _, _) => "unexpected-token",
+            Self::DuplicateKey(_, _, _) => "duplicate-key",
+            Self::UnclosedDocument(_, _) => "unclosed-document",
+            Self::TrailingGarbage(_, _) => "trailing-garbage",
+            Self::MissingRecordSeparator(_) => "missing-record-separator",
+            Self::MaxDepthExceeded(_, _) => "max-depth-exceeded",
+            Self::DocumentTooLarge(_, _) => "document-too-large",
+            Self::WrongRootKind(_, _, _) => "wrong-root-kind",
+            Self::ScalarRoot(_, _) => "scalar-root",
+            Self::InvalidUtf8(_, _) => "invalid-utf8",
+            Self::TooManyKeys(_, _) => "too-many-keys",
+            Self::TooManyElements(_, _) => "too-many-elements",
+            Self::NoValue(_) => "no-value",
+            Self::UnsortedKey(_, _, _) => "unsorted-key",
+            Self::MissingKey(_, _) => "missing-key",
+            Self::MistypedKey(_, _, _, _) => "mistyped-key",
+            Self::TrailingWhitespace(_) => "trailing-whitespace",
+            Self::UnexpectedByteOrderMark(_) => "unexpected-byte-order-mark",
+            Self::EmptyRoot(_) => "empty-root",
+            Self::MissingDelimiter(_, _) => "missing-delimiter",
+        }
+    }
+}
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tokenizer(_, e) => write!(f, "failed to take next token: {}", e),
+            Self::InvalidString(_, e) => write!(f, "invalid string: {}", e),
+            Self::InvalidUtf8InString(_, offset, _) => write!(f, "invalid UTF-8 sequence at offset {} in string", offset),
+            Self::UnexpectedToken(_, tok, expects) => write!(f, "obtained {:?}, expected {}", tok, expects),
+            Self::DuplicateKey(_, key, previous_keys) => {
+                write!(f, "duplicate key {:?}; keys so far were ", key)?;
+                for (i, previous_key) in previous_keys.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
                     }
-                } else {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
+                    write!(f, "{:?}", previous_key)?;
                 }
+                write!(f, " in order")
             },
-            JsonToken::Null|JsonToken::True|JsonToken::False|JsonToken::Number(_) => {
-                // singular value
-                if !expects.contains(ParserExpects::VALUE) {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
-                }
-
-                // what's next?
-                match json_stack.last() {
-                    Some(JsonStackValue::Array(_)) => {
-                        expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
-                    },
-                    Some(JsonStackValue::Object(_)) => {
-                        expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
-                    },
-                    None => {
-                        // end of document
-                        break;
-                    },
-                }
+            Self::UnclosedDocument(_, depth) => write!(f, "JSON document ends without closing {} containers", depth),
+            Self::TrailingGarbage(_, kind) => match kind {
+                None => write!(f, "trailing garbage at end of document"),
+                Some(TokenKind::Comma) => write!(f, "trailing comma"),
+                Some(TokenKind::OpeningBrace) => write!(f, "trailing garbage: another object begins"),
+                Some(TokenKind::OpeningBracket) => write!(f, "trailing garbage: another array begins"),
+                Some(TokenKind::ClosingBrace) => write!(f, "trailing garbage: unmatched closing brace"),
+                Some(TokenKind::ClosingBracket) => write!(f, "trailing garbage: unmatched closing bracket"),
+                Some(TokenKind::Colon) => write!(f, "trailing garbage: colon"),
+                Some(other) => write!(f, "trailing garbage: another value ({}) follows", other),
             },
-            JsonToken::Colon => {
-                if !expects.contains(ParserExpects::COLON) {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
-                }
+            Self::MissingRecordSeparator(_) => write!(f, "record does not begin with a record separator"),
+            Self::MaxDepthExceeded(_, max_depth) => write!(f, "nesting exceeds the maximum depth of {}", max_depth),
+            Self::DocumentTooLarge(_, max_bytes) => write!(f, "document is larger than the maximum of {} bytes", max_bytes),
+            Self::WrongRootKind(_, kind, tok) => write!(f, "document root must be {}, but it begins with {:?}", kind, tok),
+            Self::ScalarRoot(_, tok) => write!(f, "document root must be an object or an array, but it is {:?}", tok),
+            Self::InvalidUtf8(_, offset) => write!(f, "invalid UTF-8 sequence at offset {}", offset),
+            Self::TooManyKeys(_, max_keys) => write!(f, "object has more than the maximum of {} keys", max_keys),
+            Self::TooManyElements(_, max_elements) => write!(f, "array has more than the maximum of {} elements", max_elements),
+            Self::NoValue(_) => write!(f, "document does not contain a value"),
+            Self::UnsortedKey(_, key, previous_key) => write!(f, "key {:?} should come before the preceding key {:?}", key, previous_key),
+            Self::MissingKey(_, key) => write!(f, "required key {:?} is missing", key),
+            Self::MistypedKey(_, key, required, actual) => write!(f, "value of key {:?} must be {}, but it is {}", key, required, actual),
+            Self::TrailingWhitespace(_) => write!(f, "whitespace follows the end of the document"),
+            Self::UnexpectedByteOrderMark(_) => write!(f, "byte order mark (U+FEFF) within the document"),
+            Self::EmptyRoot(_) => write!(f, "document root is an empty object or array"),
+            Self::MissingDelimiter(_, delimiter) => write!(f, "input ends before the delimiter {:?} that ends the prefix", char::from(*delimiter)),
+        }
+    }
+}
+impl std::error::Error for VerifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Tokenizer(_, e) => Some(e),
+            Self::InvalidString(_, e) => Some(e),
+            Self::InvalidUtf8InString(_, _, e) => Some(e),
+            Self::UnexpectedToken(_, _, _) => None,
+            Self::DuplicateKey(_, _, _) => None,
+            Self::UnclosedDocument(_, _) => None,
+            Self::TrailingGarbage(_, _) => None,
+            Self::MissingRecordSeparator(_) => None,
+            Self::MaxDepthExceeded(_, _) => None,
+            Self::DocumentTooLarge(_, _) => None,
+            Self::WrongRootKind(_, _, _) => None,
+            Self::ScalarRoot(_, _) => None,
+            Self::InvalidUtf8(_, _) => None,
+            Self::TooManyKeys(_, _) => None,
+            Self::TooManyElements(_, _) => None,
+            Self::NoValue(_) => None,
+            Self::UnsortedKey(_, _, _) => None,
+            Self::MissingKey(_, _) => None,
+            Self::MistypedKey(_, _, _, _) => None,
+            Self::TrailingWhitespace(_) => None,
+            Self::UnexpectedByteOrderMark(_) => None,
+            Self::EmptyRoot(_) => None,
+            Self::MissingDelimiter(_, _) => None,
+        }
+    }
+}
 
-                // what's next?
-                match json_stack.last() {
-                    Some(JsonStackValue::Object(_)) => {
-                        expects = ParserExpects::VALUE;
-                    },
-                    other => {
-                        panic!("parser expects COLON but top stack value is {:?}", other);
-                    },
-                }
-            },
-            JsonToken::Comma => {
-                if !expects.contains(ParserExpects::COMMA) {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
-                }
 
-                // what's next?
-                match json_stack.last_mut() {
-                    Some(JsonStackValue::Array(arr)) => {
-                        arr.current_index += 1;
-                        expects = ParserExpects::VALUE;
-                    },
-                    Some(JsonStackValue::Object(obj)) => {
-                        obj.current_key = None;
-                        expects = ParserExpects::KEY;
-                    },
-                    other => {
-                        panic!("parser expects COLON but top stack value is {:?}", other);
-                    },
-                }
-            },
-            JsonToken::OpeningBracket => {
-                if !expects.contains(ParserExpects::VALUE) {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
-                }
+/// A verification error along with where in the structure of the document it was detected.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// The error.
+    pub error: VerifyError,
 
-                json_stack.push(JsonStackValue::Array(JsonArray::default()));
-                expects = ParserExpects::VALUE | ParserExpects::CLOSING_BRACKET;
-            },
-            JsonToken::ClosingBracket => {
-                if !expects.contains(ParserExpects::CLOSING_BRACKET) {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
-                }
+    /// The JSON Pointer (RFC 6901) to the value that was being processed when the error was
+    /// detected, e.g. `/items/12345`; empty at the root.
+    pub pointer: String,
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.error, self.error.position())?;
+        if self.pointer.len() > 0 {
+            write!(f, " in {}", self.pointer)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for Diagnostic {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
 
-                match json_stack.pop() {
-                    Some(JsonStackValue::Array(_)) => {},
-                    other => {
-                        panic!("parser expects CLOSING_BRACKET but popped stack value is {:?}", other);
-                    },
-                }
 
-                match json_stack.last() {
-                    Some(JsonStackValue::Array(_)) => {
-                        expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
-                    },
-                    Some(JsonStackValue::Object(_)) => {
-                        expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
-                    },
-                    None => {
-                        // end of document
-                        break;
-                    },
-                }
-            },
-            JsonToken::OpeningBrace => {
-                if !expects.contains(ParserExpects::VALUE) {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
-                }
+/// A finding that does not make the document invalid but might point to a problem.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Warning {
+    NonCanonicalNumber(Position, Vec<u8>, NumberQuirk),
+    IntegerWithExponent(Position, Vec<u8>),
+    NumberOverflowsDouble(Position, Vec<u8>, DoubleRangeViolation),
+    JsonpUnsafeCharacter(Position, char),
+}
+impl Warning {
+    /// The position in the document at which the warning was raised.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::NonCanonicalNumber(p, _, _) => *p,
+            Self::IntegerWithExponent(p, _) => *p,
+            Self::NumberOverflowsDouble(p, _, _) => *p,
+            Self::JsonpUnsafeCharacter(p, _) => *p,
+        }
+    }
+}
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonCanonicalNumber(_, number, quirk) => write!(f, "number {} is written unusually: {}", String::from_utf8_lossy(number), quirk),
+            Self::IntegerWithExponent(_, number) => write!(f, "number {} is an integer but written with an exponent", String::from_utf8_lossy(number)),
+            Self::NumberOverflowsDouble(_, number, violation) => write!(f, "number {} is out of range for a double and {}", String::from_utf8_lossy(number), violation),
+            Self::JsonpUnsafeCharacter(_, c) => write!(f, "string contains an unescaped U+{:04X}, which ends a line in JavaScript", u32::from(*c)),
+        }
+    }
+}
 
-                json_stack.push(JsonStackValue::Object(JsonObject::default()));
-                expects = ParserExpects::KEY | ParserExpects::CLOSING_BRACE;
-            },
-            JsonToken::ClosingBrace => {
-                if !expects.contains(ParserExpects::CLOSING_BRACE) {
-                    eprintln!("obtained {:?}, expected {:?}", tok, expects);
-                    return false;
-                }
 
-                match json_stack.pop() {
-                    Some(JsonStackValue::Object(_)) => {},
-                    other => {
-                        panic!("parser expects CLOSING_BRACE but popped stack value is {:?}", other);
-                    },
-                }
+/// What to do when an object contains the same key more than once.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DuplicateKeyPolicy {
+    /// Fail verification.
+    #[default]
+    Reject,
 
-                match json_stack.last() {
-                    Some(JsonStackValue::Array(_)) => {
-                        expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
-                    },
-                    Some(JsonStackValue::Object(_)) => {
-                        expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
-                    },
-                    None => {
-                        // end of document
-                        break;
-                    },
-                }
-            },
+    /// Accept the document anyway.
+    Allow,
+}
+
+
+/// How keys are compared to find duplicates.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DuplicateKeyComparison {
+    /// Compare the decoded keys, so that e.g. `"a"` and `"\u0061"` are the same key.
+    #[default]
+    Decoded,
+
+    /// Compare the keys as they are written, so that e.g. `"a"` and `"\u0061"` are different keys.
+    RawBytes,
+}
+
+
+/// Whether keys that only differ in case are considered duplicates.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DuplicateKeyCaseFolding {
+    /// Keys that differ in case are different keys.
+    #[default]
+    CaseSensitive,
+
+    /// Keys are case-folded character by character before they are compared, so that e.g. `"Name"`
+    /// and `"name"` are the same key.
+    ///
+    /// Unicode simple case folding is approximated using the case mappings of the standard
+    /// library: a character is mapped to its uppercase and then to its lowercase form, where
+    /// either mapping is only followed if it results in a single character.
+    Simple,
+}
+impl DuplicateKeyCaseFolding {
+    fn fold<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        match self {
+            Self::CaseSensitive => Cow::Borrowed(key),
+            Self::Simple => Cow::Owned(key.chars().map(simple_fold).collect()),
         }
     }
+}
+
 
-    if json_stack.len() > 0 {
-        eprintln!("JSON document ends without closing: {:?}", json_stack);
-        return false;
+/// Maps a character to a representative of its case-insensitive equivalence class.
+fn simple_fold(c: char) -> char {
+    fn single(mut chars: impl Iterator<Item = char>) -> Option<char> {
+        let first = chars.next()?;
+        if chars.next().is_some() { None } else { Some(first) }
     }
 
-    if let Err(e) = skip_whitespace(&mut json_reader) {
-        eprintln!("failed to skip final whitespace: {}", e);
-        return false;
+    // going through uppercase first unifies e.g. final and medial sigma
+    let upper = single(c.to_uppercase()).unwrap_or(c);
+    single(upper.to_lowercase()).unwrap_or(upper)
+}
+
+
+/// The order in which the keys of an object can be required to be sorted.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum KeyOrder {
+    /// Sort by the bytes of the UTF-8 encoding, which is the same as sorting by code points.
+    Bytes,
+
+    /// Sort by UTF-16 code units, as required by the JSON Canonicalization Scheme (RFC 8785).
+    Utf16,
+}
+impl KeyOrder {
+    fn compare(&self, key1: &str, key2: &str) -> Ordering {
+        match self {
+            Self::Bytes => key1.as_bytes().cmp(key2.as_bytes()),
+            Self::Utf16 => key1.encode_utf16().cmp(key2.encode_utf16()),
+        }
     }
+}
+
 
-    match json_reader.peek() {
-        Ok(Some(_)) => {
-            eprintln!("trailing garbage at end of document");
-            false
-        },
-        Ok(None) => true,
-        Err(e) => {
-            eprintln!("failed to check for trailing garbage: {}", e);
-            false
-        },
+/// The kind of value that can be required at the root of a document.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RootKind {
+    Object,
+    Array,
+}
+impl RootKind {
+    fn matches(&self, first_token: &JsonToken) -> bool {
+        match self {
+            Self::Object => first_token == &JsonToken::OpeningBrace,
+            Self::Array => first_token == &JsonToken::OpeningBracket,
+        }
+    }
+}
+impl fmt::Display for RootKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Object => write!(f, "an object"),
+            Self::Array => write!(f, "an array"),
+        }
     }
 }
 
 
-#[cfg(test)]
-mod tests {
-    fn test_verify(json: &str) -> bool {
-        let cursor = std::io::Cursor::new(json);
-        super::verify(cursor)
+/// The kind of a JSON value.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ValueKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Boolean,
+    Null,
+}
+impl ValueKind {
+    /// Returns the kind of the value that begins with the given token, or `None` if the token
+    /// cannot begin a value.
+    pub fn of(first_token: &JsonToken) -> Option<Self> {
+        match first_token {
+            JsonToken::OpeningBrace => Some(Self::Object),
+            JsonToken::OpeningBracket => Some(Self::Array),
+            JsonToken::String(_) => Some(Self::String),
+            JsonToken::Number(_) => Some(Self::Number),
+            JsonToken::True|JsonToken::False => Some(Self::Boolean),
+            JsonToken::Null => Some(Self::Null),
+            _ => None,
+        }
     }
+}
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Object => write!(f, "an object"),
+            Self::Array => write!(f, "an array"),
+            Self::String => write!(f, "a string"),
+            Self::Number => write!(f, "a number"),
+            Self::Boolean => write!(f, "a boolean"),
+            Self::Null => write!(f, "null"),
+        }
+    }
+}
 
-    #[test]
-    fn test_empty() {
-        assert_eq!(test_verify("{}"), true);
-        assert_eq!(test_verify("[]"), true);
+
+/// The number of bytes between two invocations of the progress callback.
+const PROGRESS_INTERVAL: u64 = 1024 * 1024;
+
+
+/// A callback that is informed about the number of bytes read so far.
+#[derive(Clone)]
+struct ProgressCallback(Arc<dyn Fn(u64) + Send + Sync>);
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProgressCallback").finish_non_exhaustive()
     }
+}
 
-    #[test]
-    fn test_simple() {
-        assert_eq!(test_verify("{\"a\":0}"), true);
-        assert_eq!(test_verify("{\"a\":0,\"b\":1}"), true);
-        assert_eq!(test_verify("[0,1]"), true);
 
-        // wrong number of colons in dict
-        assert_eq!(test_verify("{\"a\",0}"), false);
-        assert_eq!(test_verify("{\"a\":0:1}"), false);
+/// A callback that is informed about warnings.
+#[derive(Clone)]
+struct WarningCallback(Arc<dyn Fn(&Warning) + Send + Sync>);
+impl fmt::Debug for WarningCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WarningCallback").finish_non_exhaustive()
+    }
+}
 
-        // colon in list
-        assert_eq!(test_verify("[\"a\":0]"), false);
 
-        // unterminated string
-        assert_eq!(test_verify("[\"a]"), false);
+/// A writer to which diagnostic messages are written.
+#[derive(Clone)]
+struct DiagnosticWriter(Arc<Mutex<dyn Write + Send>>);
+impl fmt::Debug for DiagnosticWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DiagnosticWriter").finish_non_exhaustive()
+    }
+}
 
-        // unterminated list
-        assert_eq!(test_verify("[\"a\""), false);
+impl_identity_comparisons!(ProgressCallback, WarningCallback, DiagnosticWriter);
 
-        // bareword
-        assert_eq!(test_verify("[a]"), false);
+
+/// A configurable JSON verifier.
+///
+/// The default configuration verifies strictly according to RFC 8259, rejecting duplicate keys
+/// and imposing no limits on nesting depth. The only exception is that a document without a value
+/// (empty or consisting only of whitespace) is accepted; see `allow_empty`.
+///
+/// Callbacks and writers are compared by identity, so verifiers are only equal if they share them
+/// (e.g. because one is a clone of the other).
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Verifier {
+    tokenizer_options: TokenizerOptions,
+    max_depth: Option<usize>,
+    max_keys_per_object: Option<usize>,
+    sorted_keys: Option<KeyOrder>,
+    max_array_elements: Option<usize>,
+    duplicate_key_policy: DuplicateKeyPolicy,
+    duplicate_key_comparison: DuplicateKeyComparison,
+    trim_keys_for_dedup: bool,
+    duplicate_key_case_folding: DuplicateKeyCaseFolding,
+    #[cfg(feature = "unicode-normalization")]
+    normalize_keys_for_dedup: bool,
+    max_bytes: Option<u64>,
+    require_root: Option<RootKind>,
+    required_keys: Vec<(String, ValueKind)>,
+    require_composite_root: bool,
+    reject_empty_root: bool,
+    reject_empty: bool,
+    forbid_trailing_whitespace: bool,
+    require_utf8: bool,
+    progress_callback: Option<ProgressCallback>,
+    warning_callback: Option<WarningCallback>,
+    diagnostic_writer: Option<DiagnosticWriter>,
+    lint_number_quirks: bool,
+    lint_integer_exponents: bool,
+    lint_double_range: bool,
+    lint_jsonp_safe: bool,
+    decimal_comma: bool,
+}
+impl Verifier {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    #[test]
-    fn test_boxed() {
-        assert_eq!(test_verify("{\"a\":{\"b\":[0,{\"c\":1}],\"d\":\"e\"}}"), true);
+    /// Sets the maximum nesting depth of arrays and objects.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
 
-        // swapped brackets
-        assert_eq!(test_verify("{\"a\":{\"b\":[0,{\"c\":1]},\"d\":\"e\"}}"), false);
+    /// Sets the maximum number of keys in each object.
+    pub fn max_keys_per_object(mut self, max_keys_per_object: usize) -> Self {
+        self.max_keys_per_object = Some(max_keys_per_object);
+        self
     }
 
-    #[test]
-    fn test_duplicate_key() {
-        assert_eq!(test_verify("{\"a\":0,\"a\":0}"), false);
+    /// Requires the keys of each object to be sorted in the given order.
+    pub fn require_sorted_keys(mut self, key_order: KeyOrder) -> Self {
+        self.sorted_keys = Some(key_order);
+        self
+    }
 
-        // consider different encoded forms equivalent
-        assert_eq!(test_verify("{\"a\":0,\"\\u0061\":0}"), false);
-        assert_eq!(test_verify("{\"/\":0,\"\\/\":0}"), false);
-        assert_eq!(test_verify("{\"/\":0,\"\\u002F\":0}"), false);
+    /// Requires the root to be an object containing the given key with a value of the given kind.
+    ///
+    /// May be called multiple times to require multiple keys.
+    pub fn require_key<K: Into<String>>(mut self, key: K, kind: ValueKind) -> Self {
+        self.required_keys.push((key.into(), kind));
+        self
     }
 
-    #[test]
-    fn test_trailing_garbage() {
-        assert_eq!(test_verify("{}{}"), false);
-        assert_eq!(test_verify("{},{}"), false);
-        assert_eq!(test_verify("{}true"), false);
-        assert_eq!(test_verify("{}0"), false);
+    /// Sets the maximum number of Unicode escapes (`\uXXXX`) in each string.
+    pub fn max_escapes_per_string(mut self, max_escapes_per_string: usize) -> Self {
+        self.tokenizer_options.max_escapes_per_string = Some(max_escapes_per_string);
+        self
+    }
+
+    /// Sets the maximum number of elements in each array.
+    pub fn max_array_elements(mut self, max_array_elements: usize) -> Self {
+        self.max_array_elements = Some(max_array_elements);
+        self
+    }
+
+    /// Sets whether `// line comments` and `/* block comments */` are allowed between tokens.
+    pub fn allow_comments(mut self, allow_comments: bool) -> Self {
+        self.tokenizer_options.allow_comments = allow_comments;
+        self
+    }
+
+    /// Sets whether strings may also be delimited by single quotes, as in JSON5.
+    pub fn allow_single_quotes(mut self, allow_single_quotes: bool) -> Self {
+        self.tokenizer_options.allow_single_quotes = allow_single_quotes;
+        self
+    }
+
+    /// Sets whether object keys may be identifiers (`[A-Za-z_$][A-Za-z0-9_$]*`) instead of
+    /// strings, as in JSON5.
+    pub fn allow_unquoted_keys(mut self, allow_unquoted_keys: bool) -> Self {
+        self.tokenizer_options.allow_unquoted_keys = allow_unquoted_keys;
+        self
+    }
+
+    /// Sets whether numbers may begin with a plus sign (e.g. `+5`).
+    pub fn allow_leading_plus(mut self, allow_leading_plus: bool) -> Self {
+        self.tokenizer_options.allow_leading_plus = allow_leading_plus;
+        self
+    }
+
+    /// Sets whether numbers may be hexadecimal (`0xFF`) or have a leading (`.5`) or trailing (`5.`)
+    /// decimal point, as in JSON5.
+    pub fn allow_json5_numbers(mut self, allow_json5_numbers: bool) -> Self {
+        self.tokenizer_options.allow_json5_numbers = allow_json5_numbers;
+        self
+    }
+
+    /// Sets a predicate that decides whether a byte that the number grammar would not accept at
+    /// that point is taken as part of the number after all; see
+    /// `TokenizerOptions::accept_number_byte`.
+    pub fn accept_number_byte<F: Fn(&[u8], u8) -> bool + Send + Sync + 'static>(mut self, predicate: F) -> Self {
+        self.tokenizer_options.accept_number_byte = Some(NumberBytePredicate::new(predicate));
+        self
+    }
+
+    /// Sets whether non-ASCII whitespace (such as U+00A0 NO-BREAK SPACE) and U+FEFF ZERO WIDTH
+    /// NO-BREAK SPACE are allowed between tokens.
+    pub fn allow_unicode_whitespace(mut self, allow_unicode_whitespace: bool) -> Self {
+        self.tokenizer_options.allow_unicode_whitespace = allow_unicode_whitespace;
+        self
+    }
+
+    /// Sets the ASCII control characters that are allowed as whitespace in addition to space, tab,
+    /// line feed and carriage return.
+    pub fn extra_whitespace(mut self, extra_whitespace: ExtraWhitespace) -> Self {
+        self.tokenizer_options.extra_whitespace = extra_whitespace;
+        self
+    }
+
+    /// Sets what to do when an object contains the same key more than once.
+    pub fn duplicate_key_policy(mut self, duplicate_key_policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_key_policy = duplicate_key_policy;
+        self
+    }
+
+    /// Sets how keys are compared to find duplicates.
+    pub fn duplicate_key_comparison(mut self, duplicate_key_comparison: DuplicateKeyComparison) -> Self {
+        self.duplicate_key_comparison = duplicate_key_comparison;
+        self
+    }
+
+    /// Sets whether keys that only differ in leading or trailing whitespace (e.g. `"a"` and `"a "`)
+    /// are considered duplicates.
+    ///
+    /// Only applies when comparing decoded keys; see `duplicate_key_comparison`.
+    pub fn trim_keys_for_dedup(mut self, trim_keys_for_dedup: bool) -> Self {
+        self.trim_keys_for_dedup = trim_keys_for_dedup;
+        self
+    }
+
+    /// Sets whether keys that only differ in case are considered duplicates.
+    ///
+    /// Only applies when comparing decoded keys; see `duplicate_key_comparison`.
+    pub fn duplicate_key_case_folding(mut self, duplicate_key_case_folding: DuplicateKeyCaseFolding) -> Self {
+        self.duplicate_key_case_folding = duplicate_key_case_folding;
+        self
+    }
+
+    /// Sets whether keys are brought into Unicode Normalization Form C before they are compared, so
+    /// that e.g. `"\u00e9"` and `"e\u0301"` (both `é`) are considered duplicates.
+    ///
+    /// Only applies when comparing decoded keys; see `duplicate_key_comparison`.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalize_keys_for_dedup(mut self, normalize_keys_for_dedup: bool) -> Self {
+        self.normalize_keys_for_dedup = normalize_keys_for_dedup;
+        self
+    }
+
+    /// Returns the canonical form of a decoded key, i.e. the form in which it is compared to find
+    /// duplicates and in which it is reported when it is one.
+    ///
+    /// Keys that only differ in aspects disregarded by the options (whitespace around them, case or
+    /// normalization form) have the same canonical form, so the report does not depend on which of
+    /// them appeared first.
+    fn canonical_key<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        let key = if self.trim_keys_for_dedup { key.trim() } else { key };
+        #[cfg(feature = "unicode-normalization")]
+        if self.normalize_keys_for_dedup && !is_nfc(key) {
+            let normalized: String = key.nfc().collect();
+            return Cow::Owned(self.duplicate_key_case_folding.fold(&normalized).into_owned());
+        }
+        self.duplicate_key_case_folding.fold(key)
+    }
+
+    /// Sets the maximum number of bytes that are read from the input.
+    ///
+    /// Input beyond this limit is never read; if there is any, verification fails.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Requires the root of each document to be of the given kind.
+    ///
+    /// By default, any JSON value is accepted as the root.
+    pub fn require_root(mut self, root_kind: RootKind) -> Self {
+        self.require_root = Some(root_kind);
+        self
+    }
+
+    /// Sets whether the root of each document must be an object or an array, as required by the
+    /// obsolete RFC 4627.
+    ///
+    /// By default, a scalar value (string, number, boolean or null) is accepted as the root too, as
+    /// allowed by RFC 8259.
+    pub fn require_composite_root(mut self, require_composite_root: bool) -> Self {
+        self.require_composite_root = require_composite_root;
+        self
+    }
+
+    /// Sets whether a root object or array without any members or elements (`{}` or `[]`) fails
+    /// verification. Empty objects and arrays within the root are accepted regardless.
+    pub fn reject_empty_root(mut self, reject_empty_root: bool) -> Self {
+        self.reject_empty_root = reject_empty_root;
+        self
+    }
+
+    /// Sets whether a document without a value, i.e. one that is empty or consists only of
+    /// whitespace (and anything else that may be skipped between tokens, such as comments), is
+    /// accepted.
+    ///
+    /// RFC 8259 requires a document to contain a value; with `false`, such a document fails
+    /// verification with `VerifyError::NoValue`. For compatibility with earlier versions, the
+    /// default is `true`.
+    ///
+    /// This does not apply to streams and JSON text sequences, which may contain any number of
+    /// documents.
+    pub fn allow_empty(mut self, allow_empty: bool) -> Self {
+        self.reject_empty = !allow_empty;
+        self
+    }
+
+    /// Sets whether the document must end immediately after its value, without even whitespace
+    /// (such as a final newline) following it.
+    pub fn forbid_trailing_whitespace(mut self, forbid_trailing_whitespace: bool) -> Self {
+        self.forbid_trailing_whitespace = forbid_trailing_whitespace;
+        self
+    }
+
+    /// Sets whether the whole input, not only the contents of strings, must be valid UTF-8.
+    ///
+    /// The input is validated as it is read; the first invalid sequence fails verification with
+    /// `VerifyError::InvalidUtf8`, which contains its exact byte offset.
+    pub fn require_utf8(mut self, require_utf8: bool) -> Self {
+        self.require_utf8 = require_utf8;
+        self
+    }
+
+    /// Sets a callback that is regularly called with the number of bytes read so far.
+    ///
+    /// The callback is called whenever another mebibyte has been read.
+    pub fn on_progress<F: Fn(u64) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.progress_callback = Some(ProgressCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Sets a callback that is called with each warning raised by the enabled lints.
+    ///
+    /// Warnings do not influence the result of verification.
+    pub fn on_warning<F: Fn(&Warning) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.warning_callback = Some(WarningCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Sets the writer to which `report` writes diagnostic messages instead of standard error.
+    pub fn diagnostic_writer<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.diagnostic_writer = Some(DiagnosticWriter(Arc::new(Mutex::new(writer))));
+        self
+    }
+
+    /// Writes a message describing the error to the diagnostic writer (standard error by default).
+    pub fn report(&self, error: &VerifyError) -> io::Result<()> {
+        self.write_diagnostic(format_args!("{} at {}", error, error.position()))
+    }
+
+    /// Writes a message describing the error and where it was detected to the diagnostic writer
+    /// (standard error by default).
+    pub fn report_diagnostic(&self, diagnostic: &Diagnostic) -> io::Result<()> {
+        self.write_diagnostic(format_args!("{}", diagnostic))
+    }
+
+    fn write_diagnostic(&self, message: fmt::Arguments<'_>) -> io::Result<()> {
+        match &self.diagnostic_writer {
+            Some(writer) => {
+                let mut writer = writer.0.lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                writeln!(writer, "{}", message)
+            },
+            None => writeln!(io::stderr(), "{}", message),
+        }
+    }
+
+    /// Sets whether a warning is raised for each number that is written in a valid but unusual way,
+    /// e.g. with leading zeros in its exponent (`1e007`).
+    pub fn lint_number_quirks(mut self, lint_number_quirks: bool) -> Self {
+        self.lint_number_quirks = lint_number_quirks;
+        self
+    }
+
+    /// Sets whether a warning is raised for each number that is written with an exponent although
+    /// its value is an integer, e.g. `1e5` or `2.5e1`.
+    pub fn lint_integer_exponents(mut self, lint_integer_exponents: bool) -> Self {
+        self.lint_integer_exponents = lint_integer_exponents;
+        self
+    }
+
+    /// Sets whether a warning is raised for each number that is out of range for a double-precision
+    /// floating-point value, i.e. one that becomes infinite (e.g. `1e999`) or zero (e.g. `1e-999`)
+    /// when parsed as such.
+    pub fn lint_double_range(mut self, lint_double_range: bool) -> Self {
+        self.lint_double_range = lint_double_range;
+        self
+    }
+
+    /// Sets whether a warning is raised for each string that contains an unescaped U+2028 (LINE
+    /// SEPARATOR) or U+2029 (PARAGRAPH SEPARATOR). JSON allows both within strings, but JavaScript
+    /// before ES2019 does not, which breaks consumers that `eval` the document or serve it as JSONP.
+    pub fn lint_jsonp_safe(mut self, lint_jsonp_safe: bool) -> Self {
+        self.lint_jsonp_safe = lint_jsonp_safe;
+        self
+    }
+
+    /// Sets whether a comma may take the place of the decimal point in a number (e.g. `3,14`), as
+    /// in some European exports.
+    ///
+    /// Since `[3,14]` is a valid array of two numbers, a decimal comma is only recognized where a
+    /// comma followed by a digit would otherwise be invalid: in the value of an object member
+    /// (where a key must follow a separating comma) and as the root value. The comma must be
+    /// directly followed by at least one digit and the number must not contain a decimal point or
+    /// an exponent before it. The number is kept as written, comma included.
+    pub fn decimal_comma(mut self, decimal_comma: bool) -> Self {
+        self.decimal_comma = decimal_comma;
+        self
+    }
+
+    fn warn(&self, warning: Warning) {
+        if let Some(warning_callback) = &self.warning_callback {
+            (warning_callback.0)(&warning);
+        }
+    }
+
+    fn lint_number(&self, token_position: Position, number: &[u8]) {
+        if self.warning_callback.is_none() {
+            // nobody is listening
+            return;
+        }
+
+        if self.lint_number_quirks {
+            for quirk in number_quirks(number) {
+                self.warn(Warning::NonCanonicalNumber(token_position, number.to_vec(), quirk));
+            }
+        }
+
+        if self.lint_integer_exponents && is_integer_with_exponent(number) {
+            self.warn(Warning::IntegerWithExponent(token_position, number.to_vec()));
+        }
+
+        if self.lint_double_range {
+            if let Some(violation) = double_range_violation(number) {
+                self.warn(Warning::NumberOverflowsDouble(token_position, number.to_vec(), violation));
+            }
+        }
+    }
+
+    fn lint_string(&self, token_position: Position, chars: &[JsonChar]) {
+        if self.warning_callback.is_none() {
+            // nobody is listening
+            return;
+        }
+
+        if self.lint_jsonp_safe {
+            // escaped forms (\u2028) are harmless; look for the raw UTF-8 sequences
+            for window in chars.windows(3) {
+                match window {
+                    [JsonChar::Byte(0xE2), JsonChar::Byte(0x80), JsonChar::Byte(0xA8)] => self.warn(Warning::JsonpUnsafeCharacter(token_position, '\u{2028}')),
+                    [JsonChar::Byte(0xE2), JsonChar::Byte(0x80), JsonChar::Byte(0xA9)] => self.warn(Warning::JsonpUnsafeCharacter(token_position, '\u{2029}')),
+                    _ => {},
+                }
+            }
+        }
+    }
+
+    fn limit_reader<R: BufRead>(&self, json_reader: R) -> PositionReader<Take<MaybeUtf8Reader<R>>> {
+        let json_reader = if self.require_utf8 {
+            MaybeUtf8Reader::Checked(Utf8Reader::new(json_reader))
+        } else {
+            MaybeUtf8Reader::Unchecked(json_reader)
+        };
+        PositionReader::new(json_reader.take(self.max_bytes.unwrap_or(u64::MAX)))
+    }
+
+    /// Returns the appropriate error if the input was cut off at the maximum number of bytes.
+    fn check_max_bytes<R: BufRead>(&self, json_reader: &mut PositionReader<Take<R>>) -> Result<(), VerifyError> {
+        let max_bytes = match self.max_bytes {
+            Some(mb) => mb,
+            None => return Ok(()),
+        };
+        let position = json_reader.position();
+        let limited_reader = json_reader.get_mut();
+        if limited_reader.limit() > 0 {
+            // we stopped before the limit
+            return Ok(());
+        }
+        match limited_reader.get_mut().peek() {
+            Ok(Some(_)) => Err(VerifyError::DocumentTooLarge(position, max_bytes)),
+            Ok(None) => Ok(()),
+            Err(e) => Err(VerifyError::from_tokenizer(position, e.into())),
+        }
+    }
+
+    /// Verifies that the reader contains exactly one JSON document.
+    pub fn verify<R: BufRead>(&self, json_reader: R) -> Result<(), VerifyError> {
+        let mut json_reader = self.limit_reader(json_reader);
+        let result = self.verify_document(&mut json_reader, &mut ParserState::new(self));
+        self.check_max_bytes(&mut json_reader)?;
+        result
+    }
+
+    /// Skips everything up to and including the first occurrence of the delimiter (e.g. the space
+    /// after a log level or the end of a header line), then verifies that the rest of the reader
+    /// contains exactly one JSON document.
+    ///
+    /// Positions in errors are relative to the beginning of the reader, prefix included. If the
+    /// delimiter does not occur, verification fails with `VerifyError::MissingDelimiter`.
+    pub fn verify_after_prefix<R: BufRead>(&self, json_reader: R, skip_until: u8) -> Result<(), VerifyError> {
+        let mut json_reader = self.limit_reader(json_reader);
+        loop {
+            match json_reader.read_byte() {
+                Ok(Some(b)) if b == skip_until => break,
+                Ok(Some(_)) => {},
+                Ok(None) => {
+                    self.check_max_bytes(&mut json_reader)?;
+                    return Err(VerifyError::MissingDelimiter(json_reader.position(), skip_until));
+                },
+                Err(e) => return Err(VerifyError::from_tokenizer(json_reader.position(), e.into())),
+            }
+        }
+        let result = self.verify_document(&mut json_reader, &mut ParserState::new(self));
+        self.check_max_bytes(&mut json_reader)?;
+        result
+    }
+
+    /// Verifies that the reader contains exactly one JSON document, reporting the error (if any) to
+    /// the diagnostic writer.
+    pub fn verify_and_report<R: BufRead>(&self, json_reader: R) -> bool {
+        match self.diagnose(json_reader) {
+            Ok(_) => true,
+            Err(d) => {
+                // failing to report does not change the verdict
+                let _ = self.report_diagnostic(&d);
+                false
+            },
+        }
+    }
+
+    /// Verifies that the reader contains exactly one JSON document and returns statistics about it.
+    pub fn verify_with_stats<R: BufRead>(&self, json_reader: R) -> Result<Stats, VerifyError> {
+        self.diagnose(json_reader)
+            .map_err(|d| d.error)
+    }
+
+    /// Verifies that the reader contains exactly one JSON document and returns statistics about it;
+    /// on failure, also returns where in the structure of the document the error was detected.
+    pub fn diagnose<R: BufRead>(&self, json_reader: R) -> Result<Stats, Diagnostic> {
+        let mut json_reader = self.limit_reader(json_reader);
+        let mut state = ParserState::new(self);
+        let result = self.verify_document(&mut json_reader, &mut state)
+            .and_then(|()| self.check_max_bytes(&mut json_reader));
+        if let Err(error) = result {
+            return Err(Diagnostic { error, pointer: state.pointer() });
+        }
+
+        let mut stats = state.stats();
+        stats.bytes = json_reader.position().offset;
+        Ok(stats)
+    }
+
+    /// Verifies that the reader contains exactly one JSON document, continuing after errors where
+    /// possible and returning up to the given number of them.
+    ///
+    /// After an error, the verifier makes an educated guess about what was meant (e.g. a missing
+    /// comma or a superfluous one before a closing bracket) and carries on. Errors that cannot be
+    /// recovered from, such as exceeded limits or I/O errors, end verification. Since the guesses
+    /// may be wrong, errors after the first one may be consequences of an earlier one.
+    pub fn verify_collecting<R: BufRead>(&self, json_reader: R, max_errors: usize) -> Vec<VerifyError> {
+        self.diagnose_collecting(json_reader, max_errors)
+            .into_iter()
+            .map(|d| d.error)
+            .collect()
+    }
+
+    /// Like `verify_collecting`, but also returns where in the structure of the document each
+    /// error was detected.
+    pub fn diagnose_collecting<R: BufRead>(&self, json_reader: R, max_errors: usize) -> Vec<Diagnostic> {
+        let mut json_reader = self.limit_reader(json_reader);
+        let mut state = ParserState::new(self);
+        let mut diagnostics = Vec::new();
+        if max_errors == 0 {
+            return diagnostics;
+        }
+        let mut errors = ErrorCollector {
+            state: &mut state,
+            diagnostics: &mut diagnostics,
+        };
+        self.collect_errors(&mut json_reader, &mut errors, max_errors);
+        if errors.len() < max_errors {
+            if let Err(e) = self.check_max_bytes(&mut json_reader) {
+                errors.push(e);
+            }
+        }
+        diagnostics
+    }
+
+    fn collect_errors<R: BufRead>(&self, json_reader: &mut PositionReader<R>, errors: &mut ErrorCollector<'_, '_>, max_errors: usize) {
+        let mut saw_value = false;
+        let mut complete = false;
+
+        while !complete {
+            if errors.len() >= max_errors {
+                return;
+            }
+
+            let previous_offset = json_reader.position().offset;
+            if let Err(e) = skip_insignificant(&mut *json_reader, &self.tokenizer_options) {
+                errors.push(VerifyError::from_tokenizer(json_reader.position(), e));
+                return;
+            }
+            let token_position = json_reader.position();
+            let (tok, separator_position) = match errors.state.read_token(&mut *json_reader) {
+                Ok(Some(t)) => t,
+                Ok(None) => break,
+                Err(error) => {
+                    if let VerifyError::Tokenizer(_, TokenizerError::Io(_)) = &error {
+                        errors.push(error);
+                        return;
+                    }
+                    errors.push(error);
+
+                    // skip the rest of the token, then pretend it was a valid one
+                    if json_reader.position().offset == token_position.offset {
+                        json_reader.consume(1);
+                    }
+                    if let Err(e) = skip_to_token_boundary(&mut *json_reader, &self.tokenizer_options) {
+                        errors.push(VerifyError::from_tokenizer(json_reader.position(), e.into()));
+                        return;
+                    }
+                    saw_value = true;
+                    complete = errors.state.accept_placeholder() == StepOutcome::Complete;
+                    continue;
+                },
+            };
+            saw_value = true;
+
+            if let Some(progress_callback) = &self.progress_callback {
+                let offset = json_reader.position().offset;
+                if offset / PROGRESS_INTERVAL > previous_offset / PROGRESS_INTERVAL {
+                    (progress_callback.0)(offset);
+                }
+            }
+
+            let mut fed = errors.state.feed_token(tok, token_position);
+            if let Some(comma_position) = separator_position {
+                fed = match fed {
+                    Ok(step_outcome) => errors.state.feed_separator(step_outcome, comma_position),
+                    Err(e) => match errors.push_and_recover(e) {
+                        Some(step_outcome) => errors.state.feed_separator(step_outcome, comma_position),
+                        None => return,
+                    },
+                };
+            }
+            match fed {
+                Ok(step_outcome) => complete = step_outcome == StepOutcome::Complete,
+                Err(e) => {
+                    match errors.push_and_recover(e) {
+                        Some(step_outcome) => complete = step_outcome == StepOutcome::Complete,
+                        None => return,
+                    }
+                },
+            }
+        }
+
+        if errors.len() >= max_errors {
+            return;
+        }
+        let depth = errors.state.depth();
+        if depth > 0 {
+            errors.push(VerifyError::UnclosedDocument(json_reader.position(), depth));
+            return;
+        }
+        if !saw_value && self.reject_empty {
+            errors.push(VerifyError::NoValue(json_reader.position()));
+            return;
+        }
+
+        if let Err(e) = self.check_document_end(json_reader) {
+            errors.push(e);
+        }
+    }
+
+    /// Verifies that the reader contains exactly one JSON document, collecting all duplicate keys
+    /// instead of failing at the first one.
+    ///
+    /// Returns each repeated key along with the JSON Pointer to the repeated member, in document
+    /// order. The duplicate key policy is not consulted.
+    pub fn find_duplicate_keys<R: BufRead>(&self, json_reader: R) -> Result<Vec<(String, String)>, VerifyError> {
+        let mut json_reader = self.limit_reader(json_reader);
+        let mut state = ParserState::new(self);
+        state.duplicates = Some(Vec::new());
+        let result = self.verify_document(&mut json_reader, &mut state);
+        self.check_max_bytes(&mut json_reader)?;
+        result.map(|()| state.duplicates.take().unwrap_or_default())
+    }
+
+    fn verify_document<R: BufRead>(&self, json_reader: &mut PositionReader<R>, state: &mut ParserState<'_>) -> Result<(), VerifyError> {
+        let saw_value = self.verify_one_value(&mut *json_reader, state)?;
+        if !saw_value && self.reject_empty {
+            return Err(VerifyError::NoValue(json_reader.position()));
+        }
+
+        self.check_document_end(json_reader)
+    }
+
+    /// Checks that only insignificant bytes follow the end of the document, or nothing at all if
+    /// trailing whitespace is forbidden.
+    fn check_document_end<R: BufRead>(&self, json_reader: &mut PositionReader<R>) -> Result<(), VerifyError> {
+        let end_position = json_reader.position();
+        skip_insignificant(&mut *json_reader, &self.tokenizer_options)
+            .map_err(|e| VerifyError::from_tokenizer(json_reader.position(), e))?;
+
+        let garbage_position = json_reader.position();
+        match json_reader.peek() {
+            Ok(Some(_)) => Err(VerifyError::trailing_garbage(garbage_position, &mut *json_reader, &self.tokenizer_options)),
+            Ok(None) if self.forbid_trailing_whitespace && garbage_position != end_position => Err(VerifyError::TrailingWhitespace(end_position)),
+            Ok(None) => Ok(()),
+            Err(e) => Err(VerifyError::from_tokenizer(garbage_position, e.into())),
+        }
+    }
+
+    /// Verifies exactly one JSON value at the beginning of the reader, returning the number of
+    /// bytes it occupies (including any whitespace before it).
+    ///
+    /// Reading stops at the end of the value; whatever follows it is neither read nor verified, so
+    /// the reader can be used to continue reading the surrounding data. A number is delimited by
+    /// the first byte that cannot be part of it, which is only peeked at.
+    pub fn verify_value<R: BufRead>(&self, json_reader: R) -> Result<u64, VerifyError> {
+        let mut json_reader = self.limit_reader(json_reader);
+        self.verify_single_value(&mut json_reader)
+    }
+
+    /// Verifies exactly one JSON value at the beginning of the reader and consumes the whitespace
+    /// following it, returning the offset of the byte immediately after the value.
+    ///
+    /// The offset is relative to where the reader was when this function was called and does not
+    /// include the trailing whitespace, but the reader is left at the first byte after it, e.g. at
+    /// the beginning of the next frame.
+    pub fn verify_value_consuming<R: BufRead>(&self, json_reader: R) -> Result<u64, VerifyError> {
+        let mut json_reader = self.limit_reader(json_reader);
+        let end_offset = self.verify_single_value(&mut json_reader)?;
+        skip_insignificant(&mut json_reader, &self.tokenizer_options)
+            .map_err(|e| VerifyError::from_tokenizer(json_reader.position(), e))?;
+        Ok(end_offset)
+    }
+
+    /// Verifies one value, returning the offset immediately after it.
+    fn verify_single_value<R: BufRead>(&self, json_reader: &mut PositionReader<Take<R>>) -> Result<u64, VerifyError> {
+        let result = self.verify_one_value(&mut *json_reader, &mut ParserState::new(self));
+        match result {
+            Ok(true) => Ok(json_reader.position().offset),
+            Ok(false) => Err(VerifyError::NoValue(json_reader.position())),
+            Err(e) => {
+                self.check_max_bytes(json_reader)?;
+                Err(e)
+            },
+        }
+    }
+
+    /// Verifies a stream of whitespace-separated JSON documents, returning the number of documents.
+    pub fn verify_stream<R: BufRead>(&self, json_reader: R) -> Result<usize, VerifyError> {
+        let mut json_reader = self.limit_reader(json_reader);
+        let result = self.verify_stream_documents(&mut json_reader);
+        self.check_max_bytes(&mut json_reader)?;
+        result
+    }
+
+    fn verify_stream_documents<R: BufRead>(&self, json_reader: &mut PositionReader<R>) -> Result<usize, VerifyError> {
+        let mut document_count = 0;
+        let mut state = ParserState::new(self);
+
+        loop {
+            skip_insignificant(&mut *json_reader, &self.tokenizer_options)
+                .map_err(|e| VerifyError::from_tokenizer(json_reader.position(), e))?;
+
+            // more input means another document
+            let peek_position = json_reader.position();
+            match json_reader.peek() {
+                Ok(Some(_)) => {},
+                Ok(None) => return Ok(document_count),
+                Err(e) => return Err(VerifyError::from_tokenizer(peek_position, e.into())),
+            }
+
+            self.verify_one_value(&mut *json_reader, &mut state)?;
+            document_count += 1;
+        }
+    }
+
+    /// Verifies an RFC 7464 JSON text sequence, in which each record is preceded by a record
+    /// separator (0x1E).
+    ///
+    /// Returns the verification result of each non-empty record. A malformed record does not stop
+    /// the verification of the following records; an I/O error does. If the maximum number of
+    /// bytes is exceeded, the result of the record that was cut off is replaced by the
+    /// corresponding error.
+    pub fn verify_json_seq<R: BufRead>(&self, json_reader: R) -> Vec<Result<(), VerifyError>> {
+        let mut json_reader = self.limit_reader(json_reader);
+        let mut results = self.verify_json_seq_records(&mut json_reader);
+        if let Err(e) = self.check_max_bytes(&mut json_reader) {
+            results.pop();
+            results.push(Err(e));
+        }
+        results
+    }
+
+    fn verify_json_seq_records<R: BufRead>(&self, json_reader: &mut PositionReader<R>) -> Vec<Result<(), VerifyError>> {
+        let mut results = Vec::new();
+        let mut state = ParserState::new(self);
+
+        loop {
+            let record_position = json_reader.position();
+            match json_reader.peek() {
+                Ok(Some(RECORD_SEPARATOR)) => {
+                    json_reader.consume(1);
+                },
+                Ok(Some(_)) => {
+                    // garbage before the record separator; skip the whole record
+                    results.push(Err(VerifyError::MissingRecordSeparator(record_position)));
+                    if let Err(e) = skip_to_record_separator(&mut *json_reader) {
+                        results.push(Err(VerifyError::from_tokenizer(json_reader.position(), e.into())));
+                        return results;
+                    }
+                    continue;
+                },
+                Ok(None) => return results,
+                Err(e) => {
+                    results.push(Err(VerifyError::from_tokenizer(record_position, e.into())));
+                    return results;
+                },
+            }
+
+            let mut record_reader = PositionReader::starting_at(
+                RecordReader::new(&mut *json_reader),
+                record_position,
+            );
+
+            // consecutive record separators do not denote empty records
+            if let Err(e) = skip_insignificant(&mut record_reader, &self.tokenizer_options) {
+                results.push(Err(VerifyError::from_tokenizer(record_reader.position(), e)));
+                return results;
+            }
+            match record_reader.peek() {
+                Ok(Some(_)) => {},
+                Ok(None) => continue,
+                Err(e) => {
+                    results.push(Err(VerifyError::from_tokenizer(record_reader.position(), e.into())));
+                    return results;
+                },
+            }
+
+            results.push(self.verify_document(&mut record_reader, &mut state));
+
+            // skip whatever is left of a malformed record
+            if let Err(e) = skip_to_record_separator(&mut *json_reader) {
+                results.push(Err(VerifyError::from_tokenizer(json_reader.position(), e.into())));
+                return results;
+            }
+        }
+    }
+
+    /// Returns the value at the given JSON Pointer (RFC 6901), e.g. `/items/0/name`, without
+    /// building up the whole document in memory.
+    ///
+    /// The document is verified up to the end of the value; the rest of the document is not read.
+    /// If the document does not contain the value, it is verified in full and `None` is returned.
+    /// The empty pointer refers to the whole document; a pointer that neither is empty nor
+    /// begins with `/` never refers to any value.
+    pub fn extract<R: BufRead>(&self, json_reader: R, pointer: &str) -> Result<Option<ExtractedValue>, VerifyError> {
+        // the value can only be found at the depth matching the number of reference tokens
+        let pointer_depth = pointer.matches('/').count();
+        let mut extracted: Option<ExtractedValue> = None;
+
+        self.scan::<_, _, VerifyError, _>(json_reader, |state, tok, span| {
+            let is_wanted = extracted.is_none()
+                && state.depth() == pointer_depth
+                && state.begins_value(&tok)
+                && json_pointer(&state.json_stack) == pointer;
+            if is_wanted {
+                extracted = Some(ExtractedValue {
+                    tokens: Vec::new(),
+                    span,
+                });
+            }
+
+            let captured_token = extracted.as_ref().map(|_| tok.clone());
+            let step_outcome = state.feed_token(tok, span.start)?;
+
+            if let Some(captured_token) = captured_token {
+                let mut value = extracted.take().expect("token captured without a value");
+                value.tokens.push(captured_token);
+                value.span.end = span.end;
+                if state.depth() == pointer_depth {
+                    // the value is complete
+                    return Ok(ControlFlow::Break(value));
+                }
+                extracted = Some(value);
+            }
+            Ok(ControlFlow::Continue(step_outcome))
+        })
+    }
+
+    /// Returns whether the object at the given JSON Pointer (RFC 6901) contains the given key.
+    ///
+    /// The document is verified only as far as necessary to answer the question. If the pointer
+    /// does not refer to any value or refers to a value that is not an object, the document is
+    /// verified in full and `false` is returned.
+    pub fn contains_key_at<R: BufRead>(&self, json_reader: R, pointer: &str, key: &str) -> Result<bool, VerifyError> {
+        let pointer_depth = pointer.matches('/').count();
+        let mut target_seen = false;
+        let mut in_object = false;
+
+        let found = self.scan::<_, _, VerifyError, _>(json_reader, |state, tok, span| {
+            if !target_seen && state.depth() == pointer_depth && state.begins_value(&tok) && json_pointer(&state.json_stack) == pointer {
+                // if this is not an object, the answer is no once the rest has been verified
+                target_seen = true;
+                in_object = tok == JsonToken::OpeningBrace;
+            }
+
+            let step_outcome = state.feed_token(tok, span.start)?;
+
+            if in_object {
+                if state.depth() == pointer_depth {
+                    // the object has been closed without the key
+                    return Ok(ControlFlow::Break(false));
+                }
+                if state.depth() == pointer_depth + 1 {
+                    if let Some(JsonStackValue::Object(obj)) = state.json_stack.last() {
+                        if obj.current_key.as_deref() == Some(key) {
+                            return Ok(ControlFlow::Break(true));
+                        }
+                    }
+                }
+            }
+            Ok(ControlFlow::Continue(step_outcome))
+        })?;
+        Ok(found.unwrap_or(false))
+    }
+
+    /// Verifies a document whose root must be an array and returns the number of elements in it.
+    ///
+    /// Fails with `VerifyError::WrongRootKind` if the root is not an array and with
+    /// `VerifyError::NoValue` if the document is empty.
+    pub fn count_root_array_elements<R: BufRead>(&self, json_reader: R) -> Result<usize, VerifyError> {
+        let verifier = self.clone()
+            .require_root(RootKind::Array)
+            .allow_empty(false);
+        let mut count = 0;
+        verifier.scan::<_, (), VerifyError, _>(json_reader, |state, tok, span| {
+            // each element of the root array begins with exactly one token at depth 1
+            let is_element = state.depth() == 1
+                && !matches!(tok, JsonToken::Comma | JsonToken::ClosingBracket);
+            let step_outcome = state.feed_token(tok, span.start)?;
+            if is_element {
+                count += 1;
+            }
+            Ok(ControlFlow::Continue(step_outcome))
+        })?;
+        Ok(count)
+    }
+
+    /// Verifies a document while passing each token (with its span) to the visitor, which must
+    /// feed it to the parser state. The visitor can end the scan early by returning a result.
+    ///
+    /// If the visitor never ends the scan, the whole document is verified and `None` is returned.
+    /// Errors of the visitor end the scan as well and are returned as they are.
+    pub(crate) fn scan<R, T, E, F>(&self, json_reader: R, mut visitor: F) -> Result<Option<T>, E>
+        where
+            R: BufRead,
+            E: From<VerifyError>,
+            F: FnMut(&mut ParserState<'_>, JsonToken, Span) -> Result<ControlFlow<T, StepOutcome>, E>,
+    {
+        let mut json_reader = self.limit_reader(json_reader);
+        let mut state = ParserState::new(self);
+        let mut saw_value = false;
+
+        loop {
+            skip_insignificant(&mut json_reader, &self.tokenizer_options)
+                .map_err(|e| VerifyError::from_tokenizer(json_reader.position(), e))?;
+            let token_position = json_reader.position();
+            let (tok, separator_position) = match state.read_token(&mut json_reader)? {
+                Some(t) => t,
+                None => break,
+            };
+            saw_value = true;
+
+            let span = Span {
+                start: token_position,
+                end: separator_position.unwrap_or(json_reader.position()),
+            };
+            let mut step_outcome = match visitor(&mut state, tok, span)? {
+                ControlFlow::Break(result) => return Ok(Some(result)),
+                ControlFlow::Continue(step_outcome) => step_outcome,
+            };
+            if let Some(comma_position) = separator_position {
+                if step_outcome == StepOutcome::Complete {
+                    // the comma follows the root value
+                    return Err(VerifyError::TrailingGarbage(comma_position, Some(TokenKind::Comma)).into());
+                }
+                let comma_span = Span {
+                    start: comma_position,
+                    end: json_reader.position(),
+                };
+                step_outcome = match visitor(&mut state, JsonToken::Comma, comma_span)? {
+                    ControlFlow::Break(result) => return Ok(Some(result)),
+                    ControlFlow::Continue(step_outcome) => step_outcome,
+                };
+            }
+            if step_outcome == StepOutcome::Complete {
+                break;
+            }
+        }
+
+        if state.depth() > 0 {
+            return Err(VerifyError::UnclosedDocument(json_reader.position(), state.depth()).into());
+        }
+        if !saw_value && self.reject_empty {
+            return Err(VerifyError::NoValue(json_reader.position()).into());
+        }
+
+        self.check_document_end(&mut json_reader)?;
+        self.check_max_bytes(&mut json_reader)?;
+        Ok(None)
+    }
+
+    /// Verifies a single value, returning whether there was one at all.
+    fn verify_one_value<R: BufRead>(&self, json_reader: &mut PositionReader<R>, state: &mut ParserState<'_>) -> Result<bool, VerifyError> {
+        state.reset();
+        let mut saw_value = false;
+
+        loop {
+            // take a token
+            let previous_offset = json_reader.position().offset;
+            skip_insignificant(&mut *json_reader, &self.tokenizer_options)
+                .map_err(|e| VerifyError::from_tokenizer(json_reader.position(), e))?;
+            let token_position = json_reader.position();
+            let (tok, separator_position) = match state.read_token(&mut *json_reader)? {
+                Some(t) => t,
+                None => break,
+            };
+            saw_value = true;
+
+            if let Some(progress_callback) = &self.progress_callback {
+                let offset = json_reader.position().offset;
+                if offset / PROGRESS_INTERVAL > previous_offset / PROGRESS_INTERVAL {
+                    (progress_callback.0)(offset);
+                }
+            }
+
+            let mut step_outcome = state.feed_token(tok, token_position)?;
+            if let Some(comma_position) = separator_position {
+                step_outcome = state.feed_separator(step_outcome, comma_position)?;
+            }
+
+            // once the root value is complete, we stop reading
+            if step_outcome == StepOutcome::Complete {
+                break;
+            }
+        }
+
+        if state.depth() > 0 {
+            return Err(VerifyError::UnclosedDocument(json_reader.position(), state.depth()));
+        }
+
+        Ok(saw_value)
+    }
+}
+
+
+/// Statistics about a verified document.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Stats {
+    /// The number of tokens in the document.
+    pub tokens: u64,
+
+    /// The number of bytes in the document, including insignificant whitespace.
+    pub bytes: u64,
+
+    /// The number of objects in the document.
+    pub objects: u64,
+
+    /// The number of arrays in the document.
+    pub arrays: u64,
+
+    /// The number of strings in the document, not counting object keys.
+    pub strings: u64,
+
+    /// The number of numbers in the document.
+    pub numbers: u64,
+
+    /// The number of `true` and `false` values in the document.
+    pub booleans: u64,
+
+    /// The number of `null` values in the document.
+    pub nulls: u64,
+
+    /// The deepest nesting of containers in the document.
+    pub max_depth: usize,
+
+    /// The length of the longest string in the document, including object keys, in decoded
+    /// characters.
+    pub longest_string: usize,
+
+    /// The length of the longest number in the document, in bytes.
+    pub longest_number: usize,
+}
+
+
+/// A value found by `Verifier::extract`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ExtractedValue {
+    /// The tokens that make up the value.
+    pub tokens: Vec<JsonToken>,
+
+    /// The area of the document occupied by the value.
+    pub span: Span,
+}
+
+
+/// Collects errors along with the location in the document at which the parser state was when each
+/// of them was detected.
+struct ErrorCollector<'s, 'v> {
+    state: &'s mut ParserState<'v>,
+    diagnostics: &'s mut Vec<Diagnostic>,
+}
+impl ErrorCollector<'_, '_> {
+    fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    fn push(&mut self, error: VerifyError) {
+        let pointer = self.state.pointer();
+        self.diagnostics.push(Diagnostic { error, pointer });
+    }
+
+    /// Adds the error returned by feeding a token, then attempts to recover from it.
+    fn push_and_recover(&mut self, error: VerifyError) -> Option<StepOutcome> {
+        // the location before recovery is the one at which the error was detected
+        let pointer = self.state.pointer();
+        let recovery = self.state.recover(&error);
+        self.diagnostics.push(Diagnostic { error, pointer });
+        recovery
+    }
+}
+
+
+/// The result of feeding a token to a `ParserState`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum StepOutcome {
+    /// The root value is not complete yet.
+    NeedMore,
+
+    /// The token completed the root value.
+    Complete,
+}
+
+
+/// The state of the verification of a single value, fed one token at a time.
+///
+/// After the root value is complete or an error has been returned, the state can be reset and
+/// reused for the next document; the allocations made so far are retained.
+#[derive(Clone, Debug)]
+pub struct ParserState<'v> {
+    verifier: &'v Verifier,
+    json_stack: Vec<JsonStackValue>,
+    expects: ParserExpects,
+    duplicates: Option<Vec<(String, String)>>,
+    seen_required_keys: Vec<bool>,
+    pending: PendingBytes,
+    stats: Stats,
+}
+impl<'v> ParserState<'v> {
+    /// Creates a parser state that verifies according to the options of the given verifier.
+    pub fn new(verifier: &'v Verifier) -> Self {
+        Self {
+            verifier,
+            json_stack: Vec::new(),
+            expects: ParserExpects::VALUE,
+            duplicates: None,
+            seen_required_keys: vec![false; verifier.required_keys.len()],
+            pending: PendingBytes::default(),
+            stats: Stats::default(),
+        }
+    }
+
+    /// Prepares the state for a new document, retaining the capacity of the stack.
+    pub fn reset(&mut self) {
+        self.json_stack.clear();
+        self.expects = ParserExpects::VALUE;
+        if let Some(dupes) = &mut self.duplicates {
+            dupes.clear();
+        }
+        self.seen_required_keys.fill(false);
+        self.pending.bytes.clear();
+        self.pending.position = Position::default();
+        self.pending.after_carriage_return = false;
+        self.pending.complete = false;
+        self.pending.scan = None;
+    }
+
+    /// The number of containers that are currently open.
+    pub fn depth(&self) -> usize {
+        self.json_stack.len()
+    }
+
+    /// Statistics about all the tokens fed so far, including those fed before a reset.
+    ///
+    /// The byte count is not known to the parser state and is always 0.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// The kinds of tokens that are valid next.
+    pub fn expects(&self) -> ParserExpects {
+        self.expects
+    }
+
+    /// The JSON Pointer (RFC 6901) to the value currently being processed, e.g. `/items/12345`.
+    ///
+    /// Between the members of an object, this is the pointer to the object itself.
+    pub fn pointer(&self) -> String {
+        json_pointer(&self.json_stack)
+    }
+
+    /// Reads the next token with the tokenizer options of the verifier.
+    ///
+    /// If the verifier allows decimal commas and this state accepts one after the number that was
+    /// read, a comma directly following the number is read as well. If digits follow it, it
+    /// becomes part of the number; otherwise it is a separator after all, and its position is
+    /// returned so that it can be fed with `feed_separator` after the number.
+    fn read_token<R: BufRead>(&self, json_reader: &mut PositionReader<R>) -> Result<Option<(JsonToken, Option<Position>)>, VerifyError> {
+        let token_position = json_reader.position();
+        let mut tok = match read_next_token_with(&mut *json_reader, &self.verifier.tokenizer_options) {
+            Ok(Some(t)) => t,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(VerifyError::from_tokenizer(token_position, e)),
+        };
+
+        let mut separator_position = None;
+        if let JsonToken::Number(number) = &mut tok {
+            if self.verifier.decimal_comma && self.accepts_decimal_comma(number) {
+                let comma_position = json_reader.position();
+                let is_separator = take_decimal_comma(&mut *json_reader, number)
+                    .map_err(|e| VerifyError::from_tokenizer(comma_position, e.into()))?;
+                if is_separator {
+                    separator_position = Some(comma_position);
+                }
+            }
+        }
+        Ok(Some((tok, separator_position)))
+    }
+
+    /// Feeds a comma that `read_token` took for a decimal comma but that turned out to be a
+    /// separator, given the outcome of feeding the number before it.
+    fn feed_separator(&mut self, step_outcome: StepOutcome, comma_position: Position) -> Result<StepOutcome, VerifyError> {
+        if step_outcome == StepOutcome::Complete {
+            // the comma follows the root value
+            return Err(VerifyError::TrailingGarbage(comma_position, Some(TokenKind::Comma)));
+        }
+        self.feed_token(JsonToken::Comma, comma_position)
+    }
+
+    /// Returns whether the number, if fed next, may be continued with a decimal comma.
+    fn accepts_decimal_comma(&self, number: &[u8]) -> bool {
+        let in_array = matches!(self.json_stack.last(), Some(JsonStackValue::Array(_)));
+        let is_integer = number.iter().all(|b| b.is_ascii_digit() || *b == b'-' || *b == b'+');
+        !in_array && is_integer && self.expects.contains(ParserExpects::VALUE)
+    }
+
+    /// Continues as if a valid key or value had been fed in place of a malformed one.
+    fn accept_placeholder(&mut self) -> StepOutcome {
+        if self.expects.contains(ParserExpects::KEY) {
+            self.expects = ParserExpects::COLON;
+            return StepOutcome::NeedMore;
+        }
+        if self.expects.contains(ParserExpects::VALUE) {
+            return self.feed_token(JsonToken::Null, Position::default())
+                .unwrap_or(StepOutcome::NeedMore);
+        }
+        // the malformed token was out of place anyway
+        StepOutcome::NeedMore
+    }
+
+    /// Attempts to bring the state into a position from which parsing can continue after feeding
+    /// a token has failed with the given error.
+    ///
+    /// Returns `None` if the error cannot be recovered from.
+    fn recover(&mut self, error: &VerifyError) -> Option<StepOutcome> {
+        match error {
+            VerifyError::UnexpectedToken(token_position, tok, expects) => {
+                let is_closing = matches!(tok, JsonToken::ClosingBracket | JsonToken::ClosingBrace);
+                let is_punctuation = is_closing || matches!(tok, JsonToken::Colon | JsonToken::Comma);
+                if expects.contains(ParserExpects::COMMA) && !is_punctuation {
+                    // a comma is probably missing
+                    self.feed_token(JsonToken::Comma, *token_position).ok()?;
+                    return Some(self.feed_token(tok.clone(), *token_position).unwrap_or(StepOutcome::NeedMore));
+                }
+                if is_closing {
+                    // close the innermost container of this kind, e.g. after a trailing comma or
+                    // when another container has not been closed
+                    let closes_array = *tok == JsonToken::ClosingBracket;
+                    let container_index = self.json_stack.iter()
+                        .rposition(|value| matches!(value, JsonStackValue::Array(_)) == closes_array);
+                    if let Some(container_index) = container_index {
+                        self.json_stack.truncate(container_index + 1);
+                        self.expects = if closes_array { ParserExpects::CLOSING_BRACKET } else { ParserExpects::CLOSING_BRACE };
+                        return self.feed_token(tok.clone(), *token_position).ok();
+                    }
+                }
+                // skip the token
+                Some(StepOutcome::NeedMore)
+            },
+            VerifyError::DuplicateKey(_, key, _) => {
+                if let Some(JsonStackValue::Object(obj)) = self.json_stack.last_mut() {
+                    obj.current_key = Some(key.clone());
+                }
+                self.expects = ParserExpects::COLON;
+                Some(StepOutcome::NeedMore)
+            },
+            VerifyError::UnsortedKey(_, _, _) => {
+                self.expects = ParserExpects::COLON;
+                Some(StepOutcome::NeedMore)
+            },
+            VerifyError::MistypedKey(_, _, _, _) => Some(StepOutcome::NeedMore),
+            VerifyError::MissingKey(_, _) => Some(StepOutcome::Complete),
+            VerifyError::EmptyRoot(_) => Some(StepOutcome::Complete),
+            VerifyError::InvalidString(_, _) | VerifyError::InvalidUtf8InString(_, _, _) => {
+                Some(self.accept_placeholder())
+            },
+            _ => None,
+        }
+    }
+
+    /// Checks the value that begins with the given token against the required keys, if it is the
+    /// value of a key in the root object.
+    fn check_required_key(&mut self, token_position: Position, tok: &JsonToken) -> Result<(), VerifyError> {
+        if self.json_stack.len() != 1 || !self.begins_value(tok) {
+            return Ok(());
+        }
+        let key = match self.json_stack.last() {
+            Some(JsonStackValue::Object(obj)) => obj.current_key.as_ref(),
+            _ => None,
+        };
+        let (key, actual_kind) = match (key, ValueKind::of(tok)) {
+            (Some(k), Some(ak)) => (k, ak),
+            _ => return Ok(()),
+        };
+        for (i, (required_key, required_kind)) in self.verifier.required_keys.iter().enumerate() {
+            if required_key != key {
+                continue;
+            }
+            self.seen_required_keys[i] = true;
+            if *required_kind != actual_kind {
+                return Err(VerifyError::MistypedKey(token_position, key.clone(), *required_kind, actual_kind));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the token, if fed next, begins a value (as opposed to being a key or
+    /// punctuation).
+    fn begins_value(&self, tok: &JsonToken) -> bool {
+        let is_punctuation = matches!(
+            tok,
+            JsonToken::Colon | JsonToken::Comma | JsonToken::ClosingBracket | JsonToken::ClosingBrace
+        );
+        !is_punctuation && self.expects.contains(ParserExpects::VALUE)
+    }
+
+    /// Registers a key in the object at the top of the stack.
+    ///
+    /// If duplicates are being collected, a duplicate key is added to them instead of being handled
+    /// according to the duplicate key policy.
+    ///
+    /// The raw key is the key as it is written; `None` denotes an unquoted key, which is written
+    /// exactly like its decoded form.
+    fn register_key(&mut self, token_position: Position, key: String, raw_key: Option<&[JsonChar]>) -> Result<(), VerifyError> {
+        let mut unsorted_after = None;
+        let is_duplicate = match self.json_stack.last_mut() {
+            Some(JsonStackValue::Object(obj)) => {
+                if let Some(max_keys) = self.verifier.max_keys_per_object {
+                    if obj.keys.len() >= max_keys {
+                        return Err(VerifyError::TooManyKeys(token_position, max_keys));
+                    }
+                }
+
+                let identity = match self.verifier.duplicate_key_comparison {
+                    DuplicateKeyComparison::Decoded => KeyIdentity::Decoded(self.verifier.canonical_key(&key).into_owned()),
+                    DuplicateKeyComparison::RawBytes => KeyIdentity::Raw(match raw_key {
+                        Some(rk) => rk.to_vec(),
+                        None => key.bytes().map(JsonChar::Byte).collect(),
+                    }),
+                };
+                let is_duplicate = obj.key_identities.contains(&identity);
+                let reject_duplicates = self.verifier.duplicate_key_policy == DuplicateKeyPolicy::Reject;
+                if is_duplicate && reject_duplicates && self.duplicates.is_none() {
+                    return Err(match identity {
+                        // as written is as compared
+                        KeyIdentity::Raw(_) => VerifyError::DuplicateKey(token_position, key, obj.keys.clone()),
+                        KeyIdentity::Decoded(decoded_key) => {
+                            let previous_keys = obj.keys.iter()
+                                .map(|previous_key| self.verifier.canonical_key(previous_key).into_owned())
+                                .collect();
+                            VerifyError::DuplicateKey(token_position, decoded_key, previous_keys)
+                        },
+                    });
+                }
+                obj.key_identities.insert(identity);
+                if let (Some(key_order), Some(previous_key)) = (self.verifier.sorted_keys, obj.keys.last()) {
+                    if key_order.compare(&key, previous_key) == Ordering::Less {
+                        unsorted_after = Some(previous_key.clone());
+                    }
+                }
+                obj.keys.push(key.clone());
+                obj.current_key = Some(key.clone());
+                is_duplicate
+            },
+            other => {
+                panic!("parser expects KEY but top stack value is {:?}", other);
+            },
+        };
+
+        if is_duplicate {
+            if let Some(dupes) = &mut self.duplicates {
+                dupes.push((key.clone(), json_pointer(&self.json_stack)));
+            }
+        }
+
+        // the key is registered regardless, which makes this error easy to recover from
+        if let Some(previous_key) = unsorted_after {
+            return Err(VerifyError::UnsortedKey(token_position, key, previous_key));
+        }
+        Ok(())
+    }
+
+    /// Processes the next token of the document, which begins at the given position.
+    pub fn feed_token(&mut self, tok: JsonToken, token_position: Position) -> Result<StepOutcome, VerifyError> {
+        self.stats.tokens += 1;
+        match &tok {
+            JsonToken::String(chars) => {
+                self.stats.longest_string = self.stats.longest_string.max(decoded_length(chars));
+            },
+            JsonToken::Number(number) => {
+                self.stats.longest_number = self.stats.longest_number.max(number.len());
+            },
+            _ => {},
+        }
+        if !self.expects.contains(ParserExpects::KEY) {
+            match &tok {
+                JsonToken::String(_) => self.stats.strings += 1,
+                JsonToken::Number(_) => self.stats.numbers += 1,
+                JsonToken::True|JsonToken::False => self.stats.booleans += 1,
+                JsonToken::Null => self.stats.nulls += 1,
+                JsonToken::OpeningBrace => self.stats.objects += 1,
+                JsonToken::OpeningBracket => self.stats.arrays += 1,
+                _ => {},
+            }
+        }
+
+        // a token fed with an empty stack is the first token of the root value
+        if self.json_stack.len() == 0 {
+            let required_keys_root = if self.verifier.required_keys.len() > 0 { Some(RootKind::Object) } else { None };
+            if let Some(root_kind) = self.verifier.require_root.or(required_keys_root) {
+                if !root_kind.matches(&tok) {
+                    return Err(VerifyError::WrongRootKind(token_position, root_kind, tok));
+                }
+            }
+        }
+
+        // a value within an array counts towards the array's length
+        if let Some(max_elements) = self.verifier.max_array_elements {
+            if let Some(JsonStackValue::Array(arr)) = self.json_stack.last() {
+                if self.begins_value(&tok) && arr.current_index >= max_elements {
+                    return Err(VerifyError::TooManyElements(token_position, max_elements));
+                }
+            }
+        }
+
+        // the token is processed regardless, which makes this error easy to recover from
+        let mistyped_key = self.check_required_key(token_position, &tok).err();
+
+        match &tok {
+            JsonToken::String(s) => {
+                let processed_string = interpret_string(s)
+                    .map_err(|e| VerifyError::from_string(token_position, s, e))?;
+                self.verifier.lint_string(token_position, s);
+
+                // strings can be keys or values
+                if self.expects.contains(ParserExpects::KEY) {
+                    self.register_key(token_position, processed_string, Some(s))?;
+                    self.expects = ParserExpects::COLON;
+                } else if self.expects.contains(ParserExpects::VALUE) {
+                    // what's next?
+                    match self.json_stack.last() {
+                        Some(JsonStackValue::Array(_)) => {
+                            self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
+                        },
+                        Some(JsonStackValue::Object(_)) => {
+                            self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
+                        },
+                        None => {
+                            // end of document; the root is a scalar
+                            if self.verifier.require_composite_root {
+                                return Err(VerifyError::ScalarRoot(token_position, tok));
+                            }
+                            return Ok(StepOutcome::Complete);
+                        },
+                    }
+                } else {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+            },
+            JsonToken::Identifier(identifier) => {
+                // only valid as an unquoted key
+                if !self.expects.contains(ParserExpects::KEY) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                // identifiers consist purely of ASCII characters
+                let key = String::from_utf8_lossy(identifier).into_owned();
+                self.register_key(token_position, key, None)?;
+                self.expects = ParserExpects::COLON;
+            },
+            JsonToken::Null|JsonToken::True|JsonToken::False
+                    if self.verifier.tokenizer_options.allow_unquoted_keys && self.expects.contains(ParserExpects::KEY) => {
+                // the literals are valid unquoted keys as well
+                let key = match tok {
+                    JsonToken::Null => "null",
+                    JsonToken::True => "true",
+                    _ => "false",
+                };
+                self.register_key(token_position, key.to_owned(), None)?;
+                self.expects = ParserExpects::COLON;
+            },
+            JsonToken::Null|JsonToken::True|JsonToken::False|JsonToken::Number(_) => {
+                // singular value
+                if !self.expects.contains(ParserExpects::VALUE) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                if let JsonToken::Number(number) = &tok {
+                    self.verifier.lint_number(token_position, number);
+                }
+
+                // what's next?
+                match self.json_stack.last() {
+                    Some(JsonStackValue::Array(_)) => {
+                        self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
+                    },
+                    Some(JsonStackValue::Object(_)) => {
+                        self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
+                    },
+                    None => {
+                        // end of document; the root is a scalar
+                        if self.verifier.require_composite_root {
+                            return Err(VerifyError::ScalarRoot(token_position, tok));
+                        }
+                        return Ok(StepOutcome::Complete);
+                    },
+                }
+            },
+            JsonToken::Colon => {
+                if !self.expects.contains(ParserExpects::COLON) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                // what's next?
+                match self.json_stack.last() {
+                    Some(JsonStackValue::Object(_)) => {
+                        self.expects = ParserExpects::VALUE;
+                    },
+                    other => {
+                        panic!("parser expects COLON but top stack value is {:?}", other);
+                    },
+                }
+            },
+            JsonToken::Comma => {
+                if !self.expects.contains(ParserExpects::COMMA) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                // what's next?
+                match self.json_stack.last_mut() {
+                    Some(JsonStackValue::Array(arr)) => {
+                        arr.current_index += 1;
+                        self.expects = ParserExpects::VALUE;
+                    },
+                    Some(JsonStackValue::Object(obj)) => {
+                        obj.current_key = None;
+                        self.expects = ParserExpects::KEY;
+                    },
+                    other => {
+                        panic!("parser expects COMMA but top stack value is {:?}", other);
+                    },
+                }
+            },
+            JsonToken::OpeningBracket => {
+                if !self.expects.contains(ParserExpects::VALUE) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                if let Some(max_depth) = self.verifier.max_depth {
+                    if self.json_stack.len() >= max_depth {
+                        return Err(VerifyError::MaxDepthExceeded(token_position, max_depth));
+                    }
+                }
+
+                self.json_stack.push(JsonStackValue::Array(JsonArray::default()));
+                self.stats.max_depth = self.stats.max_depth.max(self.json_stack.len());
+                self.expects = ParserExpects::VALUE | ParserExpects::CLOSING_BRACKET;
+            },
+            JsonToken::ClosingBracket => {
+                if !self.expects.contains(ParserExpects::CLOSING_BRACKET) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                let is_empty = match self.json_stack.pop() {
+                    // the index only advances at a comma; before the first element, a value is expected
+                    Some(JsonStackValue::Array(arr)) => arr.current_index == 0 && self.expects.contains(ParserExpects::VALUE),
+                    other => {
+                        panic!("parser expects CLOSING_BRACKET but popped stack value is {:?}", other);
+                    },
+                };
+                if is_empty && self.json_stack.len() == 0 && self.verifier.reject_empty_root {
+                    return Err(VerifyError::EmptyRoot(token_position));
+                }
+
+                match self.json_stack.last() {
+                    Some(JsonStackValue::Array(_)) => {
+                        self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
+                    },
+                    Some(JsonStackValue::Object(_)) => {
+                        self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
+                    },
+                    None => {
+                        // end of document
+                        return Ok(StepOutcome::Complete);
+                    },
+                }
+            },
+            JsonToken::OpeningBrace => {
+                if !self.expects.contains(ParserExpects::VALUE) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                if let Some(max_depth) = self.verifier.max_depth {
+                    if self.json_stack.len() >= max_depth {
+                        return Err(VerifyError::MaxDepthExceeded(token_position, max_depth));
+                    }
+                }
+
+                self.json_stack.push(JsonStackValue::Object(JsonObject::default()));
+                self.stats.max_depth = self.stats.max_depth.max(self.json_stack.len());
+                self.expects = ParserExpects::KEY | ParserExpects::CLOSING_BRACE;
+            },
+            JsonToken::ClosingBrace => {
+                if !self.expects.contains(ParserExpects::CLOSING_BRACE) {
+                    return Err(VerifyError::UnexpectedToken(token_position, tok, self.expects));
+                }
+
+                let is_empty = match self.json_stack.pop() {
+                    Some(JsonStackValue::Object(obj)) => obj.keys.len() == 0,
+                    other => {
+                        panic!("parser expects CLOSING_BRACE but popped stack value is {:?}", other);
+                    },
+                };
+
+                if self.json_stack.len() == 0 {
+                    if is_empty && self.verifier.reject_empty_root {
+                        return Err(VerifyError::EmptyRoot(token_position));
+                    }
+
+                    let missing_key = self.seen_required_keys.iter()
+                        .position(|seen| !seen);
+                    if let Some(missing_key) = missing_key {
+                        let (key, _) = &self.verifier.required_keys[missing_key];
+                        return Err(VerifyError::MissingKey(token_position, key.clone()));
+                    }
+                }
+
+                match self.json_stack.last() {
+                    Some(JsonStackValue::Array(_)) => {
+                        self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET;
+                    },
+                    Some(JsonStackValue::Object(_)) => {
+                        self.expects = ParserExpects::COMMA | ParserExpects::CLOSING_BRACE;
+                    },
+                    None => {
+                        // end of document
+                        return Ok(StepOutcome::Complete);
+                    },
+                }
+            },
+        }
+
+        if let Some(e) = mistyped_key {
+            return Err(e);
+        }
+        Ok(StepOutcome::NeedMore)
+    }
+
+    /// Processes the next chunk of the document, returning whether the root value is complete.
+    ///
+    /// Tokens that might continue in the next chunk (such as numbers) are held back until more
+    /// bytes arrive or `finish` is called. Only the tokenizer options and the checks performed by
+    /// `feed_token` apply; limits on the input as a whole (such as `max_bytes`) do not.
+    pub fn feed_bytes(&mut self, bytes: &[u8]) -> Result<StepOutcome, VerifyError> {
+        self.pending.bytes.extend_from_slice(bytes);
+        self.process_pending(false)
+    }
+
+    /// Processes the rest of the document after the last chunk has been fed with `feed_bytes`,
+    /// checking that the document is complete.
+    pub fn finish(&mut self) -> Result<(), VerifyError> {
+        if self.process_pending(true)? == StepOutcome::Complete {
+            return Ok(());
+        }
+        if self.depth() > 0 {
+            return Err(VerifyError::UnclosedDocument(self.pending.position, self.depth()));
+        }
+        if self.verifier.reject_empty {
+            return Err(VerifyError::NoValue(self.pending.position));
+        }
+        Ok(())
+    }
+
+    /// Turns as many of the pending bytes into tokens as possible and feeds them.
+    fn process_pending(&mut self, at_end: bool) -> Result<StepOutcome, VerifyError> {
+        // take the bytes out to be able to feed tokens while reading them
+        let mut bytes = std::mem::take(&mut self.pending.bytes);
+        let mut consumed = 0;
+        let result = self.process_bytes(&bytes, &mut consumed, at_end);
+        bytes.drain(..consumed);
+        self.pending.bytes = bytes;
+        result
+    }
+
+    fn process_bytes(&mut self, bytes: &[u8], consumed: &mut usize, at_end: bool) -> Result<StepOutcome, VerifyError> {
+        let options = &self.verifier.tokenizer_options;
+
+        // a token that was held back is only read again once it might have ended
+        if let Some(scan) = &mut self.pending.scan {
+            if !at_end && !scan.might_end(&bytes[*consumed..], options) {
+                return Ok(self.pending.outcome());
+            }
+        }
+
+        loop {
+            // anything that reaches the end of the bytes might still continue in the next chunk;
+            // whitespace and comments are skipped one at a time so that only an unfinished comment
+            // is held back
+            loop {
+                let remaining = &bytes[*consumed..];
+                let mut cursor = Cursor::new(remaining);
+                let skipped = skip_insignificant_once(&mut cursor, options);
+                let reached_end = cursor.position() == remaining.len() as u64;
+                match skipped {
+                    Ok(false) => break,
+                    Ok(true) if reached_end && !at_end && remaining.starts_with(b"//") => return Ok(self.hold_back()),
+                    Ok(true) => {
+                        let skipped_length = cursor.position() as usize;
+                        self.pending.position.advance(&remaining[..skipped_length], &mut self.pending.after_carriage_return);
+                        *consumed += skipped_length;
+                        self.pending.scan = None;
+                    },
+                    Err(_) if reached_end && !at_end => return Ok(self.hold_back()),
+                    Err(e) => return Err(VerifyError::from_tokenizer(self.pending.position, e)),
+                }
+            }
+
+            let token_position = self.pending.position;
+            let remaining = &bytes[*consumed..];
+            let mut reader = PositionReader::starting_at(Cursor::new(remaining), token_position);
+            let read_token = self.read_token(&mut reader);
+            let token_length = (reader.position().offset - token_position.offset) as usize;
+            let reached_end = token_length == remaining.len();
+            let (tok, separator_position) = match read_token {
+                Ok(Some(t)) => t,
+                Ok(None) => return Ok(self.pending.outcome()),
+                Err(_) if reached_end && !at_end => return Ok(self.hold_back()),
+                Err(e) => return Err(e),
+            };
+            let might_continue = matches!(
+                tok,
+                JsonToken::Number(_) | JsonToken::Identifier(_) | JsonToken::True | JsonToken::False | JsonToken::Null
+            );
+            if might_continue && reached_end && !at_end {
+                return Ok(self.hold_back());
+            }
+            self.pending.position.advance(&remaining[..token_length], &mut self.pending.after_carriage_return);
+            *consumed += token_length;
+            self.pending.scan = None;
+
+            if self.pending.complete {
+                return Err(VerifyError::TrailingGarbage(token_position, Some(tok.kind())));
+            }
+            let mut step_outcome = self.feed_token(tok, token_position)?;
+            if let Some(comma_position) = separator_position {
+                step_outcome = self.feed_separator(step_outcome, comma_position)?;
+            }
+            if step_outcome == StepOutcome::Complete {
+                self.pending.complete = true;
+            }
+        }
+    }
+
+    /// Keeps the remaining bytes for the next chunk, since the token or comment at their beginning
+    /// might continue in it.
+    fn hold_back(&mut self) -> StepOutcome {
+        // a token that is held back again keeps its scan
+        self.pending.scan.get_or_insert_with(TokenScan::default);
+        self.pending.outcome()
+    }
+}
+
+
+/// Returns the number of characters the string decodes to.
+fn decoded_length(chars: &[JsonChar]) -> usize {
+    chars.iter()
+        .filter(|c| match c {
+            // continuation bytes and the second half of a surrogate pair don't begin a character
+            JsonChar::Byte(b) => !(0x80..=0xBF).contains(b),
+            JsonChar::UnicodeEscape(u) => !(0xDC00..=0xDFFF).contains(u),
+            _ => true,
+        })
+        .count()
+}
+
+
+pub fn verify<R: BufRead>(json_reader: R) -> bool {
+    Verifier::new().verify_and_report(json_reader)
+}
+
+
+pub fn verify_detailed<R: BufRead>(json_reader: R) -> Result<(), VerifyError> {
+    Verifier::new().verify(json_reader)
+}
+
+
+/// Opens and verifies the file at the given path.
+///
+/// Failing to open or read the file is returned as an error; whether the file contains valid JSON
+/// is returned as the value.
+pub fn verify_file<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    match verify_file_detailed(path) {
+        Ok(()) => Ok(true),
+        // a truncated document is invalid, not unreadable
+        Err(VerifyError::Tokenizer(_, TokenizerError::Io(e))) if e.kind() != io::ErrorKind::UnexpectedEof => Err(e),
+        Err(_) => Ok(false),
+    }
+}
+
+
+/// Opens and verifies the file at the given path, returning the reason if it is not valid JSON.
+///
+/// Failing to open the file is reported as an I/O error at the beginning of the document.
+pub fn verify_file_detailed<P: AsRef<Path>>(path: P) -> Result<(), VerifyError> {
+    let file = File::open(path)
+        .map_err(|e| VerifyError::Tokenizer(Position::default(), TokenizerError::Io(e)))?;
+    Verifier::new().verify(BufReader::new(file))
+}
+
+
+/// Skips everything up to and including the first occurrence of the delimiter, then verifies that
+/// the rest of the reader contains exactly one JSON document.
+pub fn verify_after_prefix<R: BufRead>(json_reader: R, skip_until: u8) -> Result<(), VerifyError> {
+    Verifier::new().verify_after_prefix(json_reader, skip_until)
+}
+
+
+/// Verifies a document whose root must be an array and returns the number of elements in it.
+pub fn count_root_array_elements<R: BufRead>(json_reader: R) -> Result<usize, VerifyError> {
+    Verifier::new().count_root_array_elements(json_reader)
+}
+
+
+/// Verifies exactly one JSON value at the beginning of the reader, returning the number of bytes it
+/// occupies.
+pub fn verify_value<R: BufRead>(json_reader: R) -> Result<u64, VerifyError> {
+    Verifier::new().verify_value(json_reader)
+}
+
+
+/// Verifies exactly one JSON value at the beginning of the reader and consumes the whitespace
+/// following it, returning the offset of the byte immediately after the value.
+pub fn verify_value_consuming<R: BufRead>(json_reader: R) -> Result<u64, VerifyError> {
+    Verifier::new().verify_value_consuming(json_reader)
+}
+
+
+/// Verifies a stream of whitespace-separated JSON documents, returning the number of documents.
+pub fn verify_stream<R: BufRead>(json_reader: R) -> Result<usize, VerifyError> {
+    Verifier::new().verify_stream(json_reader)
+}
+
+
+/// Verifies an RFC 7464 JSON text sequence, in which each record is preceded by a record
+/// separator (0x1E).
+pub fn verify_json_seq<R: BufRead>(json_reader: R) -> Vec<Result<(), VerifyError>> {
+    Verifier::new().verify_json_seq(json_reader)
+}
+
+
+/// Returns the value at the given JSON Pointer (RFC 6901) without building up the whole document
+/// in memory.
+pub fn extract<R: BufRead>(json_reader: R, pointer: &str) -> Result<Option<ExtractedValue>, VerifyError> {
+    Verifier::new().extract(json_reader, pointer)
+}
+
+
+/// Returns whether the object at the given JSON Pointer (RFC 6901) contains the given key.
+pub fn contains_key_at<R: BufRead>(json_reader: R, pointer: &str, key: &str) -> Result<bool, VerifyError> {
+    Verifier::new().contains_key_at(json_reader, pointer, key)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Verifier;
+
+    fn test_verify(json: &str) -> bool {
+        let cursor = std::io::Cursor::new(json);
+        super::verify(cursor)
+    }
+
+    fn test_verify_with(verifier: &Verifier, json: &str) -> bool {
+        let cursor = std::io::Cursor::new(json);
+        verifier.verify(cursor).is_ok()
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(test_verify("{}"), true);
+        assert_eq!(test_verify("[]"), true);
+    }
+
+    #[test]
+    fn test_simple() {
+        assert_eq!(test_verify("{\"a\":0}"), true);
+        assert_eq!(test_verify("{\"a\":0,\"b\":1}"), true);
+        assert_eq!(test_verify("[0,1]"), true);
+
+        // wrong number of colons in dict
+        assert_eq!(test_verify("{\"a\",0}"), false);
+        assert_eq!(test_verify("{\"a\":0:1}"), false);
+
+        // colon in list
+        assert_eq!(test_verify("[\"a\":0]"), false);
+
+        // unterminated string
+        assert_eq!(test_verify("[\"a]"), false);
+
+        // unterminated list
+        assert_eq!(test_verify("[\"a\""), false);
+
+        // bareword
+        assert_eq!(test_verify("[a]"), false);
+    }
+
+    #[test]
+    fn test_boxed() {
+        assert_eq!(test_verify("{\"a\":{\"b\":[0,{\"c\":1}],\"d\":\"e\"}}"), true);
+
+        // swapped brackets
+        assert_eq!(test_verify("{\"a\":{\"b\":[0,{\"c\":1]},\"d\":\"e\"}}"), false);
+    }
+
+    #[test]
+    fn test_duplicate_key() {
+        assert_eq!(test_verify("{\"a\":0,\"a\":0}"), false);
+
+        // consider different encoded forms equivalent
+        assert_eq!(test_verify("{\"a\":0,\"\\u0061\":0}"), false);
+        assert_eq!(test_verify("{\"/\":0,\"\\/\":0}"), false);
+        assert_eq!(test_verify("{\"/\":0,\"\\u002F\":0}"), false);
+    }
+
+    #[test]
+    fn test_forbid_trailing_whitespace() {
+        let strict = Verifier::new().forbid_trailing_whitespace(true);
+
+        // a final newline is fine by default, another document after it is not
+        assert_eq!(test_verify("{}\n"), true);
+        assert_eq!(test_verify("{}\r\n \t"), true);
+        assert_eq!(test_verify("{}\n\n{}"), false);
+
+        assert_eq!(test_verify_with(&strict, "{}"), true);
+        assert_eq!(test_verify_with(&strict, "  {}"), true);
+        assert_eq!(test_verify_with(&strict, "[1, 2]"), true);
+        assert_eq!(test_verify_with(&strict, "{}\n"), false);
+        assert_eq!(test_verify_with(&strict, "1 "), false);
+
+        let error = strict.verify("[]\n\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), "trailing-whitespace");
+        assert_eq!(error.position().offset, 2);
+
+        // actual garbage is still reported as such
+        let error = strict.verify("[]\n{}".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), "trailing-garbage");
+
+        let errors = strict.verify_collecting("[1 2]\n".as_bytes(), 10);
+        let kinds: Vec<&str> = errors.iter().map(|e| e.kind()).collect();
+        assert_eq!(kinds, vec!["unexpected-token", "trailing-whitespace"]);
+
+        // the same goes for the functions built on scanning the document
+        let error = strict.count_root_array_elements("[1]\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), "trailing-whitespace");
+    }
+
+    #[test]
+    fn test_trailing_garbage() {
+        assert_eq!(test_verify("{}{}"), false);
+        assert_eq!(test_verify("{},{}"), false);
+        assert_eq!(test_verify("{}true"), false);
+        assert_eq!(test_verify("{}0"), false);
+
+        fn garbage_message(json: &str) -> String {
+            let error = super::verify_detailed(json.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), "trailing-garbage");
+            error.to_string()
+        }
+        assert_eq!(garbage_message("{}{}"), "trailing garbage: another object begins");
+        assert_eq!(garbage_message("{} [1]"), "trailing garbage: another array begins");
+        assert_eq!(garbage_message("{},{}"), "trailing comma");
+        assert_eq!(garbage_message("{}]"), "trailing garbage: unmatched closing bracket");
+        assert_eq!(garbage_message("{}true"), "trailing garbage: another value (true) follows");
+        assert_eq!(garbage_message("{}0"), "trailing garbage: another value (a number) follows");
+        assert_eq!(garbage_message("{}@"), "trailing garbage at end of document");
+
+        // only the beginning of the garbage is read to describe it
+        let long_string = format!("{{}} \"{}", "a".repeat(100_000));
+        assert_eq!(garbage_message(&long_string), "trailing garbage: another value (a string) follows");
+    }
+
+    #[test]
+    fn test_error_position() {
+        let cursor = std::io::Cursor::new("{\n  \"a\": 0\n  \"b\": 1\n}");
+        let error = super::verify_detailed(cursor).unwrap_err();
+        assert_eq!(error.kind(), "unexpected-token");
+        let position = error.position();
+        assert_eq!(position.offset, 13);
+        assert_eq!(position.line, 3);
+        assert_eq!(position.column, 3);
+    }
+
+    #[test]
+    fn test_stream() {
+        fn test_verify_stream(json: &str) -> Option<usize> {
+            let cursor = std::io::Cursor::new(json);
+            super::verify_stream(cursor).ok()
+        }
+
+        assert_eq!(test_verify_stream(""), Some(0));
+        assert_eq!(test_verify_stream(" \n "), Some(0));
+        assert_eq!(test_verify_stream("{}"), Some(1));
+        assert_eq!(test_verify_stream("{}{}"), Some(2));
+        assert_eq!(test_verify_stream("{\"a\":0}\n[1,2]\n\"s\" 3 true\n"), Some(5));
+
+        assert_eq!(test_verify_stream("{}{"), None);
+        assert_eq!(test_verify_stream("{},{}"), None);
+    }
+
+    #[test]
+    fn test_json_seq() {
+        fn test_verify_json_seq(json: &str) -> Vec<bool> {
+            let cursor = std::io::Cursor::new(json);
+            super::verify_json_seq(cursor)
+                .into_iter()
+                .map(|r| r.is_ok())
+                .collect()
+        }
+
+        assert_eq!(test_verify_json_seq(""), Vec::<bool>::new());
+        assert_eq!(test_verify_json_seq("\x1E{}\n"), vec![true]);
+        assert_eq!(test_verify_json_seq("\x1E{\"a\":0}\n\x1E[1,2]\n\x1E\x1E3\n"), vec![true, true, true]);
+
+        // malformed records do not abort the stream
+        assert_eq!(test_verify_json_seq("\x1E{\"a\":\n\x1E[1,2]\n"), vec![false, true]);
+        assert_eq!(test_verify_json_seq("\x1E{}{}\n\x1Etrue\n"), vec![false, true]);
+
+        // missing record separator
+        assert_eq!(test_verify_json_seq("{}\n\x1E{}\n"), vec![false, true]);
+    }
+
+    #[test]
+    fn test_verifier_options() {
+        use super::DuplicateKeyPolicy;
+
+        let shallow = Verifier::new().max_depth(2);
+        assert_eq!(test_verify_with(&shallow, "[[]]"), true);
+        assert_eq!(test_verify_with(&shallow, "{\"a\":[0]}"), true);
+        assert_eq!(test_verify_with(&shallow, "[[[]]]"), false);
+        assert_eq!(test_verify_with(&shallow, "{\"a\":{\"b\":{}}}"), false);
+
+        let commented = Verifier::new().allow_comments(true);
+        assert_eq!(test_verify_with(&commented, "/* head */ {\"a\": // a\n0} // tail\n"), true);
+        assert_eq!(test_verify_with(&Verifier::new(), "{\"a\": // a\n0}"), false);
+
+        let duplicates = Verifier::new().duplicate_key_policy(DuplicateKeyPolicy::Allow);
+        assert_eq!(test_verify_with(&duplicates, "{\"a\":0,\"a\":1}"), true);
+    }
+
+    #[test]
+    fn test_duplicate_key_comparison() {
+        use super::DuplicateKeyComparison;
+
+        let decoded = Verifier::new();
+        let raw = Verifier::new().duplicate_key_comparison(DuplicateKeyComparison::RawBytes);
+
+        assert_eq!(test_verify_with(&decoded, r#"{"a": 0, "\u0061": 1}"#), false);
+        assert_eq!(test_verify_with(&raw, r#"{"a": 0, "\u0061": 1}"#), true);
+        assert_eq!(test_verify_with(&raw, r#"{"\/": 0, "/": 1, "\u002f": 2}"#), true);
+        // escapes are compared by value, not by their hex digits
+        assert_eq!(test_verify_with(&raw, r#"{"\u002f": 0, "\u002F": 1}"#), false);
+
+        assert_eq!(test_verify_with(&raw, r#"{"a": 0, "a": 1}"#), false);
+        assert_eq!(test_verify_with(&raw, r#"{"\u0061": 0, "\u0061": 1}"#), false);
+        assert_eq!(test_verify_with(&raw, r#"{"a": {"a": 0}, "b": {"a": 1}}"#), true);
+
+        let unquoted = Verifier::new()
+            .allow_unquoted_keys(true)
+            .duplicate_key_comparison(DuplicateKeyComparison::RawBytes);
+        assert_eq!(test_verify_with(&unquoted, r#"{a: 0, "a": 1}"#), false);
+        assert_eq!(test_verify_with(&unquoted, r#"{a: 0, "\u0061": 1}"#), true);
+    }
+
+    #[test]
+    fn test_trim_keys_for_dedup() {
+        let trimming = Verifier::new().trim_keys_for_dedup(true);
+
+        assert_eq!(test_verify(r#"{"a":1,"a ":2}"#), true);
+        assert_eq!(test_verify_with(&trimming, r#"{"a":1,"a ":2}"#), false);
+        assert_eq!(test_verify_with(&trimming, r#"{" a":1,"a\t":2}"#), false);
+        assert_eq!(test_verify_with(&trimming, r#"{"a":1,"a b":2,"ab":3}"#), true);
+        assert_eq!(test_verify_with(&trimming, r#"{"a":1,"b":{"a ":2}}"#), true);
+    }
+
+    #[test]
+    fn test_duplicate_key_case_folding() {
+        use super::DuplicateKeyCaseFolding;
+
+        let folding = Verifier::new().duplicate_key_case_folding(DuplicateKeyCaseFolding::Simple);
+
+        assert_eq!(test_verify(r#"{"A":1,"a":2}"#), true);
+        assert_eq!(test_verify_with(&folding, r#"{"A":1,"a":2}"#), false);
+        assert_eq!(test_verify_with(&folding, r#"{"Name":1,"nAME":2}"#), false);
+        assert_eq!(test_verify_with(&folding, r#"{"a":1,"b":2,"B":3}"#), false);
+        assert_eq!(test_verify_with(&folding, r#"{"a":1,"b":{"A":2}}"#), true);
+
+        // beyond ASCII
+        assert_eq!(test_verify_with(&folding, r#"{"ÄRGER":1,"ärger":2}"#), false);
+        assert_eq!(test_verify_with(&folding, r#"{"ΣΟΦΟΣ":1,"σοφος":2}"#), false);
+        assert_eq!(test_verify_with(&folding, r#"{"σοφοσ":1,"σοφος":2}"#), false);
+        assert_eq!(test_verify_with(&folding, r#"{"\u212A":1,"k":2}"#), false);
+        // full case folding would turn the sharp s into "ss"
+        assert_eq!(test_verify_with(&folding, r#"{"straße":1,"STRASSE":2}"#), true);
+
+        // combines with trimming
+        let trimming_folding = folding.trim_keys_for_dedup(true);
+        assert_eq!(test_verify_with(&trimming_folding, r#"{"a":1,"A ":2}"#), false);
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_normalize_keys_for_dedup() {
+        use super::{DuplicateKeyCaseFolding, VerifyError};
+
+        let normalizing = Verifier::new().normalize_keys_for_dedup(true);
+
+        assert_eq!(test_verify(r#"{"\u00e9":1,"e\u0301":2}"#), true);
+        assert_eq!(test_verify_with(&normalizing, r#"{"\u00e9":1,"e\u0301":2}"#), false);
+        assert_eq!(test_verify_with(&normalizing, r#"{"\u00e9":1,"e":2}"#), true);
+        assert_eq!(test_verify_with(&normalizing, "{\"\u{212B}\":1,\"\u{C5}\":2}"), false);
+
+        // the canonical form is reported, whichever form came first
+        for json in [r#"{"x":0,"\u00e9":1,"e\u0301":2}"#, r#"{"x":0,"e\u0301":1,"\u00e9":2}"#] {
+            match normalizing.verify(json.as_bytes()) {
+                Err(VerifyError::DuplicateKey(_, key, previous_keys)) => {
+                    assert_eq!(key, "\u{e9}");
+                    assert_eq!(previous_keys, vec!["x", "\u{e9}"]);
+                },
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+
+        // as are keys that only differ in case or surrounding whitespace
+        let lenient = normalizing
+            .duplicate_key_case_folding(DuplicateKeyCaseFolding::Simple)
+            .trim_keys_for_dedup(true);
+        for json in [r#"{"A":1," a":2}"#, r#"{" a":1,"A":2}"#] {
+            match lenient.verify(json.as_bytes()) {
+                Err(VerifyError::DuplicateKey(_, key, previous_keys)) => {
+                    assert_eq!(key, "a");
+                    assert_eq!(previous_keys, vec!["a"]);
+                },
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_require_sorted_keys() {
+        use super::{KeyOrder, VerifyError};
+
+        let bytes = Verifier::new().require_sorted_keys(KeyOrder::Bytes);
+        let utf16 = Verifier::new().require_sorted_keys(KeyOrder::Utf16);
+
+        assert_eq!(test_verify(r#"{"b": 0, "a": 1}"#), true);
+        assert_eq!(test_verify_with(&bytes, r#"{"a": 0, "b": 1, "c": {"x": 2, "y": 3}}"#), true);
+        assert_eq!(test_verify_with(&bytes, r#"{"": 0, "a": 1, "aa": 2, "b": 3}"#), true);
+        assert_eq!(test_verify_with(&bytes, r#"[{"b": 0}, {"a": 1}]"#), true);
+
+        let cursor = std::io::Cursor::new(r#"{"a": 0, "c": 1, "b": 2}"#);
+        match bytes.verify(cursor) {
+            Err(VerifyError::UnsortedKey(position, key, previous_key)) => {
+                assert_eq!(position.offset, 17);
+                assert_eq!(key, "b");
+                assert_eq!(previous_key, "c");
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(test_verify_with(&bytes, r#"{"a": {"b": 0, "a": 1}}"#), false);
+
+        // U+1F600 is D83D DE00 in UTF-16 and therefore sorts before U+E000
+        let json = "{\"\u{E000}\": 0, \"\u{1F600}\": 1}";
+        assert_eq!(test_verify_with(&bytes, json), true);
+        assert_eq!(test_verify_with(&utf16, json), false);
+        let json = "{\"\u{1F600}\": 0, \"\u{E000}\": 1}";
+        assert_eq!(test_verify_with(&bytes, json), false);
+        assert_eq!(test_verify_with(&utf16, json), true);
+
+        // all unsorted keys are found when collecting errors
+        let cursor = std::io::Cursor::new(r#"{"c": 0, "b": 1, "a": 2}"#);
+        let errors = bytes.verify_collecting(cursor, 10);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|e| e.kind() == "unsorted-key"));
+    }
+
+    #[test]
+    fn test_require_key() {
+        use super::{ValueKind, VerifyError};
+
+        let verifier = Verifier::new()
+            .require_key("name", ValueKind::String)
+            .require_key("age", ValueKind::Number);
+
+        assert_eq!(test_verify_with(&verifier, r#"{"name": "Ann", "age": 42}"#), true);
+        assert_eq!(test_verify_with(&verifier, r#"{"age": 42, "other": [1], "name": "Ann"}"#), true);
+        assert_eq!(test_verify_with(&verifier, r#"{"nested": {"name": 1}, "name": "Ann", "age": 42}"#), true);
+
+        let cursor = std::io::Cursor::new(r#"{"name": "Ann", "more": {"age": 42}}"#);
+        match verifier.verify(cursor) {
+            Err(VerifyError::MissingKey(position, key)) => {
+                assert_eq!(position.offset, 35);
+                assert_eq!(key, "age");
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let cursor = std::io::Cursor::new(r#"{"name": "Ann", "age": "42"}"#);
+        match verifier.verify(cursor) {
+            Err(VerifyError::MistypedKey(position, key, required, actual)) => {
+                assert_eq!(position.offset, 23);
+                assert_eq!(key, "age");
+                assert_eq!(required, ValueKind::Number);
+                assert_eq!(actual, ValueKind::String);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(test_verify_with(&verifier, r#"{"name": ["Ann"], "age": 42}"#), false);
+        assert_eq!(test_verify_with(&verifier, r#"[{"name": "Ann", "age": 42}]"#), false);
+        assert_eq!(test_verify_with(&verifier, r#""Ann""#), false);
+
+        // both problems are found when collecting errors
+        let cursor = std::io::Cursor::new(r#"{"name": null}"#);
+        let errors = verifier.verify_collecting(cursor, 10);
+        let kinds: Vec<&str> = errors.iter().map(|e| e.kind()).collect();
+        assert_eq!(kinds, vec!["mistyped-key", "missing-key"]);
+    }
+
+    #[test]
+    fn test_feed_bytes() {
+        use super::{ParserState, StepOutcome, VerifyError};
+
+        fn feed_in_chunks(verifier: &Verifier, json: &str, chunk_size: usize) -> Result<(), VerifyError> {
+            let mut state = ParserState::new(verifier);
+            for chunk in json.as_bytes().chunks(chunk_size) {
+                state.feed_bytes(chunk)?;
+            }
+            state.finish()
+        }
+
+        let verifier = Verifier::new();
+        let documents = [
+            r#"{"a": [1, 2.5e-3, true, false, null], "b": {"c": "d\u00e9\n"}}"#,
+            "  [ 12345678 , -0.5 ]  ",
+            "123",
+            "true",
+            "\"string\"",
+            "",
+            "{\"a\" 1}",
+            "[1, 2",
+            "[1] 2",
+            "[tru]",
+            "\"unterminated",
+        ];
+        for json in documents {
+            let expected = verifier.verify(std::io::Cursor::new(json));
+            for chunk_size in [1, 2, 3, 7, 100] {
+                let result = feed_in_chunks(&verifier, json, chunk_size);
+                assert_eq!(result.is_ok(), expected.is_ok(), "{:?} in chunks of {}", json, chunk_size);
+            }
+        }
+
+        let lenient = Verifier::new()
+            .allow_comments(true)
+            .allow_single_quotes(true)
+            .allow_unquoted_keys(true)
+            .max_escapes_per_string(2);
+        let documents = [
+            "// line comment\n[1, /* block * comment */ 2] // another",
+            "{key: 'it\\'s', \"q\": \"\\\"\\\\\\u00e9\"}",
+            "[\"\\u0041\\u0042\\u0043\"]",
+            "[\"\\x\"]",
+            "[\"\\u12G4\"]",
+            "[01]",
+            "[1, /* unterminated]",
+            "[1, / 2]",
+        ];
+        for json in documents {
+            let expected = lenient.verify(std::io::Cursor::new(json));
+            for chunk_size in [1, 2, 3, 7, 100] {
+                let result = feed_in_chunks(&lenient, json, chunk_size);
+                assert_eq!(result.is_ok(), expected.is_ok(), "{:?} in chunks of {}", json, chunk_size);
+            }
+        }
+
+        // a line comment that reaches the end of a chunk continues in the next one
+        let mut state = ParserState::new(&lenient);
+        state.feed_bytes(b"[1, // comm").unwrap();
+        state.feed_bytes(b"ent\n2]").unwrap();
+        state.finish().unwrap();
+
+        // a long token fed in many chunks is not read again for each of them
+        let mut state = ParserState::new(&verifier);
+        state.feed_bytes(b"[\"").unwrap();
+        for _ in 0..200_000 {
+            assert_eq!(state.feed_bytes(b"ab\\n").unwrap(), StepOutcome::NeedMore);
+        }
+        assert_eq!(state.feed_bytes(b"\"]").unwrap(), StepOutcome::Complete);
+
+        // errors within a held-back token are reported as soon as they are fed
+        let mut state = ParserState::new(&verifier);
+        state.feed_bytes(b"[\"abc").unwrap();
+        state.feed_bytes(b"def").unwrap();
+        assert!(state.feed_bytes(b"\\x\"]").is_err());
+        let mut state = ParserState::new(&verifier);
+        state.feed_bytes(b"[12").unwrap();
+        assert!(state.feed_bytes(b"3x]").is_err());
+
+        // completion is reported as soon as it is certain
+        let mut state = ParserState::new(&verifier);
+        assert_eq!(state.feed_bytes(b"[12").unwrap(), StepOutcome::NeedMore);
+        assert_eq!(state.feed_bytes(b"34").unwrap(), StepOutcome::NeedMore);
+        assert_eq!(state.feed_bytes(b"]").unwrap(), StepOutcome::Complete);
+        assert_eq!(state.feed_bytes(b" \n").unwrap(), StepOutcome::Complete);
+        state.finish().unwrap();
+        assert_eq!(state.stats().numbers, 1);
+
+        let mut state = ParserState::new(&verifier);
+        assert_eq!(state.feed_bytes(b"12").unwrap(), StepOutcome::NeedMore);
+        assert_eq!(state.feed_bytes(b"3 ").unwrap(), StepOutcome::Complete);
+
+        // positions are counted across chunks
+        let mut state = ParserState::new(&verifier);
+        state.feed_bytes(b"{\n\"a\"").unwrap();
+        match state.feed_bytes(b" 1}") {
+            Err(VerifyError::UnexpectedToken(position, _, _)) => {
+                assert_eq!(position.offset, 6);
+                assert_eq!(position.line, 2);
+                assert_eq!(position.column, 5);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut state = ParserState::new(&verifier);
+        state.feed_bytes(b"[1,\r").unwrap();
+        match state.feed_bytes(b"\n]") {
+            Err(VerifyError::UnexpectedToken(position, _, _)) => assert_eq!((position.line, position.column), (2, 1)),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut state = ParserState::new(&verifier);
+        state.feed_bytes(b"[[1]").unwrap();
+        match state.finish() {
+            Err(VerifyError::UnclosedDocument(position, depth)) => {
+                assert_eq!(position.offset, 4);
+                assert_eq!(depth, 1);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let verifier = Verifier::new();
+
+        let diagnostic = verifier.diagnose(r#"{"items": [1, 2, nope]}"#.as_bytes()).unwrap_err();
+        assert_eq!(diagnostic.error.kind(), "tokenizer");
+        assert_eq!(diagnostic.pointer, "/items/2");
+        assert_eq!(
+            diagnostic.to_string(),
+            "failed to take next token: invalid bareword beginning \"no\" at line 1, column 18 (offset 17) in /items/2",
+        );
+
+        let diagnostic = verifier.diagnose(r#"{"a/b": {"c": 1 "d": 2}}"#.as_bytes()).unwrap_err();
+        assert_eq!(diagnostic.pointer, "/a~1b/c");
+
+        // at the root, there is no pointer to show
+        let diagnostic = verifier.diagnose("[1] 2".as_bytes()).unwrap_err();
+        assert_eq!(diagnostic.pointer, "");
+        assert_eq!(diagnostic.to_string(), "trailing garbage: another value (a number) follows at line 1, column 5 (offset 4)");
+
+        assert_eq!(verifier.diagnose("[1, [2]]".as_bytes()).unwrap().arrays, 2);
+
+        let pointers: Vec<String> = verifier.diagnose_collecting(r#"[{"a": 1,}, [2 3], "x" "y"]"#.as_bytes(), 10)
+            .into_iter()
+            .map(|d| d.pointer)
+            .collect();
+        assert_eq!(pointers, vec!["/0", "/1/0", "/2"]);
+    }
+
+    #[test]
+    fn test_unexpected_byte_order_mark() {
+        use super::{verify_detailed, VerifyError};
+
+        match verify_detailed("[1,\u{FEFF}2]".as_bytes()) {
+            Err(VerifyError::UnexpectedByteOrderMark(position)) => assert_eq!(position.offset, 3),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match verify_detailed("{\"a\": 1}\u{FEFF}".as_bytes()) {
+            Err(VerifyError::UnexpectedByteOrderMark(position)) => assert_eq!(position.offset, 8),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // a leading byte order mark is not stray, just not allowed
+        assert!(matches!(verify_detailed("\u{FEFF}[1]".as_bytes()), Err(VerifyError::Tokenizer(_, _))));
+
+        // nor are any when they are allowed as whitespace
+        let lenient = Verifier::new().allow_unicode_whitespace(true);
+        assert_eq!(test_verify_with(&lenient, "\u{FEFF}[1,\u{FEFF}2]"), true);
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        use super::VerifyError;
+
+        // roughly 2 KiB
+        let mut json = String::from("[");
+        for i in 0..400 {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("1234");
+        }
+        json.push(']');
+        assert_eq!(json.len(), 2001);
+
+        let small = Verifier::new().max_bytes(1024);
+        let result = small.verify(std::io::Cursor::new(&json));
+        assert!(matches!(result, Err(VerifyError::DocumentTooLarge(_, 1024))));
+
+        let exact = Verifier::new().max_bytes(2001);
+        assert_eq!(exact.verify(std::io::Cursor::new(&json)).is_ok(), true);
+
+        assert_eq!(small.verify_stream(std::io::Cursor::new(&json)).is_err(), true);
+    }
+
+    #[test]
+    fn test_require_root() {
+        use super::RootKind;
+
+        let object = Verifier::new().require_root(RootKind::Object);
+        assert_eq!(test_verify_with(&object, "{\"a\":[]}"), true);
+        assert_eq!(test_verify_with(&object, "[{}]"), false);
+        assert_eq!(test_verify_with(&object, "\"a\""), false);
+
+        let array = Verifier::new().require_root(RootKind::Array);
+        assert_eq!(test_verify_with(&array, "[{}]"), true);
+        assert_eq!(test_verify_with(&array, "{\"a\":[]}"), false);
+        assert_eq!(test_verify_with(&array, "0"), false);
+
+        assert_eq!(test_verify_with(&Verifier::new(), "0"), true);
+    }
+
+    #[test]
+    fn test_verify_after_prefix() {
+        use super::{verify_after_prefix, VerifyError};
+
+        assert!(verify_after_prefix(r#"LEVEL=INFO {"event": "started", "pid": 42}"#.as_bytes(), b' ').is_ok());
+        assert!(verify_after_prefix("header line\n[1, 2]\n".as_bytes(), b'\n').is_ok());
+        // only the first delimiter ends the prefix
+        assert!(verify_after_prefix(r#"LEVEL=INFO {"event": "user logged in"}"#.as_bytes(), b' ').is_ok());
+        assert!(verify_after_prefix("LEVEL=INFO = {}".as_bytes(), b'=').is_err());
+
+        match verify_after_prefix(r#"LEVEL=INFO {"event": "started",}"#.as_bytes(), b' ') {
+            Err(VerifyError::UnexpectedToken(position, _, _)) => assert_eq!(position.offset, 31),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match verify_after_prefix("LEVEL=INFO".as_bytes(), b' ') {
+            Err(VerifyError::MissingDelimiter(position, b' ')) => assert_eq!(position.offset, 10),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_count_root_array_elements() {
+        use super::{count_root_array_elements, VerifyError};
+
+        fn count(json: &str) -> Result<usize, VerifyError> {
+            count_root_array_elements(json.as_bytes())
+        }
+
+        assert_eq!(count("[]").unwrap(), 0);
+        assert_eq!(count(" [ 1 ] ").unwrap(), 1);
+        assert_eq!(count(r#"[1, "two", [3, 3, 3], {"four": [4]}, null]"#).unwrap(), 5);
+        assert_eq!(count("[[], [[]], {}]").unwrap(), 3);
+
+        assert!(matches!(count(r#"{"a": [1, 2]}"#), Err(VerifyError::WrongRootKind(_, _, _))));
+        assert!(matches!(count("1"), Err(VerifyError::WrongRootKind(_, _, _))));
+        assert!(matches!(count(""), Err(VerifyError::NoValue(_))));
+        assert!(count("[1, 2").is_err());
+        assert!(count("[1, 2] 3").is_err());
+
+        // the verifier's options apply
+        let verifier = Verifier::new().allow_comments(true);
+        assert_eq!(verifier.count_root_array_elements("[1, /* 2, */ 3]".as_bytes()).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_reject_empty_root() {
+        use super::VerifyError;
+
+        let non_empty = Verifier::new().reject_empty_root(true);
+        assert_eq!(test_verify_with(&non_empty, "{}"), false);
+        assert_eq!(test_verify_with(&non_empty, " [ ] "), false);
+        assert_eq!(test_verify_with(&non_empty, r#"{"a": {}}"#), true);
+        assert_eq!(test_verify_with(&non_empty, "[[], {}]"), true);
+        assert_eq!(test_verify_with(&non_empty, "[0]"), true);
+        assert_eq!(test_verify_with(&non_empty, "0"), true);
+
+        match non_empty.verify("[\n]".as_bytes()) {
+            Err(VerifyError::EmptyRoot(position)) => assert_eq!(position.offset, 2),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_require_composite_root() {
+        let composite = Verifier::new().require_composite_root(true);
+        assert_eq!(test_verify_with(&composite, "{}"), true);
+        assert_eq!(test_verify_with(&composite, "[42]"), true);
+        assert_eq!(test_verify_with(&composite, "42"), false);
+        assert_eq!(test_verify_with(&composite, "\"42\""), false);
+        assert_eq!(test_verify_with(&composite, "true"), false);
+
+        assert_eq!(test_verify_with(&Verifier::new(), "42"), true);
+        assert_eq!(test_verify_with(&Verifier::new(), "\"42\""), true);
+    }
+
+    #[test]
+    fn test_duplicate_key_order() {
+        let cursor = std::io::Cursor::new("{\"c\":0,\"a\":1,\"b\":2,\"a\":3}");
+        match super::verify_detailed(cursor) {
+            Err(super::VerifyError::DuplicateKey(_, key, previous_keys)) => {
+                assert_eq!(key, "a");
+                assert_eq!(previous_keys, vec!["c", "a", "b"]);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_single_quotes() {
+        let json5 = Verifier::new().allow_single_quotes(true);
+        assert_eq!(test_verify_with(&json5, "['a']"), true);
+        assert_eq!(test_verify_with(&json5, "{'a':\"b\"}"), true);
+        assert_eq!(test_verify_with(&json5, "{'a':0,\"a\":1}"), false);
+
+        assert_eq!(test_verify_with(&Verifier::new(), "['a']"), false);
+    }
+
+    #[test]
+    fn test_unquoted_keys() {
+        let lenient = Verifier::new().allow_unquoted_keys(true);
+        assert_eq!(test_verify_with(&lenient, "{a:1}"), true);
+        assert_eq!(test_verify_with(&lenient, "{_a$1: 1, \"b\": {null: true}}"), true);
+        assert_eq!(test_verify_with(&lenient, "{a:1,\"a\":2}"), false);
+        assert_eq!(test_verify_with(&lenient, "{a:b}"), false);
+        assert_eq!(test_verify_with(&lenient, "[a]"), false);
+        assert_eq!(test_verify_with(&lenient, "{1a:0}"), false);
+
+        assert_eq!(test_verify_with(&Verifier::new(), "{a:1}"), false);
+    }
+
+    #[test]
+    fn test_find_duplicate_keys() {
+        let verifier = Verifier::new();
+        let json = r#"{"a": 1, "b": [{"c": 1, "c": 2}, {"d/e~f": 1, "d/e~f": 2}], "a": {"x": 1, "x": 2, "x": 3}}"#;
+        assert_eq!(verifier.find_duplicate_keys(json.as_bytes()).unwrap(), vec![
+            ("c".to_owned(), "/b/0/c".to_owned()),
+            ("d/e~f".to_owned(), "/b/1/d~1e~0f".to_owned()),
+            ("a".to_owned(), "/a".to_owned()),
+            ("x".to_owned(), "/a/x".to_owned()),
+            ("x".to_owned(), "/a/x".to_owned()),
+        ]);
+
+        assert_eq!(verifier.find_duplicate_keys(r#"{"a": 1, "b": 2}"#.as_bytes()).unwrap(), Vec::new());
+        assert_eq!(verifier.find_duplicate_keys(r#"{"a": 1, "a": 2"#.as_bytes()).is_err(), true);
+
+        // the default stays fail-fast
+        assert_eq!(test_verify_with(&verifier, json), false);
+    }
+
+    #[test]
+    fn test_unicode_whitespace() {
+        assert_eq!(test_verify("[1]\u{A0}"), false);
+        assert_eq!(test_verify("{\"a\":\u{FEFF}1}"), false);
+
+        let verifier = Verifier::new()
+            .allow_unicode_whitespace(true);
+        assert_eq!(test_verify_with(&verifier, "\u{FEFF}[1]\u{A0}"), true);
+        assert_eq!(test_verify_with(&verifier, "{\"a\"\u{2003}:\u{3000}1}"), true);
+    }
+
+    #[test]
+    fn test_progress() {
+        use std::sync::{Arc, Mutex};
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let callback_reports = Arc::clone(&reports);
+        let verifier = Verifier::new()
+            .on_progress(move |bytes_read| callback_reports.lock().unwrap().push(bytes_read));
+
+        // 3 MiB and a bit
+        let mut json = String::from("[");
+        while json.len() < 3 * 1024 * 1024 {
+            json.push_str("12345678,");
+        }
+        json.push_str("0]");
+        assert_eq!(test_verify_with(&verifier, &json), true);
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 3);
+        for (i, &bytes_read) in reports.iter().enumerate() {
+            let mebibytes = (i as u64) + 1;
+            assert_eq!(bytes_read >= mebibytes * 1024 * 1024, true);
+            assert_eq!(bytes_read < mebibytes * 1024 * 1024 + 16, true);
+        }
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error;
+
+        let e = Verifier::new().verify("[nope]".as_bytes()).unwrap_err();
+        assert_eq!(e.source().map(|s| s.to_string()), Some("invalid bareword beginning \"no\"".to_owned()));
+
+        let e = Verifier::new().verify("[1 2]".as_bytes()).unwrap_err();
+        assert_eq!(e.source().is_none(), true);
+    }
+
+    #[test]
+    fn test_require_utf8() {
+        use super::VerifyError;
+
+        let verifier = Verifier::new()
+            .require_utf8(true);
+        assert_eq!(verifier.verify("[\"\u{E9}\u{1F600}\"]".as_bytes()).is_ok(), true);
+
+        match verifier.verify(&b"[1, \xFF]"[..]) {
+            Err(VerifyError::InvalidUtf8(p, offset)) => {
+                assert_eq!(p.offset, 4);
+                assert_eq!(offset, 4);
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match verifier.verify(&b"[\"a\xC3(\"]"[..]) {
+            Err(VerifyError::InvalidUtf8(p, offset)) => {
+                // reported at the beginning of the string
+                assert_eq!(p.offset, 1);
+                assert_eq!(offset, 3);
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // without the option, the invalid byte is a bareword
+        match Verifier::new().verify(&b"[1, \xFF]"[..]) {
+            Err(VerifyError::Tokenizer(_, _)) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_max_keys_per_object() {
+        fn object_with_keys(key_count: usize) -> String {
+            let members: Vec<String> = (0..key_count)
+                .map(|i| format!("\"k{}\": {}", i, i))
+                .collect();
+            format!("{{{}}}", members.join(", "))
+        }
+
+        let verifier = Verifier::new()
+            .max_keys_per_object(1000);
+        assert_eq!(test_verify_with(&verifier, &object_with_keys(1000)), true);
+        assert_eq!(test_verify_with(&verifier, &object_with_keys(1001)), false);
+        assert_eq!(test_verify(&object_with_keys(1001)), true);
+
+        // the limit applies to each object separately
+        let nested = format!("[{}, {}]", object_with_keys(1000), object_with_keys(1000));
+        assert_eq!(test_verify_with(&verifier, &nested), true);
+    }
+
+    #[test]
+    fn test_max_array_elements() {
+        use super::VerifyError;
+
+        fn array_with_elements(element_count: usize) -> String {
+            let elements: Vec<String> = (0..element_count)
+                .map(|i| i.to_string())
+                .collect();
+            format!("[{}]", elements.join(","))
+        }
+
+        let verifier = Verifier::new()
+            .max_array_elements(1000);
+        assert_eq!(test_verify_with(&verifier, &array_with_elements(1000)), true);
+        assert_eq!(test_verify(&array_with_elements(1001)), true);
+
+        // fails at the 1001st element
+        let json = array_with_elements(1001);
+        match verifier.verify(json.as_bytes()) {
+            Err(VerifyError::TooManyElements(p, 1000)) => assert_eq!(p.offset, json.len() as u64 - 5),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // nested containers count as elements, and the limit applies to each array separately
+        let verifier = Verifier::new()
+            .max_array_elements(2);
+        assert_eq!(test_verify_with(&verifier, "[[1, 2], {\"a\": [3, 4]}]"), true);
+        assert_eq!(test_verify_with(&verifier, "[[1, 2], {}, []]"), false);
+        assert_eq!(test_verify_with(&verifier, "[[1, 2, 3]]"), false);
+        assert_eq!(test_verify_with(&Verifier::new().max_array_elements(0), "[]"), true);
+        assert_eq!(test_verify_with(&Verifier::new().max_array_elements(0), "[\"a\"]"), false);
+    }
+
+    /// Verifies the document, which must be valid, and returns the warnings raised on the way.
+    fn collected_warnings(verifier: &Verifier, json: &str) -> Vec<super::Warning> {
+        use std::sync::{Arc, Mutex};
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let callback_warnings = Arc::clone(&warnings);
+        let verifier = verifier.clone()
+            .on_warning(move |w| callback_warnings.lock().unwrap().push(w.clone()));
+        assert_eq!(test_verify_with(&verifier, json), true);
+        let collected = warnings.lock().unwrap().clone();
+        collected
+    }
+
+    /// A position on the first line of a document.
+    fn first_line(offset: u64, column: u64) -> crate::position::Position {
+        crate::position::Position { offset, line: 1, column }
+    }
+
+    #[test]
+    fn test_lint_number_quirks() {
+        use crate::number::NumberQuirk;
+        use super::Warning;
+
+        assert_eq!(
+            collected_warnings(&Verifier::new().lint_number_quirks(true), "[1e7, 1e007, -0]"),
+            vec![
+                Warning::NonCanonicalNumber(first_line(6, 7), b"1e007".to_vec(), NumberQuirk::ExponentLeadingZeros),
+                Warning::NonCanonicalNumber(first_line(13, 14), b"-0".to_vec(), NumberQuirk::NegativeZero),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_lint_integer_exponents() {
+        use super::Warning;
+
+        assert_eq!(
+            collected_warnings(&Verifier::new().lint_integer_exponents(true), "[1e5, 1.25e1, 100, 2.5e1]"),
+            vec![
+                Warning::IntegerWithExponent(first_line(1, 2), b"1e5".to_vec()),
+                Warning::IntegerWithExponent(first_line(19, 20), b"2.5e1".to_vec()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_lint_double_range() {
+        use crate::number::DoubleRangeViolation;
+        use super::Warning;
+
+        assert_eq!(
+            collected_warnings(&Verifier::new().lint_double_range(true), "[1e308, 1e999999999, 0e999, -1e-400]"),
+            vec![
+                Warning::NumberOverflowsDouble(first_line(8, 9), b"1e999999999".to_vec(), DoubleRangeViolation::Overflow),
+                Warning::NumberOverflowsDouble(first_line(28, 29), b"-1e-400".to_vec(), DoubleRangeViolation::Underflow),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_lint_jsonp_safe() {
+        use super::Warning;
+
+        // the positions are those of the strings; U+2028 and U+2029 take three bytes each
+        assert_eq!(
+            collected_warnings(
+                &Verifier::new().lint_jsonp_safe(true),
+                "{\"a\u{2028}\": \"\\u2028\", \"b\": [\"x\u{2029}y\u{2028}\", \"\u{2027}\"]}",
+            ),
+            vec![
+                Warning::JsonpUnsafeCharacter(first_line(1, 2), '\u{2028}'),
+                Warning::JsonpUnsafeCharacter(first_line(25, 24), '\u{2029}'),
+                Warning::JsonpUnsafeCharacter(first_line(25, 24), '\u{2028}'),
+            ],
+        );
+
+        // off by default
+        assert_eq!(collected_warnings(&Verifier::new(), "[\"\u{2028}\"]"), vec![]);
+    }
+
+    #[test]
+    fn test_allow_empty() {
+        use super::VerifyError;
+
+        // accepted by default
+        assert_eq!(test_verify(""), true);
+        assert_eq!(test_verify(" \t\r\n "), true);
+
+        let verifier = Verifier::new()
+            .allow_empty(false);
+        match verifier.verify("".as_bytes()) {
+            Err(VerifyError::NoValue(p)) => assert_eq!(p.offset, 0),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match verifier.verify(" \t\r\n ".as_bytes()) {
+            Err(VerifyError::NoValue(p)) => assert_eq!(p.offset, 5),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(test_verify_with(&verifier, "0"), true);
+        assert_eq!(test_verify_with(&verifier, " [] "), true);
+
+        // streams may be empty either way
+        assert_eq!(verifier.verify_stream("".as_bytes()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_whitespace_only() {
+        use super::VerifyError;
+
+        let lenient = Verifier::new()
+            .allow_comments(true)
+            .allow_unicode_whitespace(true);
+        let strict = lenient.clone()
+            .allow_empty(false);
+
+        for json in ["\n", "   \n  ", "// nothing\n", "/* nothing */ ", "\u{FEFF}\u{A0}"] {
+            assert_eq!(test_verify_with(&lenient, json), true);
+            match strict.verify(json.as_bytes()) {
+                Err(VerifyError::NoValue(p)) => assert_eq!(p.offset, json.len() as u64),
+                other => panic!("unexpected result for {:?}: {:?}", json, other),
+            }
+        }
+
+        // a value surrounded by whitespace is fine
+        assert_eq!(test_verify_with(&strict, "  /* a */ null // b\n"), true);
+    }
+
+    #[test]
+    fn test_parser_state() {
+        use crate::position::Position;
+        use crate::tokenizer::JsonToken;
+        use super::{ParserState, StepOutcome, VerifyError};
+
+        let verifier = Verifier::new();
+        let mut state = ParserState::new(&verifier);
+        let pos = Position::default();
+
+        assert_eq!(state.feed_token(JsonToken::OpeningBracket, pos).unwrap(), StepOutcome::NeedMore);
+        assert_eq!(state.feed_token(JsonToken::OpeningBrace, pos).unwrap(), StepOutcome::NeedMore);
+        assert_eq!(state.depth(), 2);
+        assert_eq!(state.feed_token(JsonToken::ClosingBrace, pos).unwrap(), StepOutcome::NeedMore);
+        assert_eq!(state.feed_token(JsonToken::ClosingBracket, pos).unwrap(), StepOutcome::Complete);
+        assert_eq!(state.depth(), 0);
+
+        // an error leaves the state unusable until it is reset
+        state.reset();
+        assert_eq!(state.feed_token(JsonToken::OpeningBrace, pos).unwrap(), StepOutcome::NeedMore);
+        match state.feed_token(JsonToken::Comma, pos) {
+            Err(VerifyError::UnexpectedToken(_, JsonToken::Comma, _)) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        state.reset();
+        assert_eq!(state.depth(), 0);
+        assert_eq!(state.feed_token(JsonToken::Number(b"1".to_vec()), pos).unwrap(), StepOutcome::Complete);
+    }
+
+    #[test]
+    fn test_stats() {
+        let verifier = Verifier::new();
+        let stats = verifier.verify_with_stats(" {\"a\": [1, 2], \"b\": null} \n".as_bytes()).unwrap();
+        assert_eq!(stats.tokens, 13);
+        assert_eq!(stats.bytes, 27);
+        assert_eq!(stats.objects, 1);
+        assert_eq!(stats.arrays, 1);
+        assert_eq!(stats.numbers, 2);
+        assert_eq!(stats.nulls, 1);
+        assert_eq!(stats.max_depth, 2);
+
+        let stats = verifier.verify_with_stats(r#"[{"k": "v", "l": [true, false, [[]]]}, {}, "w"]"#.as_bytes()).unwrap();
+        assert_eq!(stats.objects, 2);
+        assert_eq!(stats.arrays, 4);
+        assert_eq!(stats.strings, 2);
+        assert_eq!(stats.numbers, 0);
+        assert_eq!(stats.booleans, 2);
+        assert_eq!(stats.nulls, 0);
+        assert_eq!(stats.max_depth, 5);
+
+        let stats = verifier.verify_with_stats("\"scalar\"".as_bytes()).unwrap();
+        assert_eq!(stats.strings, 1);
+        assert_eq!(stats.max_depth, 0);
+        assert_eq!(stats.longest_string, 6);
+        assert_eq!(stats.longest_number, 0);
+
+        let stats = verifier.verify_with_stats(r#"{"longest key": ["\u00e9t\u00e9", "été", "\ud83d\ude00"], "n": [1, -12.5e3, 100]}"#.as_bytes()).unwrap();
+        assert_eq!(stats.longest_string, 11);
+        assert_eq!(stats.longest_number, 7);
+
+        let stats = verifier.verify_with_stats(r#"["été", "\ud83d\ude00x"]"#.as_bytes()).unwrap();
+        assert_eq!(stats.longest_string, 3);
+
+        let stats = verifier.verify_with_stats("".as_bytes()).unwrap();
+        assert_eq!(stats.tokens, 0);
+        assert_eq!(stats.bytes, 0);
+
+        assert_eq!(verifier.verify_with_stats("[1,]".as_bytes()).is_err(), true);
+    }
+
+    #[test]
+    fn test_extra_whitespace() {
+        use crate::tokenizer::ExtraWhitespace;
+
+        let strict = Verifier::new();
+        assert_eq!(test_verify_with(&strict, "[1,\x0C2]"), false);
+        assert_eq!(test_verify_with(&strict, "[true\x0B]"), false);
+
+        let form_feed = Verifier::new()
+            .extra_whitespace(ExtraWhitespace::FORM_FEED);
+        assert_eq!(test_verify_with(&form_feed, "[1,\x0C2]"), true);
+        assert_eq!(test_verify_with(&form_feed, "\x0C{\"a\":\x0Ctrue\x0C}\x0C"), true);
+        assert_eq!(test_verify_with(&form_feed, "[true\x0B]"), false);
+
+        let both = Verifier::new()
+            .extra_whitespace(ExtraWhitespace::FORM_FEED | ExtraWhitespace::VERTICAL_TAB);
+        assert_eq!(test_verify_with(&both, "[true\x0B,\x0Cnull]"), true);
+    }
+
+    #[test]
+    fn test_invalid_utf8_in_string() {
+        use super::VerifyError;
+
+        // the string begins at offset 8; its invalid sequence (after "a\n\u00e9") at offset 18
+        let json = b"[1, \"\", \"a\\n\\u00e9\xC3(\"]";
+        match Verifier::new().verify(&json[..]) {
+            Err(e) => {
+                assert_eq!(e.position().offset, 8);
+                match &e {
+                    VerifyError::InvalidUtf8InString(_, offset, _) => assert_eq!(*offset, 18),
+                    other => panic!("unexpected error: {:?}", other),
+                }
+                assert_eq!(e.to_string(), "invalid UTF-8 sequence at offset 18 in string");
+                assert_eq!(std::error::Error::source(&e).is_some(), true);
+            },
+            Ok(()) => panic!("invalid UTF-8 accepted"),
+        }
+
+        // a valid sequence of the same bytes earlier in the string does not confuse the offset
+        let json = b"\"\xC3\xA9\xC3\"";
+        match Verifier::new().verify(&json[..]) {
+            Err(VerifyError::InvalidUtf8InString(_, offset, _)) => assert_eq!(offset, 3),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // other string errors are reported as before
+        match Verifier::new().verify(&b"\"\\udc00\""[..]) {
+            Err(VerifyError::InvalidString(_, _)) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_extract() {
+        use crate::tokenizer::{JsonChar, JsonToken};
+        use super::{extract, VerifyError};
+
+        let json = r#"{"a": [1, {"b": "c"}], "d/e": {"f~g": null}, "": 5}"#;
+        let value = extract(json.as_bytes(), "/a/1").unwrap().unwrap();
+        assert_eq!(value.tokens, vec![
+            JsonToken::OpeningBrace,
+            JsonToken::String(vec![JsonChar::Byte(b'b')]),
+            JsonToken::Colon,
+            JsonToken::String(vec![JsonChar::Byte(b'c')]),
+            JsonToken::ClosingBrace,
+        ]);
+        assert_eq!(&json[value.span.start.offset as usize..value.span.end.offset as usize], r#"{"b": "c"}"#);
+
+        assert_eq!(extract(json.as_bytes(), "/a/0").unwrap().unwrap().tokens, vec![JsonToken::Number(b"1".to_vec())]);
+        assert_eq!(extract(json.as_bytes(), "/d~1e/f~0g").unwrap().unwrap().tokens, vec![JsonToken::Null]);
+        assert_eq!(extract(json.as_bytes(), "/").unwrap().unwrap().tokens, vec![JsonToken::Number(b"5".to_vec())]);
+        assert_eq!(extract(json.as_bytes(), "").unwrap().unwrap().tokens.len(), 25);
+        assert_eq!(extract(json.as_bytes(), "/a/2").unwrap(), None);
+        assert_eq!(extract(json.as_bytes(), "/a/01").unwrap(), None);
+        assert_eq!(extract(json.as_bytes(), "/x").unwrap(), None);
+        assert_eq!(extract(json.as_bytes(), "a").unwrap(), None);
+
+        // the document is verified up to the end of the value
+        assert!(matches!(
+            extract(r#"{"a": [1,, 2], "b": 3}"#.as_bytes(), "/b"),
+            Err(VerifyError::UnexpectedToken(_, _, _))
+        ));
+        assert!(matches!(
+            extract(r#"{"a": [1, 2}, "b": 3}"#.as_bytes(), "/a/1"),
+            Ok(Some(_))
+        ));
+        assert!(matches!(
+            extract(r#"{"a": [1, 2}, "b": 3}"#.as_bytes(), "/a"),
+            Err(VerifyError::UnexpectedToken(_, _, _))
+        ));
+        assert!(matches!(
+            extract(r#"{"a": 1} 2"#.as_bytes(), "/b"),
+            Err(VerifyError::TrailingGarbage(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_contains_key_at() {
+        use super::{contains_key_at, VerifyError};
+
+        let json = r#"{"a": {"b": {"x": 1}, "c~d": 2}, "e": [{"f": null}], "g": 3}"#;
+        assert_eq!(contains_key_at(json.as_bytes(), "", "a").unwrap(), true);
+        assert_eq!(contains_key_at(json.as_bytes(), "", "x").unwrap(), false);
+        assert_eq!(contains_key_at(json.as_bytes(), "/a", "b").unwrap(), true);
+        assert_eq!(contains_key_at(json.as_bytes(), "/a", "c~d").unwrap(), true);
+        assert_eq!(contains_key_at(json.as_bytes(), "/a", "x").unwrap(), false);
+        assert_eq!(contains_key_at(json.as_bytes(), "/a/b", "x").unwrap(), true);
+        assert_eq!(contains_key_at(json.as_bytes(), "/e/0", "f").unwrap(), true);
+        assert_eq!(contains_key_at(json.as_bytes(), "/e", "0").unwrap(), false);
+        assert_eq!(contains_key_at(json.as_bytes(), "/g", "g").unwrap(), false);
+        assert_eq!(contains_key_at(json.as_bytes(), "/h", "i").unwrap(), false);
+
+        // scanning stops as soon as the answer is known
+        assert_eq!(contains_key_at(r#"{"a": {"b": 1, "c": }"#.as_bytes(), "/a", "b").unwrap(), true);
+        assert!(matches!(
+            contains_key_at(r#"{"a": {"b": 1, "c": }"#.as_bytes(), "/a", "d"),
+            Err(VerifyError::UnexpectedToken(_, _, _))
+        ));
+        assert!(matches!(
+            contains_key_at(r#"{"a": {"b": 1}"#.as_bytes(), "/x", "b"),
+            Err(VerifyError::UnclosedDocument(_, _))
+        ));
+        assert!(matches!(
+            contains_key_at(r#"{"a": 1, "b": }"#.as_bytes(), "/a", "b"),
+            Err(VerifyError::UnexpectedToken(_, _, _))
+        ));
+    }
+
+    #[test]
+    fn test_verifier_comparison() {
+        assert_eq!(Verifier::new(), Verifier::new());
+        assert_ne!(Verifier::new(), Verifier::new().max_depth(3));
+
+        // callbacks are compared by identity
+        let verifier = Verifier::new().on_progress(|_| {});
+        assert_eq!(verifier.clone(), verifier);
+        assert_ne!(Verifier::new().on_progress(|_| {}), verifier);
+        assert_ne!(Verifier::new(), verifier);
+    }
+
+    #[test]
+    fn test_diagnostic_writer() {
+        use std::io::Write;
+        use std::sync::{Arc, Mutex};
+
+        struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+        impl Write for SharedBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let verifier = Verifier::new()
+            .diagnostic_writer(SharedBuffer(Arc::clone(&buffer)));
+
+        assert_eq!(verifier.verify_and_report("[1, 2]".as_bytes()), true);
+        assert_eq!(buffer.lock().unwrap().len(), 0);
+
+        assert_eq!(verifier.verify_and_report("[1, 2".as_bytes()), false);
+        assert_eq!(
+            String::from_utf8(buffer.lock().unwrap().clone()).unwrap(),
+            "JSON document ends without closing 1 containers at line 1, column 6 (offset 5) in /1\n",
+        );
+    }
+
+    #[test]
+    fn test_verify_value() {
+        use std::io::{BufRead, Read};
+        use super::{verify_value, VerifyError};
+
+        assert_eq!(verify_value("{\"a\": [1]} trailing".as_bytes()).unwrap(), 10);
+        assert_eq!(verify_value("  123,456".as_bytes()).unwrap(), 5);
+        assert_eq!(verify_value("true}".as_bytes()).unwrap(), 4);
+        assert_eq!(verify_value("\"x\"\"y\"".as_bytes()).unwrap(), 3);
+        assert!(matches!(verify_value("  ".as_bytes()), Err(VerifyError::NoValue(_))));
+        assert!(matches!(verify_value("[1, 2".as_bytes()), Err(VerifyError::UnclosedDocument(_, _))));
+        assert!(matches!(verify_value("]".as_bytes()), Err(VerifyError::UnexpectedToken(_, _, _))));
+
+        // the reader stops right after the value
+        let mut cursor = std::io::Cursor::new("[1]\n[2]\nrest");
+        assert_eq!(verify_value(&mut cursor).unwrap(), 3);
+        assert_eq!(verify_value(&mut cursor).unwrap(), 4);
+        let mut rest = String::new();
+        cursor.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "\nrest");
+
+        let mut cursor = std::io::Cursor::new("12 ");
+        assert_eq!(verify_value(&mut cursor).unwrap(), 2);
+        assert_eq!(cursor.fill_buf().unwrap(), b" ");
+
+        let limited = Verifier::new().max_bytes(4);
+        assert_eq!(limited.verify_value("[1] garbage".as_bytes()).unwrap(), 3);
+        assert!(matches!(limited.verify_value("[1, 2]".as_bytes()), Err(VerifyError::DocumentTooLarge(_, 4))));
+    }
+
+    #[test]
+    fn test_expects_display() {
+        use super::ParserExpects;
+
+        assert_eq!(ParserExpects::VALUE.to_string(), "a value");
+        assert_eq!((ParserExpects::COMMA | ParserExpects::CLOSING_BRACKET).to_string(), "a comma or closing bracket");
+        assert_eq!(
+            (ParserExpects::VALUE | ParserExpects::KEY | ParserExpects::CLOSING_BRACE).to_string(),
+            "a value, key or closing brace",
+        );
+        assert_eq!(ParserExpects::empty().to_string(), "the end of the document");
+
+        let error = super::verify_detailed("[1 2]".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "obtained Number([50]), expected a comma or closing bracket");
+    }
+
+    #[test]
+    fn test_verify_collecting() {
+        use super::VerifyError;
+
+        fn collected(json: &str, max_errors: usize) -> Vec<VerifyError> {
+            Verifier::new().verify_collecting(json.as_bytes(), max_errors)
+        }
+        fn offsets(errors: &[VerifyError]) -> Vec<u64> {
+            errors.iter().map(|e| e.position().offset).collect()
+        }
+
+        assert_eq!(collected(r#"{"a": [1, 2], "b": null}"#, 10).len(), 0);
+        assert_eq!(collected("", 10).len(), 0);
+
+        // missing comma, trailing comma, duplicate key
+        let errors = collected(r#"{"a": [1 2,], "b": nope, "a": {"c": 3,}}"#, 10);
+        assert_eq!(errors.iter().map(|e| e.kind()).collect::<Vec<_>>(), vec![
+            "unexpected-token", "unexpected-token", "tokenizer", "duplicate-key", "unexpected-token",
+        ]);
+        assert_eq!(offsets(&errors), vec![9, 11, 19, 25, 38]);
+
+        // an unclosed array within an object
+        let errors = collected(r#"{"a": [1, 2}"#, 10);
+        assert_eq!(offsets(&errors), vec![11]);
+
+        // the limit is honored
+        assert_eq!(collected("[1 2 3 4 5]", 2).len(), 2);
+        assert_eq!(collected("[1 2 3 4 5]", 10).len(), 4);
+        assert_eq!(collected("[1 2", 0).len(), 0);
+
+        // the end of the document is checked as well
+        let errors = collected("[1,, 2] 3", 10);
+        assert!(matches!(errors[..], [VerifyError::UnexpectedToken(_, _, _), VerifyError::TrailingGarbage(_, _)]));
+        let errors = collected("[1 [2", 10);
+        assert!(matches!(errors[..], [VerifyError::UnexpectedToken(_, _, _), VerifyError::UnclosedDocument(_, 2)]));
+
+        // limits end verification
+        let errors = Verifier::new().max_depth(1).verify_collecting("[[1 2]]".as_bytes(), 10);
+        assert!(matches!(errors[..], [VerifyError::MaxDepthExceeded(_, 1)]));
+    }
+
+    #[test]
+    fn test_decimal_comma() {
+        use super::VerifyError;
+
+        let strict = Verifier::new();
+        let lenient = Verifier::new().decimal_comma(true);
+
+        assert_eq!(test_verify_with(&strict, r#"{"pi": 3,14}"#), false);
+        assert_eq!(test_verify_with(&lenient, r#"{"pi": 3,14}"#), true);
+        assert_eq!(test_verify_with(&lenient, r#"{"pi": -3,14, "e": 2,71828}"#), true);
+        assert_eq!(test_verify_with(&lenient, "3,14"), true);
+
+        // within arrays, the comma remains a separator
+        let stats = lenient.verify_with_stats("[3,14]".as_bytes()).unwrap();
+        assert_eq!(stats.numbers, 2);
+
+        // separators remain separators
+        assert_eq!(test_verify_with(&lenient, r#"{"a": 3,"b": 4}"#), true);
+        assert_eq!(test_verify_with(&lenient, r#"{"a": 3,"b": [1,2]}"#), true);
+        assert_eq!(test_verify_with(&lenient, r#"{"a": 3, "b": 4}"#), true);
+
+        // only a single comma directly followed by digits
+        assert_eq!(test_verify_with(&lenient, r#"{"pi": 3, 14}"#), false);
+        assert_eq!(test_verify_with(&lenient, r#"{"pi": 3,14,15}"#), false);
+        assert_eq!(test_verify_with(&lenient, r#"{"pi": 3.1,4}"#), false);
+        assert_eq!(test_verify_with(&lenient, r#"{"pi": 3,}"#), false);
+        assert!(matches!(
+            lenient.verify("3,".as_bytes()),
+            Err(VerifyError::TrailingGarbage(p, Some(_))) if p.offset == 1
+        ));
+
+        // every way of verifying agrees
+        let json = r#"[{"pi": 3,14}, {"e": 2,7, "x": 1}]"#;
+        assert_eq!(lenient.count_root_array_elements(json.as_bytes()).unwrap(), 2);
+        assert_eq!(lenient.verify_collecting(json.as_bytes(), 10).len(), 0);
+        assert_eq!(lenient.contains_key_at(json.as_bytes(), "/1", "x").unwrap(), true);
+        let extracted = lenient.extract(json.as_bytes(), "/0/pi").unwrap().unwrap();
+        assert_eq!(extracted.tokens, vec![crate::tokenizer::JsonToken::Number(b"3,14".to_vec())]);
+        for chunk_size in 1..json.len() {
+            let mut state = super::ParserState::new(&lenient);
+            for chunk in json.as_bytes().chunks(chunk_size) {
+                state.feed_bytes(chunk).unwrap();
+            }
+            state.finish().unwrap();
+        }
+        let mut state = super::ParserState::new(&lenient);
+        assert_eq!(state.feed_bytes(b"3,").unwrap(), super::StepOutcome::NeedMore);
+        assert!(matches!(state.finish(), Err(VerifyError::TrailingGarbage(p, Some(_))) if p.offset == 1));
+    }
+
+    #[test]
+    fn test_verify_value_consuming() {
+        use std::io::Read;
+        use super::{verify_value_consuming, VerifyError};
+
+        let mut cursor = std::io::Cursor::new(" {\"a\": 1} \r\n\t[2]  3");
+        assert_eq!(verify_value_consuming(&mut cursor).unwrap(), 9);
+        assert_eq!(cursor.position(), 13);
+        assert_eq!(verify_value_consuming(&mut cursor).unwrap(), 3);
+        assert_eq!(verify_value_consuming(&mut cursor).unwrap(), 1);
+        let mut rest = Vec::new();
+        cursor.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), 0);
+
+        assert!(matches!(verify_value_consuming("  ".as_bytes()), Err(VerifyError::NoValue(_))));
+        assert!(matches!(verify_value_consuming("{".as_bytes()), Err(VerifyError::UnclosedDocument(_, 1))));
+    }
+
+    #[test]
+    fn test_verify_file() {
+        use super::{verify_file, verify_file_detailed, VerifyError};
+
+        let dir = std::env::temp_dir();
+        let valid_path = dir.join(format!("jsonvfy-test-{}-valid.json", std::process::id()));
+        let invalid_path = dir.join(format!("jsonvfy-test-{}-invalid.json", std::process::id()));
+        let missing_path = dir.join(format!("jsonvfy-test-{}-missing.json", std::process::id()));
+        std::fs::write(&valid_path, "{\"a\": [1, 2]}").unwrap();
+        std::fs::write(&invalid_path, "{\"a\": [1, 2}").unwrap();
+
+        assert_eq!(verify_file(&valid_path).unwrap(), true);
+        assert_eq!(verify_file(&invalid_path).unwrap(), false);
+        assert_eq!(verify_file(&missing_path).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+
+        assert!(verify_file_detailed(&valid_path).is_ok());
+        assert!(matches!(verify_file_detailed(&invalid_path), Err(VerifyError::UnexpectedToken(_, _, _))));
+        assert!(matches!(verify_file_detailed(&missing_path), Err(VerifyError::Tokenizer(_, _))));
+
+        std::fs::remove_file(&valid_path).unwrap();
+        std::fs::remove_file(&invalid_path).unwrap();
     }
 }
@@ -0,0 +1,114 @@
+use std::io::BufRead;
+use std::ops::ControlFlow;
+
+use crate::position::Position;
+use crate::tokenizer::{interpret_string, JsonToken};
+use crate::verifier::{ParserExpects, ValueKind, Verifier, VerifyError};
+
+
+/// Receives the values of a document from the inside out while it is being verified.
+///
+/// Containers are opened when their opening token is encountered, receive their elements or
+/// members as these are completed and are turned into values when they are closed.
+pub(crate) trait ValueVisitor {
+    type Value;
+    type Array;
+    type Object;
+
+    /// The error that verification and the visitor fail with.
+    type Error: From<VerifyError>;
+
+    fn begin_array(&mut self) -> Self::Array;
+    fn begin_object(&mut self) -> Self::Object;
+    fn array_element(&mut self, array: &mut Self::Array, element: Self::Value);
+    fn object_member(&mut self, object: &mut Self::Object, key: String, value: Self::Value);
+    fn end_array(&mut self, array: Self::Array) -> Self::Value;
+    fn end_object(&mut self, object: Self::Object) -> Self::Value;
+
+    /// Turns a string, number, boolean or null token into a value.
+    fn scalar(&mut self, tok: JsonToken, position: Position) -> Result<Self::Value, Self::Error>;
+}
+
+
+/// A container whose value is being assembled.
+enum Frame<V: ValueVisitor> {
+    Array(V::Array),
+    Object {
+        object: V::Object,
+        current_key: Option<String>,
+    },
+}
+
+
+/// Returns the key that the token represents.
+fn decode_key(tok: JsonToken, position: Position) -> Result<String, VerifyError> {
+    match tok {
+        JsonToken::String(chars) => interpret_string(&chars)
+            .map_err(|e| VerifyError::from_tokenizer(position, e)),
+        JsonToken::Identifier(identifier) => Ok(String::from_utf8_lossy(&identifier).into_owned()),
+        JsonToken::True => Ok("true".to_owned()),
+        JsonToken::False => Ok("false".to_owned()),
+        _ => Ok("null".to_owned()),
+    }
+}
+
+
+/// Verifies a document following the options of the given verifier and passes its values to the
+/// visitor.
+///
+/// Returns the root value, or `None` if the document is empty.
+pub(crate) fn visit_values<R: BufRead, V: ValueVisitor>(verifier: &Verifier, json_reader: R, visitor: &mut V) -> Result<Option<V::Value>, V::Error> {
+    let mut stack: Vec<Frame<V>> = Vec::new();
+    let mut root = None;
+
+    verifier.scan::<_, (), V::Error, _>(json_reader, |state, tok, span| {
+        let expects = state.expects();
+        let step_outcome = state.feed_token(tok.clone(), span.start)?;
+
+        // the token has been verified; what remains is passing it on
+        let is_closing = matches!(tok, JsonToken::ClosingBrace | JsonToken::ClosingBracket);
+        if expects.contains(ParserExpects::KEY) && !is_closing {
+            let key = decode_key(tok, span.start)?;
+            if let Some(Frame::Object { current_key, .. }) = stack.last_mut() {
+                *current_key = Some(key);
+            }
+            return Ok(ControlFlow::Continue(step_outcome));
+        }
+
+        let value = match tok {
+            JsonToken::OpeningBrace => {
+                stack.push(Frame::Object { object: visitor.begin_object(), current_key: None });
+                return Ok(ControlFlow::Continue(step_outcome));
+            },
+            JsonToken::OpeningBracket => {
+                stack.push(Frame::Array(visitor.begin_array()));
+                return Ok(ControlFlow::Continue(step_outcome));
+            },
+            JsonToken::ClosingBrace => match stack.pop() {
+                Some(Frame::Object { object, .. }) => visitor.end_object(object),
+                _ => panic!("verifier accepted CLOSING_BRACE but the top frame is not an object"),
+            },
+            JsonToken::ClosingBracket => match stack.pop() {
+                Some(Frame::Array(array)) => visitor.end_array(array),
+                _ => panic!("verifier accepted CLOSING_BRACKET but the top frame is not an array"),
+            },
+            tok if ValueKind::of(&tok).is_some() => visitor.scalar(tok, span.start)?,
+            // punctuation
+            _ => return Ok(ControlFlow::Continue(step_outcome)),
+        };
+
+        // attach the completed value to its container
+        match stack.last_mut() {
+            Some(Frame::Array(array)) => visitor.array_element(array, value),
+            Some(Frame::Object { object, current_key }) => {
+                let key = current_key.take()
+                    .expect("object member value without key");
+                visitor.object_member(object, key, value);
+            },
+            None => root = Some(value),
+        }
+        Ok(ControlFlow::Continue(step_outcome))
+    })?;
+
+    Ok(root)
+}
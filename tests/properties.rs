@@ -0,0 +1,121 @@
+use proptest::prelude::*;
+
+use jsonvfy::verifier::{verify_detailed, Verifier};
+
+
+/// Writes a string literal, escaping the characters that must be escaped and, as chosen by the
+/// generator, some of the others.
+fn string_literal(chars: &[(char, bool)]) -> String {
+    let mut literal = String::from("\"");
+    for &(c, escape) in chars {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            c if c < ' ' || escape => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    literal.push_str(&format!("\\u{:04x}", unit));
+                }
+            },
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+
+fn whitespace() -> impl Strategy<Value = String> {
+    proptest::string::string_regex("[ \t\r\n]{0,2}").unwrap()
+}
+
+
+fn string() -> impl Strategy<Value = String> {
+    prop::collection::vec((any::<char>(), prop::bool::weighted(0.2)), 0..8)
+        .prop_map(|chars| string_literal(&chars))
+}
+
+
+fn number() -> impl Strategy<Value = String> {
+    proptest::string::string_regex("-?(0|[1-9][0-9]{0,20})(\\.[0-9]{1,10})?([eE][+-]?[0-9]{1,4})?").unwrap()
+}
+
+
+/// Generates a valid JSON document.
+fn json() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        Just("null".to_owned()),
+        Just("true".to_owned()),
+        Just("false".to_owned()),
+        number(),
+        string(),
+    ];
+    leaf.prop_recursive(6, 64, 8, |inner| {
+        let element = (whitespace(), inner, whitespace())
+            .prop_map(|(before, value, after)| format!("{}{}{}", before, value, after))
+            .boxed();
+        prop_oneof![
+            prop::collection::vec(element.clone(), 0..8)
+                .prop_map(|elements| format!("[{}]", elements.join(","))),
+            // keys are generated as a map so that they are unique (and not escaped, so that they stay
+            // unique once decoded)
+            prop::collection::btree_map(prop::collection::vec(any::<char>(), 0..6), element, 0..8)
+                .prop_map(|members| {
+                    let members: Vec<String> = members.iter()
+                        .map(|(key, value)| {
+                            let key_chars: Vec<(char, bool)> = key.iter()
+                                .map(|&c| (c, false))
+                                .collect();
+                            format!("{}:{}", string_literal(&key_chars), value)
+                        })
+                        .collect();
+                    format!("{{{}}}", members.join(","))
+                }),
+        ]
+    })
+}
+
+
+fn lenient_verifier() -> Verifier {
+    Verifier::new()
+        .allow_comments(true)
+        .allow_single_quotes(true)
+        .allow_unquoted_keys(true)
+        .allow_leading_plus(true)
+        .allow_json5_numbers(true)
+        .allow_unicode_whitespace(true)
+}
+
+
+proptest! {
+    #[test]
+    fn valid_json_is_accepted((before, document, after) in (whitespace(), json(), whitespace())) {
+        let document = format!("{}{}{}", before, document, after);
+        prop_assert!(verify_detailed(document.as_bytes()).is_ok(), "rejected {:?}", document);
+        prop_assert!(lenient_verifier().verify(document.as_bytes()).is_ok(), "lenient rejected {:?}", document);
+    }
+
+    #[test]
+    fn truncated_json_does_not_panic(document in json(), cut in any::<prop::sample::Index>()) {
+        let bytes = document.as_bytes();
+        let truncated = &bytes[..cut.index(bytes.len() + 1)];
+        let _ = verify_detailed(truncated);
+        let _ = lenient_verifier().verify(truncated);
+        let _ = Verifier::new().verify_collecting(truncated, 10);
+    }
+
+    #[test]
+    fn random_bytes_do_not_panic(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+        let _ = verify_detailed(&bytes[..]);
+        let _ = lenient_verifier().verify(&bytes[..]);
+        let _ = Verifier::new().verify_collecting(&bytes[..], 10);
+    }
+
+    #[test]
+    fn random_json_bytes_do_not_panic(bytes in prop::collection::vec(prop::sample::select(b"{}[],:\"\\-+.0123456789eEtrufalsn /*u \xEF\xBB\xBF\xF0\x9F\x98".to_vec()), 0..128)) {
+        // bytes from which JSON is made are far more likely to reach the corners of the parser
+        let _ = verify_detailed(&bytes[..]);
+        let _ = lenient_verifier().verify(&bytes[..]);
+        let _ = Verifier::new().verify_collecting(&bytes[..], 10);
+    }
+}